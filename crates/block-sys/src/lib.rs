@@ -47,7 +47,7 @@ pub struct Class {
     #[cfg(any(feature = "apple", feature = "compiler-rt"))]
     _priv: [*mut c_void; 32],
 
-    #[cfg(any(feature = "gnustep-1-7", feature = "objfw"))]
+    #[cfg(any(feature = "gnustep-1-7", feature = "unstable-objfw"))]
     // The size of this is unknown
     _priv: [u8; 0],
 
@@ -67,7 +67,7 @@ pub const BLOCK_REFCOUNT_MASK: block_flags = if cfg!(feature = "gnustep-1-7") {
     // Mask for the reference count in byref structure's flags field. The low
     // 3 bytes are reserved for the reference count, the top byte for the flags.
     0x00ffffff
-} else if cfg!(any(feature = "compiler-rt", feature = "objfw")) {
+} else if cfg!(any(feature = "compiler-rt", feature = "unstable-objfw")) {
     0xffff
 } else if cfg!(feature = "apple") {
     0xfffe // runtime
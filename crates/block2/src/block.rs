@@ -36,7 +36,7 @@ macro_rules! block_args_impl {
                 ($($a,)*): Self,
             ) -> R {
                 // Very similar to `MessageArguments::__invoke`
-                let invoke: unsafe extern "C" fn(*mut Block<Self, R> $(, $t)*) -> R = unsafe {
+                let invoke: objc2::__extern_fn!((unsafe) fn(*mut Block<Self, R> $(, $t)*) -> R) = unsafe {
                     mem::transmute(invoke)
                 };
 
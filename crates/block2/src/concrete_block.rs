@@ -49,17 +49,19 @@ macro_rules! concrete_block_impl {
             type Output = R;
 
             fn __into_concrete_block(self) -> ConcreteBlock<($($t,)*), R, X> {
-                extern "C" fn $f<$($t,)* R, X>(
-                    block: &ConcreteBlock<($($t,)*), R, X>,
-                    $($a: $t,)*
-                ) -> R
-                where
-                    X: Fn($($t,)*) -> R,
-                {
-                    (block.closure)($($a),*)
+                objc2::__extern_fn! {
+                    () fn $f<$($t,)* R, X>(
+                        block: &ConcreteBlock<($($t,)*), R, X>,
+                        $($a: $t,)*
+                    ) -> R
+                    where
+                        X: Fn($($t,)*) -> R,
+                    {
+                        objc2::__macro_helpers::panic_to_exception(|| (block.closure)($($a),*))
+                    }
                 }
 
-                let f: extern "C" fn(&ConcreteBlock<($($t,)*), R, X>, $($a: $t,)*) -> R = $f;
+                let f: objc2::__extern_fn!(() fn(&ConcreteBlock<($($t,)*), R, X>, $($a: $t,)*) -> R) = $f;
                 let f: unsafe extern "C" fn() = unsafe { mem::transmute(f) };
                 unsafe { ConcreteBlock::with_invoke(f, self) }
             }
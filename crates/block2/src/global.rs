@@ -173,10 +173,12 @@ macro_rules! global_block {
             let mut layout = $crate::GlobalBlock::<($($t,)*) $(, $r)?>::__DEFAULT_LAYOUT;
             layout.isa = &$crate::ffi::_NSConcreteGlobalBlock;
             layout.invoke = ::core::option::Option::Some({
-                unsafe extern "C" fn inner(_: *mut $crate::ffi::Block_layout, $($a: $t),*) $(-> $r)? {
-                    $body
+                $crate::__extern_fn! {
+                    (unsafe) fn inner(_: *mut $crate::ffi::Block_layout, $($a: $t),*) $(-> $r)? {
+                        $crate::__panic_to_exception(move || $body)
+                    }
                 }
-                let inner: unsafe extern "C" fn(*mut $crate::ffi::Block_layout, $($a: $t),*) $(-> $r)? = inner;
+                let inner: $crate::__extern_fn!((unsafe) fn(*mut $crate::ffi::Block_layout, $($a: $t),*) $(-> $r)?) = inner;
 
                 // TODO: SAFETY
                 ::core::mem::transmute(inner)
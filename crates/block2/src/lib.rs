@@ -107,3 +107,8 @@ pub use block::{Block, BlockArguments};
 pub use concrete_block::{ConcreteBlock, IntoConcreteBlock};
 pub use global::GlobalBlock;
 pub use rc_block::RcBlock;
+
+#[doc(hidden)]
+pub use objc2::__extern_fn;
+#[doc(hidden)]
+pub use objc2::__macro_helpers::panic_to_exception as __panic_to_exception;
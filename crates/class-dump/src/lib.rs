@@ -0,0 +1,810 @@
+//! Generate `objc2` bindings from the classes and protocols loaded into the
+//! Objective-C runtime.
+//!
+//! This is useful for classes that don't have any headers available, such as
+//! private classes or classes loaded from plugins. Since the runtime only
+//! knows the type encodings, the output is a best-effort starting point:
+//! every method is marked `unsafe`, objects are typed as [`Object`], and
+//! methods whose types can't be represented are emitted as comments.
+//!
+//! The generated code refers to superclasses and adopted protocols by name,
+//! so these must either be dumped as well, or be imported manually (except
+//! for `NSObject`, which maps to the types in `objc2::runtime`).
+//!
+//! [`Object`]: objc2::runtime::Object
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use objc2::encode::EncodingBox;
+use objc2::runtime::{Class, Protocol, Sel};
+
+const HEADER: &str = "\
+// This file was generated by `class-dump` from the Objective-C runtime.
+#![allow(non_snake_case, non_camel_case_types, unused_imports)]
+
+use std::ffi::c_void;
+use std::os::raw::c_char;
+
+use objc2::encode::{Encode, Encoding, RefEncode};
+use objc2::rc::{Allocated, Id};
+use objc2::runtime::{Class, NSObject, NSObjectProtocol, Object, Sel};
+use objc2::{extern_class, extern_methods, extern_protocol, ClassType, ProtocolType};
+";
+
+/// Methods that are handled by `objc2` itself, and hence shouldn't be bound.
+const SKIPPED_SELECTORS: &[&str] = &[
+    "dealloc",
+    "retain",
+    "release",
+    "autorelease",
+    "retainCount",
+    "_tryRetain",
+    "_isDeallocating",
+    "allowsWeakReference",
+    "retainWeakReference",
+];
+
+// https://doc.rust-lang.org/reference/keywords.html
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Where a type is used, since that affects how objects are represented.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Position {
+    Argument,
+    Return,
+    Inner,
+}
+
+/// Whether a method is an instance or a class method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum MethodKind {
+    Instance,
+    Class,
+}
+
+/// A type-encoded method, either from a class or a protocol.
+struct MethodInfo {
+    sel: Sel,
+    kind: MethodKind,
+    /// The return type and argument types, or the reason why they couldn't
+    /// be parsed.
+    encodings: Result<(EncodingBox, Vec<EncodingBox>), String>,
+    /// `Some(is_optional)` if the method is declared in a protocol.
+    protocol: Option<bool>,
+    /// An extra comment, e.g. for properties.
+    comment: Option<String>,
+}
+
+/// Generator for `objc2` bindings.
+///
+/// Add the items to generate bindings for with [`add_class`] and
+/// [`add_protocol`], and retrieve the generated source code with
+/// [`finish`].
+///
+/// [`add_class`]: Self::add_class
+/// [`add_protocol`]: Self::add_protocol
+/// [`finish`]: Self::finish
+#[derive(Debug, Default)]
+pub struct Generator {
+    items: Vec<String>,
+    /// The struct definitions referenced by the items, and their fields.
+    ///
+    /// Structs that are only used behind pointers don't need to know their
+    /// fields, and are emitted as opaque types.
+    structs: BTreeMap<String, Option<Vec<String>>>,
+    added: HashSet<String>,
+}
+
+impl Generator {
+    /// Creates a new, empty generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates an `extern_class!` and `extern_methods!` declaration for
+    /// the given class.
+    ///
+    /// Root classes are not supported, since `extern_class!` requires a
+    /// superclass.
+    pub fn add_class(&mut self, cls: &Class) {
+        if !self.added.insert(format!("class {}", cls.name())) {
+            return;
+        }
+        let mut out = String::new();
+        let name = rust_ident(cls.name());
+
+        let superclass = match cls.superclass() {
+            Some(superclass) => superclass,
+            None => {
+                writeln!(out, "// Skipped root class {}", cls.name()).unwrap();
+                self.items.push(out);
+                return;
+            }
+        };
+
+        writeln!(out, "extern_class!(").unwrap();
+        writeln!(out, "    #[derive(Debug, PartialEq, Eq, Hash)]").unwrap();
+        writeln!(out, "    pub struct {name};").unwrap();
+        writeln!(out).unwrap();
+        for ivar in &*cls.instance_variables() {
+            let ty = EncodingBox::from_str(ivar.type_encoding())
+                .ok()
+                .and_then(|enc| self.ty(&enc, Position::Inner));
+            match ty {
+                Some(ty) => writeln!(out, "    // ivar {}: {ty}", ivar.name()).unwrap(),
+                None => writeln!(
+                    out,
+                    "    // ivar {}: `{}`",
+                    ivar.name(),
+                    ivar.type_encoding()
+                )
+                .unwrap(),
+            }
+        }
+        writeln!(out, "    unsafe impl ClassType for {name} {{").unwrap();
+        let mut ancestors = Vec::new();
+        let mut ancestor = superclass.superclass();
+        while let Some(cls) = ancestor {
+            ancestors.push(rust_ident(cls.name()));
+            ancestor = cls.superclass();
+        }
+        if !ancestors.is_empty() {
+            writeln!(out, "        #[inherits({})]", ancestors.join(", ")).unwrap();
+        }
+        writeln!(
+            out,
+            "        type Super = {};",
+            rust_ident(superclass.name())
+        )
+        .unwrap();
+        if name != cls.name() {
+            writeln!(out, "        const NAME: &'static str = {:?};", cls.name()).unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, ");").unwrap();
+
+        for proto in &*cls.adopted_protocols() {
+            writeln!(out).unwrap();
+            writeln!(out, "unsafe impl {} for {name} {{}}", protocol_ident(proto)).unwrap();
+        }
+
+        // Properties are also visible as methods, so we only use them to
+        // annotate those methods.
+        let mut properties = HashMap::new();
+        for (property, kind) in cls
+            .properties()
+            .iter()
+            .map(|p| (p, MethodKind::Instance))
+            .chain(
+                cls.metaclass()
+                    .properties()
+                    .iter()
+                    .map(|p| (p, MethodKind::Class)),
+            )
+        {
+            let comment = format!(
+                "Property `{}`: `{}`",
+                property.name(),
+                property.attributes_str()
+            );
+            if let Ok(attributes) = property.attributes() {
+                properties.insert((attributes.getter(), kind), comment.clone());
+                if let Some(setter) = attributes.setter() {
+                    properties.insert((setter, kind), comment);
+                }
+            }
+        }
+
+        let mut methods: Vec<_> = cls
+            .instance_methods()
+            .iter()
+            .map(|m| (m, MethodKind::Instance))
+            .chain(cls.class_methods().iter().map(|m| (m, MethodKind::Class)))
+            .map(|(method, kind)| {
+                let sel = method.name();
+                MethodInfo {
+                    sel,
+                    kind,
+                    encodings: method_encodings(&method.return_type(), |i| {
+                        method.argument_type(i + 2).map(|s| s.to_string())
+                    }),
+                    protocol: None,
+                    comment: properties.get(&(sel, kind)).cloned(),
+                }
+            })
+            .collect();
+        sort_methods(&mut methods);
+
+        let methods = self.methods(cls.name(), &methods);
+        if !methods.is_empty() {
+            writeln!(out).unwrap();
+            writeln!(out, "extern_methods!(").unwrap();
+            writeln!(out, "    unsafe impl {name} {{").unwrap();
+            out.push_str(&methods);
+            writeln!(out, "    }}").unwrap();
+            writeln!(out, ");").unwrap();
+        }
+
+        self.items.push(out);
+    }
+
+    /// Generates an `extern_protocol!` declaration for the given protocol.
+    ///
+    /// The `NSObject` protocol is skipped, since it is already available as
+    /// `NSObjectProtocol`.
+    pub fn add_protocol(&mut self, proto: &Protocol) {
+        if proto.name() == "NSObject" || !self.added.insert(format!("protocol {}", proto.name())) {
+            return;
+        }
+        let mut out = String::new();
+        let name = rust_ident(proto.name());
+
+        let conforms_to: Vec<_> = proto
+            .adopted_protocols()
+            .iter()
+            .map(|p| protocol_ident(p))
+            .collect();
+
+        writeln!(out, "extern_protocol!(").unwrap();
+        if conforms_to.is_empty() {
+            writeln!(out, "    pub unsafe trait {name} {{").unwrap();
+        } else {
+            writeln!(
+                out,
+                "    pub unsafe trait {name}: {} {{",
+                conforms_to.join(" + ")
+            )
+            .unwrap();
+        }
+
+        let mut methods = Vec::new();
+        for (kind, instance) in [(MethodKind::Instance, true), (MethodKind::Class, false)] {
+            for required in [true, false] {
+                for desc in proto.method_descriptions(required, instance) {
+                    methods.push(MethodInfo {
+                        sel: desc.sel(),
+                        kind,
+                        encodings: (|| {
+                            let ret = desc.return_type().map_err(|e| e.to_string())?;
+                            let args = desc.argument_types().map_err(|e| e.to_string())?;
+                            Ok((ret, args))
+                        })(),
+                        protocol: Some(!required),
+                        comment: None,
+                    });
+                }
+            }
+        }
+        sort_methods(&mut methods);
+        out.push_str(&self.methods(proto.name(), &methods));
+
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    unsafe impl ProtocolType for dyn {name} {{").unwrap();
+        if name != proto.name() {
+            writeln!(
+                out,
+                "        const NAME: &'static str = {:?};",
+                proto.name()
+            )
+            .unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, ");").unwrap();
+
+        self.items.push(out);
+    }
+
+    /// Returns the generated source code.
+    pub fn finish(self) -> String {
+        let mut out = String::from(HEADER);
+
+        for (name, fields) in &self.structs {
+            writeln!(out).unwrap();
+            writeln!(out, "#[repr(C)]").unwrap();
+            match fields {
+                Some(fields) => {
+                    writeln!(out, "#[derive(Clone, Copy, Debug)]").unwrap();
+                    writeln!(out, "pub struct {name} {{").unwrap();
+                    for (i, field) in fields.iter().enumerate() {
+                        writeln!(out, "    pub _{i}: {field},").unwrap();
+                    }
+                    writeln!(out, "}}").unwrap();
+                    writeln!(out).unwrap();
+                    writeln!(out, "unsafe impl Encode for {name} {{").unwrap();
+                    writeln!(
+                        out,
+                        "    const ENCODING: Encoding = Encoding::Struct({name:?}, &[{}]);",
+                        fields
+                            .iter()
+                            .map(|field| format!("<{field}>::ENCODING"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                    .unwrap();
+                    writeln!(out, "}}").unwrap();
+                    writeln!(out).unwrap();
+                    writeln!(out, "unsafe impl RefEncode for {name} {{").unwrap();
+                    writeln!(
+                        out,
+                        "    const ENCODING_REF: Encoding = Encoding::Pointer(&Self::ENCODING);"
+                    )
+                    .unwrap();
+                    writeln!(out, "}}").unwrap();
+                }
+                None => {
+                    writeln!(out, "pub struct {name} {{").unwrap();
+                    writeln!(out, "    _priv: [u8; 0],").unwrap();
+                    writeln!(out, "}}").unwrap();
+                    writeln!(out).unwrap();
+                    writeln!(out, "unsafe impl RefEncode for {name} {{").unwrap();
+                    writeln!(
+                        out,
+                        "    const ENCODING_REF: Encoding = Encoding::Pointer(&Encoding::Struct({name:?}, &[]));"
+                    )
+                    .unwrap();
+                    writeln!(out, "}}").unwrap();
+                }
+            }
+        }
+
+        for item in &self.items {
+            writeln!(out).unwrap();
+            out.push_str(item);
+        }
+
+        out
+    }
+
+    /// Generate the methods inside an `extern_methods!` or
+    /// `extern_protocol!` declaration.
+    fn methods(&mut self, owner: &str, methods: &[MethodInfo]) -> String {
+        let mut out = String::new();
+        let mut fn_names = HashSet::new();
+
+        for method in methods {
+            let selector = method.sel.name();
+            let prefix = match method.kind {
+                MethodKind::Instance => '-',
+                MethodKind::Class => '+',
+            };
+            if SKIPPED_SELECTORS.contains(&selector)
+                || selector.starts_with('.')
+                || in_selector_family(selector, "alloc")
+            {
+                continue;
+            }
+
+            if !out.is_empty() {
+                writeln!(out).unwrap();
+            }
+            if let Some(comment) = &method.comment {
+                writeln!(out, "        // {comment}").unwrap();
+            }
+            match self.method(method) {
+                Ok((attribute, signature)) => {
+                    let mut fn_name =
+                        handle_reserved(&selector.trim_end_matches(':').replace(':', "_"));
+                    if method.kind == MethodKind::Class && fn_names.contains(&fn_name) {
+                        fn_name.push_str("_class");
+                    }
+                    while !fn_names.insert(fn_name.clone()) {
+                        fn_name.push('_');
+                    }
+
+                    if method.protocol == Some(true) {
+                        writeln!(out, "        #[optional]").unwrap();
+                    }
+                    writeln!(out, "        {attribute}").unwrap();
+                    let vis = if method.protocol.is_some() {
+                        ""
+                    } else {
+                        "pub "
+                    };
+                    writeln!(out, "        {vis}unsafe fn {fn_name}{signature};").unwrap();
+                }
+                Err(reason) => {
+                    writeln!(
+                        out,
+                        "        // Skipped {prefix}[{owner} {selector}]: {reason}"
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Returns the method attribute and the signature of the method.
+    fn method(&mut self, method: &MethodInfo) -> Result<(String, String), String> {
+        let selector = method.sel.name();
+        let (ret, args) = method.encodings.clone()?;
+
+        let is_init = method.kind == MethodKind::Instance && in_selector_family(selector, "init");
+        let is_new = method.kind == MethodKind::Class && in_selector_family(selector, "new");
+
+        let mut params = Vec::new();
+        if is_init {
+            params.push("this: Option<Allocated<Self>>".to_string());
+        } else if method.kind == MethodKind::Instance {
+            params.push("&self".to_string());
+        }
+
+        let mut param_names = HashSet::new();
+        let parts = selector.split(':');
+        for (i, (arg, part)) in args.iter().zip(parts).enumerate() {
+            let ty = self
+                .ty(arg, Position::Argument)
+                .ok_or_else(|| format!("unsupported argument type `{arg}`"))?;
+            let mut param = handle_reserved(&to_snake_case(part));
+            if param.is_empty() || !param_names.insert(param.clone()) {
+                param = format!("arg{i}");
+            }
+            params.push(format!("{param}: {ty}"));
+        }
+
+        let (attribute, ret) = match &ret {
+            EncodingBox::Void => ("method", String::new()),
+            EncodingBox::Object if is_init || is_new => {
+                ("method_id", " -> Option<Id<Self>>".into())
+            }
+            _ if is_init => return Err("initializer must return an object".into()),
+            ret => {
+                let ty = self
+                    .ty(ret, Position::Return)
+                    .ok_or_else(|| format!("unsupported return type `{ret}`"))?;
+                let attribute = if *ret == EncodingBox::Object {
+                    "method_id"
+                } else {
+                    "method"
+                };
+                (attribute, format!(" -> {ty}"))
+            }
+        };
+
+        Ok((
+            format!("#[{attribute}({selector})]"),
+            format!("({}){ret}", params.join(", ")),
+        ))
+    }
+
+    /// Convert an encoding to a Rust type, or [`None`] if the type can't be
+    /// represented.
+    ///
+    /// Primitives are rendered with [`EncodingBox::rust_type`], objects,
+    /// classes and structs are handled specially.
+    fn ty(&mut self, enc: &EncodingBox, pos: Position) -> Option<String> {
+        Some(match enc {
+            EncodingBox::Object => match pos {
+                Position::Argument => "Option<&Object>".into(),
+                Position::Return => "Option<Id<Object>>".into(),
+                Position::Inner => "*mut Object".into(),
+            },
+            EncodingBox::Class => match pos {
+                Position::Argument => "Option<&Class>".into(),
+                Position::Return => "Option<&'static Class>".into(),
+                Position::Inner => "*const Class".into(),
+            },
+            EncodingBox::Pointer(pointee) => match &**pointee {
+                EncodingBox::Void => enc.rust_type().to_string(),
+                EncodingBox::Struct(name, fields) => {
+                    match self.struct_ty(name, fields.as_deref()) {
+                        Some(name) => format!("*mut {name}"),
+                        None => format!("*mut {}", self.opaque_struct_ty(name)?),
+                    }
+                }
+                pointee => format!("*mut {}", self.ty(pointee, Position::Inner)?),
+            },
+            EncodingBox::Array(len, item) => {
+                format!("[{}; {len}]", self.ty(item, Position::Inner)?)
+            }
+            EncodingBox::Struct(name, fields) => self.struct_ty(name, fields.as_deref())?,
+            EncodingBox::Char
+            | EncodingBox::Short
+            | EncodingBox::Int
+            | EncodingBox::Long
+            | EncodingBox::LongLong
+            | EncodingBox::UChar
+            | EncodingBox::UShort
+            | EncodingBox::UInt
+            | EncodingBox::ULong
+            | EncodingBox::ULongLong
+            | EncodingBox::Float
+            | EncodingBox::Double
+            | EncodingBox::Bool
+            | EncodingBox::String
+            | EncodingBox::Sel => enc.rust_type().to_string(),
+            // Blocks, function pointers, unions, bitfields and so on are
+            // not (yet) supported.
+            _ => return None,
+        })
+    }
+
+    fn struct_ty(&mut self, name: &str, fields: Option<&[EncodingBox]>) -> Option<String> {
+        if !is_ident(name) {
+            return None;
+        }
+        if let Some(Some(_)) = self.structs.get(name) {
+            return Some(name.to_string());
+        }
+        let fields = fields?;
+
+        // Insert a placeholder first, in case the struct refers to itself.
+        let existing = self.structs.insert(name.to_string(), None);
+        let field_types: Option<Vec<_>> = fields
+            .iter()
+            .map(|field| self.ty(field, Position::Inner))
+            .collect();
+        match field_types {
+            Some(field_types) => {
+                self.structs.insert(name.to_string(), Some(field_types));
+                Some(name.to_string())
+            }
+            None => {
+                if existing.is_none() {
+                    self.structs.remove(name);
+                }
+                None
+            }
+        }
+    }
+
+    fn opaque_struct_ty(&mut self, name: &str) -> Option<String> {
+        if !is_ident(name) {
+            return None;
+        }
+        self.structs.entry(name.to_string()).or_insert(None);
+        Some(name.to_string())
+    }
+}
+
+fn method_encodings(
+    ret: &str,
+    arg: impl Fn(usize) -> Option<String>,
+) -> Result<(EncodingBox, Vec<EncodingBox>), String> {
+    let ret = EncodingBox::from_str(ret).map_err(|e| e.to_string())?;
+    let mut args = Vec::new();
+    while let Some(s) = arg(args.len()) {
+        args.push(EncodingBox::from_str(&s).map_err(|e| e.to_string())?);
+    }
+    Ok((ret, args))
+}
+
+/// Instance methods first, then class methods, each sorted by selector.
+fn sort_methods(methods: &mut [MethodInfo]) {
+    fn key(m: &MethodInfo) -> (bool, Option<bool>, &str) {
+        (m.kind == MethodKind::Class, m.protocol, m.sel.name())
+    }
+    methods.sort_by(|a, b| key(a).cmp(&key(b)));
+}
+
+/// The name of the Rust trait that represents the protocol.
+fn protocol_ident(proto: &Protocol) -> String {
+    match proto.name() {
+        "NSObject" => "NSObjectProtocol".into(),
+        name => rust_ident(name),
+    }
+}
+
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+}
+
+/// Convert an Objective-C name to a valid Rust identifier.
+fn rust_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !is_ident(&ident) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn handle_reserved(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{name}_"),
+        name if KEYWORDS.contains(&name) => format!("r#{name}"),
+        name => name.to_string(),
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = matches!(chars.get(i + 1), Some(c) if c.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// <https://clang.llvm.org/docs/AutomaticReferenceCounting.html#arc-method-families>
+fn in_selector_family(selector: &str, family: &str) -> bool {
+    match selector.trim_start_matches('_').strip_prefix(family) {
+        Some(rest) => !rest.starts_with(|c: char| c.is_ascii_lowercase()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use objc2::declare::{ClassBuilder, ProtocolBuilder};
+    use objc2::encode::{Encode, Encoding};
+    use objc2::runtime::{NSObject, Object};
+    use objc2::{sel, ClassType};
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    #[allow(dead_code)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    unsafe impl Encode for Point {
+        const ENCODING: Encoding =
+            Encoding::Struct("ClassDumpPoint", &[f64::ENCODING, f64::ENCODING]);
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(to_snake_case("addNumber"), "add_number");
+        assert_eq!(to_snake_case("URLString"), "url_string");
+        assert_eq!(to_snake_case("withUTF8String"), "with_utf8_string");
+        assert_eq!(to_snake_case("type"), "type");
+        assert_eq!(handle_reserved("type"), "r#type");
+        assert_eq!(handle_reserved("self"), "self_");
+    }
+
+    #[test]
+    fn test_selector_family() {
+        assert!(in_selector_family("init", "init"));
+        assert!(in_selector_family("initWithFoo:", "init"));
+        assert!(in_selector_family("_init", "init"));
+        assert!(!in_selector_family("initialize", "init"));
+        assert!(!in_selector_family("newThing", "init"));
+    }
+
+    #[test]
+    fn test_types() {
+        let mut generator = Generator::new();
+        let mut ty = |s: &str, pos| generator.ty(&EncodingBox::from_str(s).unwrap(), pos);
+
+        assert_eq!(ty("I", Position::Argument).as_deref(), Some("u32"));
+        assert_eq!(
+            ty("@", Position::Argument).as_deref(),
+            Some("Option<&Object>")
+        );
+        assert_eq!(
+            ty("@", Position::Return).as_deref(),
+            Some("Option<Id<Object>>")
+        );
+        assert_eq!(
+            ty("^@", Position::Argument).as_deref(),
+            Some("*mut *mut Object")
+        );
+        assert_eq!(ty("^v", Position::Argument).as_deref(), Some("*mut c_void"));
+        assert_eq!(ty("[16C]", Position::Inner).as_deref(), Some("[u8; 16]"));
+        assert_eq!(ty("{Foo=ii}", Position::Argument).as_deref(), Some("Foo"));
+        assert_eq!(
+            ty("^{Bar}", Position::Argument).as_deref(),
+            Some("*mut Bar")
+        );
+        assert_eq!(ty("{Bar}", Position::Argument), None);
+        assert_eq!(ty("@?", Position::Argument), None);
+        assert_eq!(ty("(Union=ii)", Position::Argument), None);
+
+        let out = generator.finish();
+        assert!(out.contains("pub struct Foo {\n    pub _0: i32,\n    pub _1: i32,\n}"));
+        assert!(out.contains(
+            "const ENCODING: Encoding = Encoding::Struct(\"Foo\", &[<i32>::ENCODING, <i32>::ENCODING]);"
+        ));
+        assert!(out.contains("pub struct Bar {\n    _priv: [u8; 0],\n}"));
+    }
+
+    #[test]
+    fn test_dump_class() {
+        extern "C" fn foo(_this: &Object, _cmd: Sel) -> u32 {
+            42
+        }
+        extern "C" fn set_foo(_this: &Object, _cmd: Sel, _foo: u32) {}
+        extern "C" fn object(_this: &Object, _cmd: Sel, _obj: *mut Object) -> *mut Object {
+            std::ptr::null_mut()
+        }
+        extern "C" fn point(_this: &Object, _cmd: Sel) -> Point {
+            Point { x: 0.0, y: 0.0 }
+        }
+        extern "C" fn init_with_foo(this: &mut Object, _cmd: Sel, _foo: u32) -> *mut Object {
+            this
+        }
+        extern "C" fn add(_cls: &Class, _cmd: Sel, a: i32, b: i32) -> i32 {
+            a + b
+        }
+
+        let mut builder = ClassBuilder::new("ClassDumpTestObject", NSObject::class()).unwrap();
+        unsafe {
+            builder.add_method(sel!(foo), foo as extern "C" fn(_, _) -> _);
+            builder.add_method(sel!(setFoo:), set_foo as extern "C" fn(_, _, _));
+            builder.add_method(sel!(objectFor:), object as extern "C" fn(_, _, _) -> _);
+            builder.add_method(sel!(point), point as extern "C" fn(_, _) -> _);
+            builder.add_method(
+                sel!(initWithFoo:),
+                init_with_foo as extern "C" fn(_, _, _) -> _,
+            );
+            builder.add_class_method(
+                sel!(addNumber:toNumber:),
+                add as extern "C" fn(_, _, _, _) -> _,
+            );
+        }
+        builder.add_ivar::<u8>("_bar");
+        let cls = builder.register();
+
+        let mut generator = Generator::new();
+        generator.add_class(cls);
+        let out = generator.finish();
+
+        let expected = [
+            "    pub struct ClassDumpTestObject;\n",
+            "    // ivar _bar: u8\n",
+            "        type Super = NSObject;\n",
+            "        #[method(foo)]\n        pub unsafe fn foo(&self) -> u32;\n",
+            "        #[method(setFoo:)]\n        pub unsafe fn setFoo(&self, set_foo: u32);\n",
+            "        #[method_id(objectFor:)]\n        pub unsafe fn objectFor(&self, object_for: Option<&Object>) -> Option<Id<Object>>;\n",
+            "        #[method(point)]\n        pub unsafe fn point(&self) -> ClassDumpPoint;\n",
+            "        #[method_id(initWithFoo:)]\n        pub unsafe fn initWithFoo(this: Option<Allocated<Self>>, init_with_foo: u32) -> Option<Id<Self>>;\n",
+            "        #[method(addNumber:toNumber:)]\n        pub unsafe fn addNumber_toNumber(add_number: i32, to_number: i32) -> i32;\n",
+            "pub struct ClassDumpPoint {\n    pub _0: f64,\n    pub _1: f64,\n}\n",
+        ];
+        for expected in expected {
+            assert!(out.contains(expected), "{expected:?} not found in:\n{out}");
+        }
+    }
+
+    #[test]
+    fn test_dump_protocol() {
+        let mut builder = ProtocolBuilder::new("ClassDumpTestProtocol").unwrap();
+        builder.add_method_description::<(i32,), ()>(sel!(setBar:), true);
+        builder.add_method_description::<(), f32>(sel!(bar), false);
+        builder.add_class_method_description::<(), u64>(sel!(bar), true);
+        let proto = builder.register();
+
+        let mut generator = Generator::new();
+        generator.add_protocol(proto);
+        let out = generator.finish();
+
+        let expected = [
+            "    pub unsafe trait ClassDumpTestProtocol {\n",
+            "        #[method(setBar:)]\n        unsafe fn setBar(&self, set_bar: i32);\n",
+            "        #[optional]\n        #[method(bar)]\n        unsafe fn bar(&self) -> f32;\n",
+            "        #[method(bar)]\n        unsafe fn bar_class() -> u64;\n",
+            "    unsafe impl ProtocolType for dyn ClassDumpTestProtocol {\n",
+        ];
+        for expected in expected {
+            assert!(out.contains(expected), "{expected:?} not found in:\n{out}");
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Dump the classes and protocols loaded into the Objective-C runtime as
+//! `objc2` bindings.
+//!
+//! Run with `--help` to see the available options.
+#[cfg(any(feature = "apple", all(feature = "gnustep-2-0", unix)))]
+use std::ffi::CString;
+use std::process::ExitCode;
+
+use class_dump::Generator;
+use objc2::runtime::{Class, Protocol};
+
+const USAGE: &str = "\
+Usage: class-dump [OPTIONS] [CLASS]...
+
+Print `objc2` bindings for the given classes to stdout.
+
+Options:
+  --protocol <NAME>       Also dump the protocol with the given name
+  --subclasses-of <NAME>  Also dump all (direct and indirect) subclasses
+  --image <PATH>          Also dump all classes in the given image
+                          (Apple and GNUStep 2.0+ only)
+  --all                   Dump all loaded classes
+  -h, --help              Print this help
+";
+
+fn main() -> ExitCode {
+    let mut generator = Generator::new();
+    let mut args = std::env::args().skip(1);
+    let mut dumped_any = false;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {name}"))
+        };
+        let res: Result<(), String> = match &*arg {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "--all" => {
+                let mut classes: Vec<_> = Class::classes().iter().copied().collect();
+                classes.sort_by_key(|cls| cls.name());
+                for cls in classes {
+                    generator.add_class(cls);
+                }
+                Ok(())
+            }
+            "--protocol" => value("--protocol").and_then(|name| {
+                let proto = Protocol::get(&name).ok_or(format!("protocol {name} not found"))?;
+                generator.add_protocol(proto);
+                Ok(())
+            }),
+            "--subclasses-of" => value("--subclasses-of").and_then(|name| {
+                let cls = Class::get(&name).ok_or(format!("class {name} not found"))?;
+                let mut classes = cls.all_subclasses();
+                classes.sort_by_key(|cls| cls.name());
+                for cls in classes {
+                    generator.add_class(cls);
+                }
+                Ok(())
+            }),
+            #[cfg(any(feature = "apple", all(feature = "gnustep-2-0", unix)))]
+            "--image" => value("--image").and_then(|image| {
+                let image = CString::new(image).map_err(|_| "invalid image path".to_string())?;
+                let mut classes = Class::classes_in_image(&image);
+                classes.sort_by_key(|cls| cls.name());
+                for cls in classes {
+                    generator.add_class(cls);
+                }
+                Ok(())
+            }),
+            name if name.starts_with('-') => Err(format!("unknown option {name}")),
+            name => Class::get(name)
+                .map(|cls| generator.add_class(cls))
+                .ok_or(format!("class {name} not found")),
+        };
+
+        if let Err(err) = res {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+        dumped_any = true;
+    }
+
+    if !dumped_any {
+        eprint!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    print!("{}", generator.finish());
+    ExitCode::SUCCESS
+}
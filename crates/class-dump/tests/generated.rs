@@ -0,0 +1,119 @@
+//! Test that the generated bindings are up to date, that they compile, and
+//! that they can be used to call the dumped methods.
+//!
+//! Run with `TEST_OVERWRITE=1` to update `generated/bindings.rs`.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use class_dump::Generator;
+use objc2::declare::{ClassBuilder, ProtocolBuilder};
+use objc2::encode::{Encode, Encoding};
+use objc2::rc::Id;
+use objc2::runtime::{Class, NSObject, Object, Protocol, Sel};
+use objc2::{msg_send_id, sel, ClassType};
+
+#[rustfmt::skip]
+#[allow(dead_code, clippy::missing_safety_doc)]
+#[path = "generated/bindings.rs"]
+mod bindings;
+
+use bindings::{ClassDumpCompilePoint, ClassDumpCompileTest};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+unsafe impl Encode for Point {
+    const ENCODING: Encoding =
+        Encoding::Struct("ClassDumpCompilePoint", &[f64::ENCODING, f64::ENCODING]);
+}
+
+fn register() {
+    extern "C" fn foo(_this: &Object, _cmd: Sel) -> u32 {
+        42
+    }
+    extern "C" fn set_foo(_this: &Object, _cmd: Sel, _foo: u32) {}
+    extern "C" fn object(_this: &Object, _cmd: Sel, obj: *mut Object) -> *mut Object {
+        obj
+    }
+    extern "C" fn point(_this: &Object, _cmd: Sel, factor: f64) -> Point {
+        Point {
+            x: factor,
+            y: 2.0 * factor,
+        }
+    }
+    extern "C" fn init_with_foo(this: &mut Object, _cmd: Sel, _foo: u32) -> *mut Object {
+        this
+    }
+    extern "C" fn add(_cls: &Class, _cmd: Sel, a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    let mut builder = ProtocolBuilder::new("ClassDumpCompileProtocol").unwrap();
+    builder.add_method_description::<(i32,), ()>(sel!(setBar:), true);
+    builder.add_method_description::<(), f32>(sel!(bar), false);
+    builder.add_class_method_description::<(), u64>(sel!(bar), true);
+    let proto = builder.register();
+
+    let mut builder = ClassBuilder::new("ClassDumpCompileTest", NSObject::class()).unwrap();
+    unsafe {
+        builder.add_method(sel!(foo), foo as extern "C" fn(_, _) -> _);
+        builder.add_method(sel!(setFoo:), set_foo as extern "C" fn(_, _, _));
+        builder.add_method(sel!(objectFor:), object as extern "C" fn(_, _, _) -> _);
+        builder.add_method(sel!(pointWithFactor:), point as extern "C" fn(_, _, _) -> _);
+        builder.add_method(
+            sel!(initWithFoo:),
+            init_with_foo as extern "C" fn(_, _, _) -> _,
+        );
+        builder.add_class_method(
+            sel!(addNumber:toNumber:),
+            add as extern "C" fn(_, _, _, _) -> _,
+        );
+    }
+    builder.add_ivar::<u8>("_bar");
+    builder.add_protocol(proto);
+    builder.register();
+}
+
+#[test]
+fn test_generated_bindings() {
+    register();
+
+    let mut generator = Generator::new();
+    generator.add_class(Class::get("ClassDumpCompileTest").unwrap());
+    generator.add_protocol(Protocol::get("ClassDumpCompileProtocol").unwrap());
+    let actual = generator.finish();
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/generated/bindings.rs");
+    if env::var("TEST_OVERWRITE").as_deref() == Ok("1") {
+        fs::write(&path, actual).unwrap();
+    } else {
+        let expected = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            expected, actual,
+            "the generated bindings are out of date, run with TEST_OVERWRITE=1"
+        );
+    }
+
+    let obj: Id<ClassDumpCompileTest> = unsafe { msg_send_id![ClassDumpCompileTest::class(), new] };
+    assert_eq!(unsafe { obj.foo() }, 42);
+    unsafe { obj.setFoo(7) };
+
+    let point: ClassDumpCompilePoint = unsafe { obj.pointWithFactor(1.5) };
+    assert_eq!(point._0, 1.5);
+    assert_eq!(point._1, 3.0);
+
+    let obj_ref: &Object = &obj;
+    let res = unsafe { obj.objectFor(Some(obj_ref)) }.unwrap();
+    assert!(std::ptr::eq(&*res, obj_ref));
+    assert!(unsafe { obj.objectFor(None) }.is_none());
+
+    let obj = unsafe { ClassDumpCompileTest::initWithFoo(ClassDumpCompileTest::alloc(), 3) };
+    assert!(obj.is_some());
+
+    assert_eq!(unsafe { ClassDumpCompileTest::addNumber_toNumber(2, 3) }, 5);
+}
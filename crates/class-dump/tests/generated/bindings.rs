@@ -0,0 +1,76 @@
+// This file was generated by `class-dump` from the Objective-C runtime.
+#![allow(non_snake_case, non_camel_case_types, unused_imports)]
+
+use std::ffi::c_void;
+use std::os::raw::c_char;
+
+use objc2::encode::{Encode, Encoding, RefEncode};
+use objc2::rc::{Allocated, Id};
+use objc2::runtime::{Class, NSObject, NSObjectProtocol, Object, Sel};
+use objc2::{extern_class, extern_methods, extern_protocol, ClassType, ProtocolType};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ClassDumpCompilePoint {
+    pub _0: f64,
+    pub _1: f64,
+}
+
+unsafe impl Encode for ClassDumpCompilePoint {
+    const ENCODING: Encoding = Encoding::Struct("ClassDumpCompilePoint", &[<f64>::ENCODING, <f64>::ENCODING]);
+}
+
+unsafe impl RefEncode for ClassDumpCompilePoint {
+    const ENCODING_REF: Encoding = Encoding::Pointer(&Self::ENCODING);
+}
+
+extern_class!(
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    pub struct ClassDumpCompileTest;
+
+    // ivar _bar: u8
+    unsafe impl ClassType for ClassDumpCompileTest {
+        type Super = NSObject;
+    }
+);
+
+unsafe impl ClassDumpCompileProtocol for ClassDumpCompileTest {}
+
+extern_methods!(
+    unsafe impl ClassDumpCompileTest {
+        #[method(foo)]
+        pub unsafe fn foo(&self) -> u32;
+
+        #[method_id(initWithFoo:)]
+        pub unsafe fn initWithFoo(this: Option<Allocated<Self>>, init_with_foo: u32) -> Option<Id<Self>>;
+
+        #[method_id(objectFor:)]
+        pub unsafe fn objectFor(&self, object_for: Option<&Object>) -> Option<Id<Object>>;
+
+        #[method(pointWithFactor:)]
+        pub unsafe fn pointWithFactor(&self, point_with_factor: f64) -> ClassDumpCompilePoint;
+
+        #[method(setFoo:)]
+        pub unsafe fn setFoo(&self, set_foo: u32);
+
+        #[method(addNumber:toNumber:)]
+        pub unsafe fn addNumber_toNumber(add_number: i32, to_number: i32) -> i32;
+    }
+);
+
+extern_protocol!(
+    pub unsafe trait ClassDumpCompileProtocol {
+        #[method(setBar:)]
+        unsafe fn setBar(&self, set_bar: i32);
+
+        #[optional]
+        #[method(bar)]
+        unsafe fn bar(&self) -> f32;
+
+        #[method(bar)]
+        unsafe fn bar_class() -> u64;
+    }
+
+    unsafe impl ProtocolType for dyn ClassDumpCompileProtocol {
+    }
+);
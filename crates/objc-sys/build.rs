@@ -41,7 +41,6 @@ enum Runtime {
     Apple(AppleRuntime),
     GNUStep(u8, u8),
     WinObjC,
-    #[allow(dead_code)]
     ObjFW(Option<String>),
 }
 use Runtime::*;
@@ -137,11 +136,7 @@ fn main() {
                 GNUStep(1, 7)
             }
         }
-        (false, false, true) => {
-            // For now
-            unimplemented!("ObjFW is not yet supported")
-            // ObjFW(None)
-        }
+        (false, false, true) => ObjFW(None),
         (false, false, false) => panic!("Must specify the desired runtime (using cargo features)."),
         _ => panic!("Invalid feature combination; only one runtime may be selected!"),
     };
@@ -155,6 +150,7 @@ fn main() {
     };
     println!("cargo:rustc-cfg={runtime_cfg}");
 
+    let mut apple_new = false;
     if let Apple(runtime) = &runtime {
         // A few things are defined differently depending on the __OBJC2__
         // variable, which is set for all platforms except 32-bit macOS.
@@ -162,8 +158,10 @@ fn main() {
             println!("cargo:rustc-cfg=apple_old");
         } else {
             println!("cargo:rustc-cfg=apple_new");
+            apple_new = true;
         }
     }
+    println!("cargo:apple_new={apple_new}"); // DEP_OBJC_[version]_APPLE_NEW
 
     let clang_runtime = match &runtime {
         Apple(runtime) => {
@@ -226,6 +224,8 @@ fn main() {
     if let Runtime::ObjFW(_) = &runtime {
         // Link to libobjfw-rt
         println!("cargo:rustc-link-lib=dylib=objfw-rt");
+        // And to libobjfw, which contains the autorelease pool functions
+        println!("cargo:rustc-link-lib=dylib=objfw");
     } else {
         // Link to libobjc
         println!("cargo:rustc-link-lib=dylib=objc");
@@ -1,8 +1,9 @@
 use std::os::raw::{c_char, c_int, c_uint};
 
+use crate::{objc_ivar, objc_method, objc_property, objc_protocol, objc_selector};
 #[cfg(any(doc, not(objfw)))]
-use crate::{objc_ivar, objc_method, objc_object, objc_property, objc_property_attribute_t};
-use crate::{objc_protocol, objc_selector, OpaqueData, BOOL, IMP};
+use crate::{objc_object, objc_property_attribute_t};
+use crate::{OpaqueData, BOOL, IMP};
 
 /// An opaque type that represents an Objective-C class.
 #[repr(C)]
@@ -30,7 +31,6 @@ extern_c_unwind! {
         cls: *const objc_class,
         name: *const objc_selector,
     ) -> *const objc_method;
-    #[cfg(any(doc, not(objfw)))] // Available in newer versions
     pub fn class_getInstanceMethod(
         cls: *const objc_class,
         name: *const objc_selector,
@@ -97,17 +97,14 @@ extern_c! {
     pub fn class_conformsToProtocol(cls: *const objc_class, protocol: *const objc_protocol)
         -> BOOL;
 
-    #[cfg(any(doc, not(objfw)))] // Available in newer versions
     pub fn class_copyIvarList(
         cls: *const objc_class,
         out_len: *mut c_uint,
     ) -> *mut *const objc_ivar;
-    #[cfg(any(doc, not(objfw)))] // Available in newer versions
     pub fn class_copyMethodList(
         cls: *const objc_class,
         out_len: *mut c_uint,
     ) -> *mut *const objc_method;
-    #[cfg(any(doc, not(objfw)))] // Available in newer versions
     pub fn class_copyPropertyList(
         cls: *const objc_class,
         out_len: *mut c_uint,
@@ -2,6 +2,7 @@
 //! Apple: `objc-exception.h`
 //! GNUStep: `eh_personality.c`, which is a bit brittle to rely on, but I
 //!   think it's fine...
+#[cfg(any(doc, not(objfw), feature = "unstable-exception"))]
 use core::ffi::c_void;
 #[cfg(any(doc, apple_new))]
 use std::os::raw::c_int;
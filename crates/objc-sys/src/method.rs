@@ -41,13 +41,11 @@ extern_c! {
     pub fn method_getDescription(m: *const objc_method) -> *const objc_method_description;
     #[cfg(any(doc, not(objfw)))]
     pub fn method_getImplementation(method: *const objc_method) -> IMP;
-    #[cfg(any(doc, not(objfw)))]
     pub fn method_getName(method: *const objc_method) -> *const objc_selector;
     #[cfg(any(doc, not(objfw)))]
     pub fn method_getNumberOfArguments(method: *const objc_method) -> c_uint;
     #[cfg(any(doc, not(objfw)))]
     pub fn method_getReturnType(method: *const objc_method, dst: *mut c_char, dst_len: usize);
-    #[cfg(any(doc, not(objfw)))]
     pub fn method_getTypeEncoding(method: *const objc_method) -> *const c_char;
     #[cfg(any(doc, not(objfw)))]
     pub fn method_setImplementation(method: *const objc_method, imp: IMP) -> IMP;
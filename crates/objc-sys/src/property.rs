@@ -29,13 +29,11 @@ extern_c! {
         property: *const objc_property,
         out_len: *mut c_uint,
     ) -> *mut objc_property_attribute_t;
-    #[cfg(any(doc, not(objfw)))]
     pub fn property_copyAttributeValue(
         property: *const objc_property,
         attribute_name: *const c_char,
     ) -> *mut c_char;
     #[cfg(any(doc, not(objfw)))]
     pub fn property_getAttributes(property: *const objc_property) -> *const c_char;
-    #[cfg(any(doc, not(objfw)))]
     pub fn property_getName(property: *const objc_property) -> *const c_char;
 }
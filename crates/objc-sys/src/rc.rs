@@ -18,11 +18,10 @@ use crate::objc_object;
 // `retain`, `release`, `autorelease` or `dealloc` do.
 extern_c_unwind! {
     // Autoreleasepool
-    // ObjFW: Defined in `autorelease.h`, not available with libobjfw-rt!
+    // ObjFW: Defined in `autorelease.h`, not available with libobjfw-rt, so
+    // we link to libobjfw as well in the build script.
 
-    #[cfg(any(doc, not(objfw)))]
     pub fn objc_autoreleasePoolPop(pool: *mut c_void);
-    #[cfg(any(doc, not(objfw)))]
     pub fn objc_autoreleasePoolPush() -> *mut c_void;
 
     // Autorelease
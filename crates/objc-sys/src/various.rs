@@ -1,13 +1,12 @@
 use core::ffi::c_void;
-#[cfg(any(doc, not(objfw)))]
 use std::os::raw::c_char;
 use std::os::raw::c_int;
 #[cfg(any(doc, apple))]
 use std::os::raw::c_uint;
 
 #[cfg(any(doc, not(objfw)))]
-use crate::{objc_AssociationPolicy, BOOL};
-use crate::{objc_object, OpaqueData};
+use crate::BOOL;
+use crate::{objc_AssociationPolicy, objc_object, OpaqueData};
 
 /// An opaque type that represents an instance variable.
 #[repr(C)]
@@ -50,11 +49,8 @@ extern_c! {
     #[cfg(any(doc, not(objfw)))]
     pub fn imp_removeBlock(imp: IMP) -> BOOL;
 
-    #[cfg(any(doc, not(objfw)))]
     pub fn ivar_getName(ivar: *const objc_ivar) -> *const c_char;
-    #[cfg(any(doc, not(objfw)))]
     pub fn ivar_getOffset(ivar: *const objc_ivar) -> isize;
-    #[cfg(any(doc, not(objfw)))]
     pub fn ivar_getTypeEncoding(ivar: *const objc_ivar) -> *const c_char;
 
     #[cfg(any(doc, apple))]
@@ -70,19 +66,16 @@ extern_c! {
         handler: Option<unsafe extern "C" fn(obj: *mut objc_object)>,
     );
 
-    #[cfg(any(doc, not(objfw)))]
     pub fn objc_getAssociatedObject(
         object: *const objc_object,
         key: *const c_void,
     ) -> *const objc_object;
-    #[cfg(any(doc, not(objfw)))]
     pub fn objc_setAssociatedObject(
         object: *mut objc_object,
         key: *const c_void,
         value: *mut objc_object,
         policy: objc_AssociationPolicy,
     );
-    #[cfg(any(doc, not(objfw)))]
     pub fn objc_removeAssociatedObjects(object: *mut objc_object);
 
     #[cfg(any(doc, apple, objfw))]
@@ -1,7 +1,9 @@
 use core::fmt;
 
 use crate::helper::{compare_encodings, Helper, NestingLevel};
+use crate::layout::{size_and_align, Target};
 use crate::parse::Parser;
+use crate::render::{CType, RustType};
 use crate::EncodingBox;
 
 /// An Objective-C type-encoding.
@@ -224,6 +226,91 @@ impl Encoding {
     pub fn equivalent_to_box(&self, other: &EncodingBox) -> bool {
         compare_encodings(self, NestingLevel::new(), other, NestingLevel::new(), false)
     }
+
+    /// The size and alignment of a value with this encoding on the given
+    /// target, in bytes.
+    ///
+    /// This is the equivalent of `NSGetSizeAndAlignment`, and can be used to
+    /// allocate buffers for values whose type is only known at runtime.
+    ///
+    /// Returns `None` if the encoding does not have a known size, for
+    /// example because it is [`Void`][Self::Void] or
+    /// [`Unknown`][Self::Unknown], or because it is a struct containing
+    /// bitfields whose type and offset have not been specified (as is the
+    /// case with Apple's runtime).
+    ///
+    /// Note that [`Long`][Self::Long] is always assumed to be 32 bits wide,
+    /// see [`Encoding::C_LONG`].
+    ///
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use objc2_encode::{Encoding, Target};
+    ///
+    /// let enc = Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]);
+    /// assert_eq!(enc.size_and_align(Target::CURRENT), Some((16, 8)));
+    ///
+    /// assert_eq!(Encoding::Void.size_and_align(Target::CURRENT), None);
+    /// ```
+    pub fn size_and_align(&self, target: Target) -> Option<(usize, usize)> {
+        size_and_align(self, &target)
+    }
+
+    /// Display the encoding as a Rust type.
+    ///
+    /// This is mostly useful for diagnostics and code generation. Primitives
+    /// are displayed as the Rust type that has the same encoding in `objc2`
+    /// (e.g. `i32`, `*mut Object` or `Sel`), while structs and unions are
+    /// displayed by their name. Placeholders like `_` are used for types that
+    /// cannot be described in Rust, such as bitfields.
+    ///
+    /// The output is not guaranteed to be a valid Rust type, and may change
+    /// between versions.
+    ///
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use objc2_encode::Encoding;
+    ///
+    /// assert_eq!(Encoding::Pointer(&Encoding::Object).rust_type().to_string(), "*mut *mut Object");
+    /// assert_eq!(Encoding::Array(16, &Encoding::UChar).rust_type().to_string(), "[u8; 16]");
+    ///
+    /// let enc = Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]);
+    /// assert_eq!(enc.rust_type().to_string(), "CGPoint");
+    /// ```
+    pub fn rust_type(&self) -> impl fmt::Display + '_ {
+        RustType(self)
+    }
+
+    /// Display the encoding as a C type.
+    ///
+    /// If the encoding is a struct or union, the fields are included in the
+    /// output (with placeholder names), otherwise they are referred to by
+    /// name.
+    ///
+    /// The output may change between versions.
+    ///
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use objc2_encode::Encoding;
+    ///
+    /// assert_eq!(Encoding::Pointer(&Encoding::String).c_type().to_string(), "char **");
+    /// assert_eq!(
+    ///     Encoding::Pointer(&Encoding::Array(4, &Encoding::Int)).c_type().to_string(),
+    ///     "int (*)[4]",
+    /// );
+    ///
+    /// const POINT: Encoding = Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]);
+    /// assert_eq!(POINT.c_type().to_string(), "struct CGPoint { double field0; double field1; }");
+    /// assert_eq!(Encoding::Pointer(&POINT).c_type().to_string(), "struct CGPoint *");
+    /// ```
+    pub fn c_type(&self) -> impl fmt::Display + '_ {
+        CType(self)
+    }
 }
 
 /// Formats this [`Encoding`] in a similar way that the `@encode` directive
@@ -5,7 +5,9 @@ use core::fmt;
 use core::str::FromStr;
 
 use crate::helper::{compare_encodings, Helper, NestingLevel};
+use crate::layout::{size_and_align, Target};
 use crate::parse::{ParseError, Parser};
+use crate::render::{CType, RustType};
 use crate::Encoding;
 
 /// The boxed version of [`Encoding`].
@@ -125,6 +127,28 @@ impl EncodingBox {
             }
         }
     }
+
+    /// The size and alignment of a value with this encoding on the given
+    /// target, in bytes.
+    ///
+    /// See [`Encoding::size_and_align`] for details.
+    pub fn size_and_align(&self, target: Target) -> Option<(usize, usize)> {
+        size_and_align(self, &target)
+    }
+
+    /// Display the encoding as a Rust type.
+    ///
+    /// See [`Encoding::rust_type`] for details.
+    pub fn rust_type(&self) -> impl fmt::Display + '_ {
+        RustType(self)
+    }
+
+    /// Display the encoding as a C type.
+    ///
+    /// See [`Encoding::c_type`] for details.
+    pub fn c_type(&self) -> impl fmt::Display + '_ {
+        CType(self)
+    }
 }
 
 /// Same formatting as [`Encoding`]'s `Display` implementation.
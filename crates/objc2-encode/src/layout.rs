@@ -0,0 +1,329 @@
+use core::mem;
+
+use crate::helper::{
+    ContainerKind, EncodingType, Helper, IndirectionKind, NestingLevel, Primitive,
+};
+
+/// The parts of a target's C ABI that are needed to compute the size and
+/// alignment of an encoding.
+///
+/// Use [`Target::CURRENT`] for the target that the code is compiled for.
+///
+/// See [`Encoding::size_and_align`] for details.
+///
+/// [`Encoding::size_and_align`]: crate::Encoding::size_and_align
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Target {
+    pointer_size: usize,
+    long_long_align: usize,
+    double_align: usize,
+    long_double_size: usize,
+    long_double_align: usize,
+}
+
+impl Target {
+    /// The target that the code is currently compiled for.
+    pub const CURRENT: Self = {
+        let (long_double_size, long_double_align) = if cfg!(target_arch = "x86_64") {
+            if cfg!(windows) {
+                (8, 8)
+            } else {
+                (16, 16)
+            }
+        } else if cfg!(target_arch = "x86") {
+            if cfg!(windows) {
+                (8, 8)
+            } else if cfg!(target_vendor = "apple") {
+                (16, 16)
+            } else {
+                (12, 4)
+            }
+        } else if cfg!(target_arch = "aarch64") {
+            if cfg!(any(windows, target_vendor = "apple")) {
+                (8, 8)
+            } else {
+                (16, 16)
+            }
+        } else {
+            (8, 8)
+        };
+
+        Self {
+            pointer_size: mem::size_of::<*const u8>(),
+            // Rust uses the same alignment as C for these
+            long_long_align: mem::align_of::<i64>(),
+            double_align: mem::align_of::<f64>(),
+            long_double_size,
+            long_double_align,
+        }
+    };
+
+    /// 64-bit Apple platforms on x86, i.e. macOS on Intel and the iOS
+    /// simulator on Intel.
+    pub const APPLE_X86_64: Self = Self {
+        pointer_size: 8,
+        long_long_align: 8,
+        double_align: 8,
+        long_double_size: 16,
+        long_double_align: 16,
+    };
+
+    /// 64-bit Apple platforms on ARM, i.e. macOS on Apple Silicon and iOS.
+    pub const APPLE_AARCH64: Self = Self {
+        pointer_size: 8,
+        long_long_align: 8,
+        double_align: 8,
+        long_double_size: 8,
+        long_double_align: 8,
+    };
+
+    /// 32-bit x86 Linux (e.g. GNUStep on `i686-unknown-linux-gnu`).
+    pub const LINUX_X86: Self = Self {
+        pointer_size: 4,
+        long_long_align: 4,
+        double_align: 4,
+        long_double_size: 12,
+        long_double_align: 4,
+    };
+
+    /// The size of a pointer on the target.
+    pub const fn pointer_size(&self) -> usize {
+        self.pointer_size
+    }
+}
+
+const fn round_up(size: usize, align: usize) -> Option<usize> {
+    match size.checked_add(align - 1) {
+        Some(size) => Some(size / align * align),
+        None => None,
+    }
+}
+
+const fn primitive_size_and_align(primitive: Primitive, target: &Target) -> Option<(usize, usize)> {
+    use Primitive::*;
+    let ptr = target.pointer_size;
+    Some(match primitive {
+        Char | UChar | Bool => (1, 1),
+        Short | UShort => (2, 2),
+        // `Long` is only used when `long` is 32 bits wide, see
+        // `Encoding::C_LONG`.
+        Int | UInt | Long | ULong => (4, 4),
+        LongLong | ULongLong => (8, target.long_long_align),
+        Float => (4, 4),
+        Double => (8, target.double_align),
+        LongDouble => (target.long_double_size, target.long_double_align),
+        FloatComplex => (8, 4),
+        DoubleComplex => (16, target.double_align),
+        LongDoubleComplex => (2 * target.long_double_size, target.long_double_align),
+        String | Object | Block | Class | Sel => (ptr, ptr),
+        // Incomplete types (`Unknown` is usually a function)
+        Void | Unknown => return None,
+    })
+}
+
+pub(crate) fn size_and_align<E: EncodingType>(enc: &E, target: &Target) -> Option<(usize, usize)> {
+    // Always include the fields of structs and unions, since they're needed
+    // to compute the layout.
+    match enc.helper(NestingLevel::new()) {
+        Helper::Primitive(primitive) => primitive_size_and_align(primitive, target),
+        // Bitfields only have a layout inside structs
+        Helper::BitField(_, _, _) => None,
+        Helper::Indirection(IndirectionKind::Pointer, _, _) => {
+            Some((target.pointer_size, target.pointer_size))
+        }
+        Helper::Indirection(IndirectionKind::Atomic, t, _) => {
+            let (size, align) = size_and_align(t, target)?;
+            // Atomic types that can be handled natively are aligned to their
+            // size, like in clang.
+            if size.is_power_of_two() && size <= 16 && align < size {
+                Some((size, size))
+            } else {
+                Some((size, align))
+            }
+        }
+        Helper::Array(len, item, _) => {
+            let (size, align) = size_and_align(item, target)?;
+            let len = usize::try_from(len).ok()?;
+            Some((size.checked_mul(len)?, align))
+        }
+        Helper::Container(kind, _, items, _) => {
+            let items = items?;
+            let mut align = 1;
+            // In bits, since bitfields need not end on a byte boundary.
+            let mut end = 0;
+            for item in items {
+                match item.helper(NestingLevel::new()) {
+                    // The type and bit offset is only available on GNUStep;
+                    // without them, we can't know where the bitfield is
+                    // placed, or how it affects the alignment.
+                    Helper::BitField(_, None, _) => return None,
+                    Helper::BitField(bits, Some((offset, t)), _) => {
+                        let (_, item_align) = size_and_align(t, target)?;
+                        align = align.max(item_align);
+                        let offset = usize::try_from(*offset).ok()?;
+                        end = end.max(offset.checked_add(bits as usize)?);
+                    }
+                    _ => {
+                        let (item_size, item_align) = size_and_align(item, target)?;
+                        align = align.max(item_align);
+                        let offset = match kind {
+                            ContainerKind::Struct => round_up(round_up(end, 8)? / 8, item_align)?,
+                            ContainerKind::Union => 0,
+                        };
+                        end = end.max(offset.checked_add(item_size)?.checked_mul(8)?);
+                    }
+                }
+            }
+            Some((round_up(round_up(end, 8)? / 8, align)?, align))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, EncodingBox};
+
+    const TARGETS: &[Target] = &[
+        Target::CURRENT,
+        Target::APPLE_X86_64,
+        Target::APPLE_AARCH64,
+        Target::LINUX_X86,
+    ];
+
+    #[test]
+    fn test_current() {
+        let target = Target::CURRENT;
+        let check = |enc: Encoding, size: usize, align: usize| {
+            assert_eq!(enc.size_and_align(target), Some((size, align)), "{enc}");
+        };
+        check(Encoding::Char, 1, 1);
+        check(Encoding::Int, 4, 4);
+        check(
+            Encoding::LongLong,
+            mem::size_of::<i64>(),
+            mem::align_of::<i64>(),
+        );
+        check(
+            Encoding::Double,
+            mem::size_of::<f64>(),
+            mem::align_of::<f64>(),
+        );
+        check(
+            Encoding::Object,
+            mem::size_of::<usize>(),
+            mem::align_of::<usize>(),
+        );
+        check(
+            Encoding::Pointer(&Encoding::Void),
+            mem::size_of::<usize>(),
+            mem::align_of::<usize>(),
+        );
+
+        #[repr(C)]
+        struct Foo {
+            a: u8,
+            b: f64,
+            c: [u16; 3],
+            d: *const u8,
+        }
+        const FOO: Encoding = Encoding::Struct(
+            "Foo",
+            &[
+                Encoding::UChar,
+                Encoding::Double,
+                Encoding::Array(3, &Encoding::UShort),
+                Encoding::String,
+            ],
+        );
+        check(FOO, mem::size_of::<Foo>(), mem::align_of::<Foo>());
+    }
+
+    #[test]
+    fn test_incomplete() {
+        for target in TARGETS {
+            let target = *target;
+            assert_eq!(Encoding::Void.size_and_align(target), None);
+            assert_eq!(Encoding::Unknown.size_and_align(target), None);
+            assert_eq!(Encoding::BitField(2, None).size_and_align(target), None);
+            assert_eq!(
+                Encoding::Array(2, &Encoding::Unknown).size_and_align(target),
+                None
+            );
+            assert_eq!(
+                Encoding::Struct("a", &[Encoding::BitField(2, None)]).size_and_align(target),
+                None
+            );
+            // But pointers to incomplete types are fine
+            assert_eq!(
+                Encoding::Pointer(&Encoding::Unknown).size_and_align(target),
+                Some((target.pointer_size(), target.pointer_size()))
+            );
+            let enc = EncodingBox::Struct("a".into(), None);
+            assert_eq!(enc.size_and_align(target), None);
+        }
+    }
+
+    #[test]
+    fn test_targets() {
+        let enc = Encoding::Struct(
+            "a",
+            &[Encoding::Char, Encoding::LongLong, Encoding::LongDouble],
+        );
+        assert_eq!(enc.size_and_align(Target::APPLE_X86_64), Some((32, 16)));
+        assert_eq!(enc.size_and_align(Target::APPLE_AARCH64), Some((24, 8)));
+        assert_eq!(enc.size_and_align(Target::LINUX_X86), Some((24, 4)));
+
+        let enc = Encoding::Array(3, &Encoding::Pointer(&Encoding::Int));
+        assert_eq!(enc.size_and_align(Target::APPLE_AARCH64), Some((24, 8)));
+        assert_eq!(enc.size_and_align(Target::LINUX_X86), Some((12, 4)));
+    }
+
+    #[test]
+    fn test_union() {
+        let enc = Encoding::Union("u", &[Encoding::Char, Encoding::Array(5, &Encoding::Short)]);
+        for target in TARGETS {
+            assert_eq!(enc.size_and_align(*target), Some((10, 2)));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let enc = Encoding::Struct("empty", &[]);
+        assert_eq!(enc.size_and_align(Target::CURRENT), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_atomic() {
+        let enc = Encoding::Atomic(&Encoding::LongLong);
+        assert_eq!(enc.size_and_align(Target::LINUX_X86), Some((8, 8)));
+        let enc = Encoding::Atomic(&Encoding::Array(3, &Encoding::Char));
+        assert_eq!(enc.size_and_align(Target::LINUX_X86), Some((3, 1)));
+    }
+
+    #[test]
+    fn test_bitfield() {
+        // struct {
+        //     char a;
+        //     int b : 3;
+        //     int c : 10;
+        //     char d;
+        // }
+        let enc = Encoding::Struct(
+            "bitfield",
+            &[
+                Encoding::Char,
+                Encoding::BitField(3, Some(&(8, Encoding::Int))),
+                Encoding::BitField(10, Some(&(11, Encoding::Int))),
+                Encoding::Char,
+            ],
+        );
+        for target in TARGETS {
+            assert_eq!(enc.size_and_align(*target), Some((4, 4)));
+        }
+
+        let s = "{bitfield=cb8i3b11i10c}";
+        let enc: EncodingBox = s.parse().unwrap();
+        assert_eq!(enc.size_and_align(Target::CURRENT), Some((4, 4)));
+    }
+}
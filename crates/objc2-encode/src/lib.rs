@@ -5,7 +5,9 @@
 //!
 //! This crate provides the [`Encoding`] type to describe and compare these
 //! type-encodings, and the [`EncodingBox`] type which does the same, except
-//! it can be parsed from an encoding at runtime.
+//! it can be parsed from an encoding at runtime. Full method signatures, as
+//! returned by e.g. `method_getTypeEncoding`, can be parsed with
+//! [`MethodEncoding`].
 //!
 //! The types from this crate is exported under the [`objc2`] crate as
 //! `objc2::encode`, so usually you would just use it from there.
@@ -63,7 +65,10 @@ extern crate alloc;
 mod encoding;
 mod encoding_box;
 mod helper;
+mod layout;
+mod method_encoding;
 mod parse;
+mod render;
 
 // Will be used at some point when generic constants are available
 #[allow(dead_code)]
@@ -71,4 +76,6 @@ mod static_str;
 
 pub use self::encoding::Encoding;
 pub use self::encoding_box::EncodingBox;
+pub use self::layout::Target;
+pub use self::method_encoding::{MethodArgument, MethodEncoding, Qualifier};
 pub use self::parse::ParseError;
@@ -0,0 +1,375 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::parse::{ErrorKind, ParseError, Parser};
+use crate::EncodingBox;
+
+/// A type qualifier that may be placed in front of the encoding of a method
+/// argument or return type.
+///
+/// These are mostly relevant for Distributed Objects, but are still emitted
+/// by compilers, for example `r` is used for `const`-qualified pointers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Qualifier {
+    /// `r`, the value is `const`.
+    Const,
+    /// `n`, the argument is only used as input.
+    In,
+    /// `N`, the argument is used as both input and output.
+    InOut,
+    /// `o`, the argument is only used as output.
+    Out,
+    /// `O`, the value is copied when sent over Distributed Objects.
+    Bycopy,
+    /// `R`, the value is sent by reference over Distributed Objects.
+    Byref,
+    /// `V`, the method is asynchronous.
+    Oneway,
+}
+
+impl Qualifier {
+    pub(crate) const fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            b'r' => Some(Self::Const),
+            b'n' => Some(Self::In),
+            b'N' => Some(Self::InOut),
+            b'o' => Some(Self::Out),
+            b'O' => Some(Self::Bycopy),
+            b'R' => Some(Self::Byref),
+            b'V' => Some(Self::Oneway),
+            // TODO: b'|', // GCINVISIBLE
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn to_byte(self) -> u8 {
+        match self {
+            Self::Const => b'r',
+            Self::In => b'n',
+            Self::InOut => b'N',
+            Self::Out => b'o',
+            Self::Bycopy => b'O',
+            Self::Byref => b'R',
+            Self::Oneway => b'V',
+        }
+    }
+}
+
+impl fmt::Display for Qualifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_byte() as char)
+    }
+}
+
+/// The encoding of a single part of a [`MethodEncoding`], along with its
+/// qualifiers and stack offset.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MethodArgument {
+    /// The qualifiers in front of the encoding, in the order they appeared.
+    pub qualifiers: Vec<Qualifier>,
+    /// The encoding of the type.
+    pub encoding: EncodingBox,
+    /// The offset of the argument on the stack, if specified.
+    ///
+    /// For the return type, this is instead the total size of the arguments.
+    ///
+    /// Note that this is only a hint, and compilers for some platforms do not
+    /// emit it at all.
+    pub stack_offset: Option<i64>,
+}
+
+impl MethodArgument {
+    /// Create a new argument with the given encoding, and no qualifiers or
+    /// stack offset.
+    pub const fn new(encoding: EncodingBox) -> Self {
+        Self {
+            qualifiers: Vec::new(),
+            encoding,
+            stack_offset: None,
+        }
+    }
+}
+
+impl fmt::Display for MethodArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for qualifier in &self.qualifiers {
+            write!(f, "{qualifier}")?;
+        }
+        write!(f, "{}", self.encoding)?;
+        if let Some(stack_offset) = self.stack_offset {
+            write!(f, "{stack_offset}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The full type encoding of a method, as returned by e.g.
+/// `method_getTypeEncoding`.
+///
+/// This contains the return type, the implicit receiver and selector
+/// arguments, and the rest of the arguments, each with their qualifiers and
+/// stack offsets.
+///
+///
+/// # Example
+///
+/// Parse the encoding of `- (void)setValue:(const char *)value atIndex:(int)index`.
+///
+/// ```
+/// use objc2_encode::{EncodingBox, MethodEncoding, Qualifier};
+///
+/// let s = "v28@0:8r*16i24";
+/// let method: MethodEncoding = s.parse()?;
+///
+/// assert_eq!(method.return_type.encoding, EncodingBox::Void);
+/// assert_eq!(method.return_type.stack_offset, Some(28));
+/// assert_eq!(method.receiver.encoding, EncodingBox::Object);
+/// assert_eq!(method.sel.encoding, EncodingBox::Sel);
+///
+/// assert_eq!(method.arguments.len(), 2);
+/// assert_eq!(method.arguments[0].qualifiers, [Qualifier::Const]);
+/// assert_eq!(method.arguments[0].encoding, EncodingBox::String);
+/// assert_eq!(method.arguments[1].stack_offset, Some(24));
+///
+/// assert_eq!(method.to_string(), s);
+/// # Ok::<(), objc2_encode::ParseError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MethodEncoding {
+    /// The return type.
+    pub return_type: MethodArgument,
+    /// The type of the receiver, usually [`EncodingBox::Object`].
+    pub receiver: MethodArgument,
+    /// The type of the selector (`_cmd`), usually [`EncodingBox::Sel`].
+    pub sel: MethodArgument,
+    /// The types of the rest of the arguments.
+    pub arguments: Vec<MethodArgument>,
+}
+
+impl Parser<'_> {
+    fn parse_method_argument(&mut self) -> Result<MethodArgument, ErrorKind> {
+        let qualifiers = self.parse_qualifiers();
+        let encoding = self.parse_encoding()?;
+        let stack_offset = self.parse_stack_offset()?;
+        Ok(MethodArgument {
+            qualifiers,
+            encoding,
+            stack_offset,
+        })
+    }
+
+    fn parse_method_encoding(&mut self) -> Result<MethodEncoding, ErrorKind> {
+        // See also the following other approaches:
+        // objrs: https://gitlab.com/objrs/objrs/-/blob/b4f6598696b3fa622e6fddce7aff281770b0a8c2/src/test.rs
+        // libobjc2: https://github.com/gnustep/libobjc2/blob/v2.1/encoding2.c
+        // objc4: https://github.com/apple-oss-distributions/objc4/blob/objc4-841.13/runtime/objc-typeencoding.mm
+        let return_type = self.parse_method_argument()?;
+        let receiver = self.parse_method_argument()?;
+        let sel = self.parse_method_argument()?;
+        let mut arguments = Vec::new();
+        while !self.is_empty() {
+            arguments.push(self.parse_method_argument()?);
+        }
+        Ok(MethodEncoding {
+            return_type,
+            receiver,
+            sel,
+            arguments,
+        })
+    }
+}
+
+impl FromStr for MethodEncoding {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        parser
+            .parse_method_encoding()
+            .map_err(|err| ParseError::new(parser, err))
+    }
+}
+
+/// Formats the method encoding in the same way that the compiler would.
+///
+/// Parsing the output of this again gives the same [`MethodEncoding`].
+impl fmt::Display for MethodEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.return_type, self.receiver, self.sel)?;
+        for argument in &self.arguments {
+            write!(f, "{argument}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn arg(encoding: EncodingBox, stack_offset: Option<i64>) -> MethodArgument {
+        MethodArgument {
+            qualifiers: vec![],
+            encoding,
+            stack_offset,
+        }
+    }
+
+    #[track_caller]
+    fn assert_method(s: &str, expected: MethodEncoding) {
+        let actual: MethodEncoding = s.parse().unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(actual, expected);
+        assert_eq!(actual.to_string(), s);
+    }
+
+    #[test]
+    fn stack_offset() {
+        #[track_caller]
+        fn assert_stack_offset(s: &str, expected: Result<Option<i64>, ErrorKind>, rest: &str) {
+            let mut parser = Parser::new(s);
+            assert_eq!(parser.parse_stack_offset(), expected);
+            assert_eq!(parser.remaining(), rest);
+        }
+
+        assert_stack_offset("", Ok(None), "");
+        assert_stack_offset("abc", Ok(None), "abc");
+        assert_stack_offset("abc12abc", Ok(None), "abc12abc");
+        assert_stack_offset("0", Ok(Some(0)), "");
+        assert_stack_offset("1abc", Ok(Some(1)), "abc");
+        assert_stack_offset("42def24", Ok(Some(42)), "def24");
+        assert_stack_offset("1234567890xyz", Ok(Some(1234567890)), "xyz");
+
+        assert_stack_offset("-1a", Ok(Some(-1)), "a");
+        assert_stack_offset("-a", Err(ErrorKind::ExpectedInteger), "a");
+        assert_stack_offset("99999999999999999999", Err(ErrorKind::IntegerTooLarge), "");
+
+        // GNU runtime's register parameter hint??
+        assert_stack_offset("+1a", Ok(Some(1)), "a");
+    }
+
+    #[test]
+    fn parse_bitfield() {
+        assert_method(
+            "@48@0:8Ad16^*24{bitfield=b64b1}32i48",
+            MethodEncoding {
+                return_type: arg(EncodingBox::Object, Some(48)),
+                receiver: arg(EncodingBox::Object, Some(0)),
+                sel: arg(EncodingBox::Sel, Some(8)),
+                arguments: vec![
+                    arg(EncodingBox::Atomic(Box::new(EncodingBox::Double)), Some(16)),
+                    arg(
+                        EncodingBox::Pointer(Box::new(EncodingBox::String)),
+                        Some(24),
+                    ),
+                    arg(
+                        EncodingBox::Struct(
+                            "bitfield".into(),
+                            Some(vec![
+                                EncodingBox::BitField(64, None),
+                                EncodingBox::BitField(1, None),
+                            ]),
+                        ),
+                        Some(32),
+                    ),
+                    arg(EncodingBox::Int, Some(48)),
+                ],
+            },
+        );
+    }
+
+    #[test]
+    fn parse_complex() {
+        assert_method(
+            "jf16@0:8",
+            MethodEncoding {
+                return_type: arg(EncodingBox::FloatComplex, Some(16)),
+                receiver: arg(EncodingBox::Object, Some(0)),
+                sel: arg(EncodingBox::Sel, Some(8)),
+                arguments: vec![],
+            },
+        );
+        assert_method(
+            "jf@:",
+            MethodEncoding {
+                return_type: arg(EncodingBox::FloatComplex, None),
+                receiver: arg(EncodingBox::Object, None),
+                sel: arg(EncodingBox::Sel, None),
+                arguments: vec![],
+            },
+        );
+    }
+
+    #[test]
+    fn parse_qualifiers() {
+        assert_method(
+            "Vv40@0:8r^{a=i}16no^@24NRO@32",
+            MethodEncoding {
+                return_type: MethodArgument {
+                    qualifiers: vec![Qualifier::Oneway],
+                    encoding: EncodingBox::Void,
+                    stack_offset: Some(40),
+                },
+                receiver: arg(EncodingBox::Object, Some(0)),
+                sel: arg(EncodingBox::Sel, Some(8)),
+                arguments: vec![
+                    MethodArgument {
+                        qualifiers: vec![Qualifier::Const],
+                        encoding: EncodingBox::Pointer(Box::new(EncodingBox::Struct(
+                            "a".into(),
+                            Some(vec![EncodingBox::Int]),
+                        ))),
+                        stack_offset: Some(16),
+                    },
+                    MethodArgument {
+                        qualifiers: vec![Qualifier::In, Qualifier::Out],
+                        encoding: EncodingBox::Pointer(Box::new(EncodingBox::Object)),
+                        stack_offset: Some(24),
+                    },
+                    MethodArgument {
+                        qualifiers: vec![Qualifier::InOut, Qualifier::Byref, Qualifier::Bycopy],
+                        encoding: EncodingBox::Object,
+                        stack_offset: Some(32),
+                    },
+                ],
+            },
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        #[track_caller]
+        fn assert_err(s: &str, kind: ErrorKind) {
+            let mut parser = Parser::new(s);
+            assert_eq!(parser.parse_method_encoding(), Err(kind));
+        }
+
+        assert_err("", ErrorKind::UnexpectedEnd);
+        assert_err("v16", ErrorKind::UnexpectedEnd);
+        assert_err("v16@0", ErrorKind::UnexpectedEnd);
+        assert_err("v16@0:8r", ErrorKind::UnexpectedEnd);
+        assert_err("v16@0:-a", ErrorKind::ExpectedInteger);
+        assert_err("v16@0:8-1", ErrorKind::Unknown(b'-'));
+        assert_err("v16@0:8i16!", ErrorKind::Unknown(b'!'));
+    }
+
+    #[test]
+    fn new_argument() {
+        let method = MethodEncoding {
+            return_type: MethodArgument::new(EncodingBox::Void),
+            receiver: MethodArgument::new(EncodingBox::Object),
+            sel: MethodArgument::new(EncodingBox::Sel),
+            arguments: vec![MethodArgument::new(EncodingBox::C_LONG)],
+        };
+        let expected = if cfg!(any(target_pointer_width = "32", windows)) {
+            "v@:l"
+        } else {
+            "v@:q"
+        };
+        assert_eq!(method.to_string(), expected);
+    }
+}
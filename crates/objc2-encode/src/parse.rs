@@ -6,7 +6,7 @@ use alloc::vec::Vec;
 use core::fmt;
 
 use crate::helper::{ContainerKind, Helper, NestingLevel};
-use crate::{Encoding, EncodingBox};
+use crate::{Encoding, EncodingBox, Qualifier};
 
 /// Check whether a struct or union name is a valid identifier
 pub(crate) const fn verify_name(name: &str) -> bool {
@@ -168,19 +168,40 @@ impl<'a> Parser<'a> {
 impl Parser<'_> {
     /// Strip leading qualifiers, if any.
     pub(crate) fn strip_leading_qualifiers(&mut self) {
-        // TODO: Add API for accessing and outputting qualifiers.
-        const QUALIFIERS: &[u8] = &[
-            b'r', // const
-            b'n', // in
-            b'N', // inout
-            b'o', // out
-            b'O', // bycopy
-            b'R', // byref
-            b'V', // oneway
-        ];
-        // TODO: b'|', // GCINVISIBLE
-
-        self.consume_while(|b| QUALIFIERS.contains(&b));
+        self.consume_while(|b| Qualifier::from_byte(b).is_some());
+    }
+
+    /// Parse leading qualifiers, if any.
+    pub(crate) fn parse_qualifiers(&mut self) -> Vec<Qualifier> {
+        let mut qualifiers = Vec::new();
+        while let Some(qualifier) = self.try_peek().and_then(Qualifier::from_byte) {
+            self.advance();
+            qualifiers.push(qualifier);
+        }
+        qualifiers
+    }
+
+    /// Parse the stack offset that follows each type in a method encoding,
+    /// if any.
+    pub(crate) fn parse_stack_offset(&mut self) -> Result<Option<i64>> {
+        let negative = match self.try_peek() {
+            Some(b'-') => {
+                self.advance();
+                true
+            }
+            // GNU runtime's register parameter hint
+            Some(b'+') => {
+                self.advance();
+                false
+            }
+            Some(b) if b.is_ascii_digit() => false,
+            _ => return Ok(None),
+        };
+        let offset: i64 = self
+            .chomp_digits()?
+            .parse()
+            .map_err(|_| ErrorKind::IntegerTooLarge)?;
+        Ok(Some(if negative { -offset } else { offset }))
     }
 
     /// Chomp until we hit a non-digit.
@@ -0,0 +1,359 @@
+//! Rendering encodings as Rust and C types.
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+use crate::helper::{EncodingType, Helper, IndirectionKind, NestingLevel, Primitive};
+
+impl Primitive {
+    const fn rust_type(self) -> &'static str {
+        use Primitive::*;
+        match self {
+            Char => "i8",
+            Short => "i16",
+            // `l` is always 32 bits in type encodings
+            Int | Long => "i32",
+            LongLong => "i64",
+            UChar => "u8",
+            UShort => "u16",
+            UInt | ULong => "u32",
+            ULongLong => "u64",
+            Float => "f32",
+            Double => "f64",
+            // Rust has no equivalent of these
+            LongDouble => "c_longdouble",
+            FloatComplex => "Complex<f32>",
+            DoubleComplex => "Complex<f64>",
+            LongDoubleComplex => "Complex<c_longdouble>",
+            Bool => "bool",
+            Void => "()",
+            String => "*const c_char",
+            Object => "*mut Object",
+            Block => "*mut Block<_, _>",
+            Class => "*const Class",
+            Sel => "Sel",
+            Unknown => "_",
+        }
+    }
+
+    const fn rust_atomic_type(self) -> Option<&'static str> {
+        use Primitive::*;
+        Some(match self {
+            Char => "AtomicI8",
+            Short => "AtomicI16",
+            Int => "AtomicI32",
+            LongLong => "AtomicI64",
+            UChar => "AtomicU8",
+            UShort => "AtomicU16",
+            UInt => "AtomicU32",
+            ULongLong => "AtomicU64",
+            Bool => "AtomicBool",
+            String => "AtomicPtr<c_char>",
+            Object => "AtomicPtr<Object>",
+            Class => "AtomicPtr<Class>",
+            _ => return None,
+        })
+    }
+
+    const fn c_type(self) -> &'static str {
+        use Primitive::*;
+        match self {
+            Char => "char",
+            Short => "short",
+            Int => "int",
+            Long => "long",
+            LongLong => "long long",
+            UChar => "unsigned char",
+            UShort => "unsigned short",
+            UInt => "unsigned int",
+            ULong => "unsigned long",
+            ULongLong => "unsigned long long",
+            Float => "float",
+            Double => "double",
+            LongDouble => "long double",
+            FloatComplex => "float _Complex",
+            DoubleComplex => "double _Complex",
+            LongDoubleComplex => "long double _Complex",
+            Bool => "_Bool",
+            Void => "void",
+            Object => "id",
+            Class => "Class",
+            Sel => "SEL",
+            // Need a declarator, see `write_c`
+            String | Block | Unknown => unreachable!(),
+        }
+    }
+}
+
+/// Display an encoding as a Rust type.
+pub(crate) struct RustType<'a, E>(pub(crate) &'a E);
+
+impl<E: EncodingType> fmt::Display for RustType<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.helper(NestingLevel::new()) {
+            Helper::Primitive(primitive) => write!(f, "{}", primitive.rust_type()),
+            // Rust doesn't have bitfields
+            Helper::BitField(_, _, _) => write!(f, "_"),
+            Helper::Indirection(IndirectionKind::Pointer, t, _) => {
+                match t.helper(NestingLevel::new()) {
+                    Helper::Primitive(Primitive::Void) => write!(f, "*mut c_void"),
+                    _ => write!(f, "*mut {}", RustType(t)),
+                }
+            }
+            Helper::Indirection(IndirectionKind::Atomic, t, _) => {
+                match t.helper(NestingLevel::new()) {
+                    Helper::Primitive(primitive) => {
+                        if let Some(atomic) = primitive.rust_atomic_type() {
+                            return write!(f, "{atomic}");
+                        }
+                    }
+                    Helper::Indirection(IndirectionKind::Pointer, t, _) => {
+                        return match t.helper(NestingLevel::new()) {
+                            Helper::Primitive(Primitive::Void) => write!(f, "AtomicPtr<c_void>"),
+                            _ => write!(f, "AtomicPtr<{}>", RustType(t)),
+                        };
+                    }
+                    _ => {}
+                }
+                write!(f, "Atomic<{}>", RustType(t))
+            }
+            Helper::Array(len, item, _) => write!(f, "[{}; {len}]", RustType(item)),
+            Helper::Container(_, "?", _, _) => write!(f, "_"),
+            Helper::Container(_, name, _, _) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Display an encoding as a C type.
+pub(crate) struct CType<'a, E>(pub(crate) &'a E);
+
+impl<E: EncodingType> fmt::Display for CType<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_c(f, self.0, String::new(), true)
+    }
+}
+
+fn write_base(f: &mut fmt::Formatter<'_>, base: &str, declarator: &str) -> fmt::Result {
+    if declarator.is_empty() || declarator.starts_with('[') {
+        write!(f, "{base}{declarator}")
+    } else {
+        write!(f, "{base} {declarator}")
+    }
+}
+
+/// Write the type with the given declarator, e.g. `int` with the declarator
+/// `(*)[4]` is written as `int (*)[4]`.
+///
+/// The fields of structs and unions are only written when `with_fields` is
+/// set, or when the container is anonymous (since it can't be referred to by
+/// name).
+fn write_c<E: EncodingType>(
+    f: &mut fmt::Formatter<'_>,
+    enc: &E,
+    declarator: String,
+    with_fields: bool,
+) -> fmt::Result {
+    match enc.helper(NestingLevel::new()) {
+        Helper::Primitive(Primitive::String) => write_base(f, "char", &format!("*{declarator}")),
+        Helper::Primitive(Primitive::Block) => write!(f, "void (^{declarator})()"),
+        // Usually a function
+        Helper::Primitive(Primitive::Unknown) => write!(f, "void {declarator}()"),
+        Helper::Primitive(primitive) => write_base(f, primitive.c_type(), &declarator),
+        Helper::BitField(size, t, _) => {
+            if let Some((_, t)) = t {
+                write_c(f, t, declarator, false)?;
+            } else {
+                write_base(f, "unsigned int", &declarator)?;
+            }
+            write!(f, " : {size}")
+        }
+        Helper::Indirection(IndirectionKind::Pointer, t, _) => {
+            let declarator = match t.helper(NestingLevel::new()) {
+                // Pointers to arrays and functions need parentheses
+                Helper::Array(_, _, _) | Helper::Primitive(Primitive::Unknown) => {
+                    format!("(*{declarator})")
+                }
+                _ => format!("*{declarator}"),
+            };
+            write_c(f, t, declarator, false)
+        }
+        Helper::Indirection(IndirectionKind::Atomic, t, _) => {
+            write!(f, "_Atomic(")?;
+            write_c(f, t, String::new(), false)?;
+            write!(f, ")")?;
+            write_base(f, "", &declarator)
+        }
+        Helper::Array(len, item, _) => write_c(f, item, format!("{declarator}[{len}]"), false),
+        Helper::Container(kind, name, items, _) => {
+            write!(f, "{kind}")?;
+            if name != "?" {
+                write!(f, " {name}")?;
+            }
+            match items {
+                Some(items) if with_fields || name == "?" => {
+                    write!(f, " {{")?;
+                    for (i, item) in items.iter().enumerate() {
+                        write!(f, " ")?;
+                        write_c(f, item, format!("field{i}"), false)?;
+                        write!(f, ";")?;
+                    }
+                    write!(f, " }}")?;
+                }
+                _ => {}
+            }
+            write_base(f, "", &declarator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    use crate::{Encoding, EncodingBox};
+
+    #[track_caller]
+    fn assert_render(enc: &Encoding, rust: &str, c: &str) {
+        assert_eq!(enc.rust_type().to_string(), rust);
+        assert_eq!(enc.c_type().to_string(), c);
+
+        let enc: EncodingBox = enc.to_string().parse().unwrap();
+        assert_eq!(enc.rust_type().to_string(), rust);
+        assert_eq!(enc.c_type().to_string(), c);
+    }
+
+    #[test]
+    fn primitives() {
+        assert_render(&Encoding::Char, "i8", "char");
+        assert_render(&Encoding::ULongLong, "u64", "unsigned long long");
+        assert_render(&Encoding::Long, "i32", "long");
+        assert_render(&Encoding::ULong, "u32", "unsigned long");
+        assert_render(&Encoding::Double, "f64", "double");
+        assert_render(&Encoding::DoubleComplex, "Complex<f64>", "double _Complex");
+        assert_render(&Encoding::Bool, "bool", "_Bool");
+        assert_render(&Encoding::Void, "()", "void");
+        assert_render(&Encoding::String, "*const c_char", "char *");
+        assert_render(&Encoding::Object, "*mut Object", "id");
+        assert_render(&Encoding::Block, "*mut Block<_, _>", "void (^)()");
+        assert_render(&Encoding::Class, "*const Class", "Class");
+        assert_render(&Encoding::Sel, "Sel", "SEL");
+    }
+
+    #[test]
+    fn indirection() {
+        assert_render(&Encoding::Pointer(&Encoding::Void), "*mut c_void", "void *");
+        assert_render(
+            &Encoding::Pointer(&Encoding::Pointer(&Encoding::Int)),
+            "*mut *mut i32",
+            "int **",
+        );
+        assert_render(
+            &Encoding::Pointer(&Encoding::Unknown),
+            "*mut _",
+            "void (*)()",
+        );
+        assert_render(
+            &Encoding::Pointer(&Encoding::Block),
+            "*mut *mut Block<_, _>",
+            "void (^*)()",
+        );
+        assert_render(
+            &Encoding::Atomic(&Encoding::Int),
+            "AtomicI32",
+            "_Atomic(int)",
+        );
+        assert_render(
+            &Encoding::Atomic(&Encoding::Pointer(&Encoding::Void)),
+            "AtomicPtr<c_void>",
+            "_Atomic(void *)",
+        );
+        assert_render(
+            &Encoding::Atomic(&Encoding::String),
+            "AtomicPtr<c_char>",
+            "_Atomic(char *)",
+        );
+        assert_render(
+            &Encoding::Array(2, &Encoding::Atomic(&Encoding::Int)),
+            "[AtomicI32; 2]",
+            "_Atomic(int)[2]",
+        );
+        assert_render(
+            &Encoding::Atomic(&Encoding::Double),
+            "Atomic<f64>",
+            "_Atomic(double)",
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        assert_render(
+            &Encoding::Array(16, &Encoding::UChar),
+            "[u8; 16]",
+            "unsigned char[16]",
+        );
+        assert_render(
+            &Encoding::Array(2, &Encoding::Array(3, &Encoding::Float)),
+            "[[f32; 3]; 2]",
+            "float[2][3]",
+        );
+        assert_render(
+            &Encoding::Array(4, &Encoding::Pointer(&Encoding::Int)),
+            "[*mut i32; 4]",
+            "int *[4]",
+        );
+        assert_render(
+            &Encoding::Pointer(&Encoding::Array(4, &Encoding::Int)),
+            "*mut [i32; 4]",
+            "int (*)[4]",
+        );
+    }
+
+    #[test]
+    fn containers() {
+        const POINT: Encoding = Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]);
+        const SIZE: Encoding = Encoding::Struct("CGSize", &[Encoding::Double, Encoding::Double]);
+        const RECT: Encoding = Encoding::Struct("CGRect", &[POINT, SIZE]);
+
+        assert_render(
+            &POINT,
+            "CGPoint",
+            "struct CGPoint { double field0; double field1; }",
+        );
+        assert_render(
+            &RECT,
+            "CGRect",
+            "struct CGRect { struct CGPoint field0; struct CGSize field1; }",
+        );
+        assert_render(&Encoding::Pointer(&RECT), "*mut CGRect", "struct CGRect *");
+        assert_render(
+            &Encoding::Union("u", &[Encoding::Int, Encoding::Array(2, &Encoding::Short)]),
+            "u",
+            "union u { int field0; short field1[2]; }",
+        );
+        assert_render(
+            &Encoding::Array(2, &POINT),
+            "[CGPoint; 2]",
+            "struct CGPoint[2]",
+        );
+        assert_render(
+            &Encoding::Struct(
+                "a",
+                &[
+                    Encoding::Struct("?", &[Encoding::Char]),
+                    Encoding::BitField(3, None),
+                    Encoding::BitField(2, Some(&(35, Encoding::UInt))),
+                ],
+            ),
+            "a",
+            "struct a { struct { char field0; } field0; unsigned int field1 : 3; unsigned int field2 : 2; }",
+        );
+        assert_render(&Encoding::Struct("?", &[]), "_", "struct { }");
+
+        let enc = EncodingBox::Struct("opaque".into(), None);
+        assert_eq!(enc.rust_type().to_string(), "opaque");
+        assert_eq!(enc.c_type().to_string(), "struct opaque");
+        let enc = EncodingBox::Struct("?".into(), Some(vec![]));
+        assert_eq!(enc.c_type().to_string(), "struct { }");
+    }
+}
@@ -0,0 +1,606 @@
+//! Implementation of `#[derive(Encode)]` and `#[derive(RefEncode)]`.
+//!
+//! We don't depend on `syn` or `quote`, so the input is parsed by hand. This
+//! only needs to handle the subset of Rust syntax that can appear in valid
+//! struct, union and enum definitions.
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// The kind of item that the derive is applied to.
+enum Kind {
+    Struct(Vec<TokenStream>),
+    Union(Vec<TokenStream>),
+    Enum,
+}
+
+/// The relevant parts of a `#[repr(...)]` attribute.
+#[derive(Default)]
+struct Repr {
+    c: bool,
+    transparent: bool,
+    int: Option<Ident>,
+    packed: Option<Span>,
+    align: Option<Span>,
+}
+
+/// The options of an `#[encoding(...)]` attribute.
+#[derive(Default)]
+struct EncodingAttr {
+    /// The string literal from `name = "..."`.
+    name: Option<Literal>,
+    /// The span of `unsafe_enum`, if given.
+    unsafe_enum: Option<Span>,
+}
+
+struct Input {
+    repr: Repr,
+    encoding: EncodingAttr,
+    name: Ident,
+    /// The generic parameters, including bounds, but without defaults.
+    impl_generics: Vec<TokenStream>,
+    /// The generic parameters as used in the type, without bounds.
+    ty_generics: Vec<TokenStream>,
+    has_type_params: bool,
+    where_clause: TokenStream,
+    kind: Kind,
+}
+
+type Result<T> = core::result::Result<T, TokenStream>;
+
+/// Create a `compile_error!` invocation with the given span.
+fn error(span: Span, msg: &str) -> TokenStream {
+    let mut msg = Literal::string(msg);
+    msg.set_span(span);
+    let tokens: [TokenTree; 8] = [
+        Punct::new(':', Spacing::Joint).into(),
+        Punct::new(':', Spacing::Alone).into(),
+        Ident::new("core", span).into(),
+        Punct::new(':', Spacing::Joint).into(),
+        Punct::new(':', Spacing::Alone).into(),
+        Ident::new("compile_error", span).into(),
+        Punct::new('!', Spacing::Alone).into(),
+        Group::new(Delimiter::Brace, TokenTree::from(msg).into()).into(),
+    ];
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == c)
+}
+
+fn is_ident(token: Option<&TokenTree>, s: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(ident)) if ident.to_string() == s)
+}
+
+/// Split the tokens on commas that are not inside angle brackets.
+///
+/// Commas inside groups are already hidden from us by the tokenizer.
+fn split_on_commas(tokens: impl IntoIterator<Item = TokenTree>) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![];
+    let mut current = vec![];
+    let mut depth = 0usize;
+    let mut prev_is_joint_minus = false;
+    for token in tokens {
+        let mut is_joint_minus = false;
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    parts.push(core::mem::take(&mut current));
+                    continue;
+                }
+                '<' => depth += 1,
+                // Ignore the `>` in `->`
+                '>' if !prev_is_joint_minus => depth = depth.saturating_sub(1),
+                '-' => is_joint_minus = punct.spacing() == Spacing::Joint,
+                _ => {}
+            }
+        }
+        prev_is_joint_minus = is_joint_minus;
+        current.push(token);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Remove leading outer attributes and visibility from the tokens.
+fn skip_attributes_and_vis(tokens: &[TokenTree]) -> &[TokenTree] {
+    let mut tokens = tokens;
+    while is_punct(tokens.first(), '#') {
+        tokens = &tokens[2..];
+    }
+    if is_ident(tokens.first(), "pub") {
+        tokens = &tokens[1..];
+        if let Some(TokenTree::Group(group)) = tokens.first() {
+            if group.delimiter() == Delimiter::Parenthesis {
+                tokens = &tokens[1..];
+            }
+        }
+    }
+    tokens
+}
+
+fn parse_repr(group: &Group, repr: &mut Repr) {
+    for token in group.stream() {
+        if let TokenTree::Ident(ident) = token {
+            match &*ident.to_string() {
+                "C" => repr.c = true,
+                "transparent" => repr.transparent = true,
+                "packed" => repr.packed = Some(ident.span()),
+                "align" => repr.align = Some(ident.span()),
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "isize" => repr.int = Some(ident),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn parse_encoding_attribute(group: &Group) -> Result<EncodingAttr> {
+    let mut attr = EncodingAttr::default();
+    for option in split_on_commas(group.stream()) {
+        match &*option {
+            [TokenTree::Ident(ident), TokenTree::Punct(punct), TokenTree::Literal(lit)]
+                if ident.to_string() == "name"
+                    && punct.as_char() == '='
+                    && lit.to_string().ends_with('"')
+                    && attr.name.is_none() =>
+            {
+                attr.name = Some(lit.clone());
+            }
+            [TokenTree::Ident(ident)]
+                if ident.to_string() == "unsafe_enum" && attr.unsafe_enum.is_none() =>
+            {
+                attr.unsafe_enum = Some(ident.span());
+            }
+            _ => {
+                return Err(error(
+                    group.span(),
+                    "expected `#[encoding(name = \"...\")]` or `#[encoding(unsafe_enum)]`",
+                ))
+            }
+        }
+    }
+    Ok(attr)
+}
+
+/// Find the `=` that starts the default of a generic parameter (`=` may also
+/// appear in bounds like `Iterator<Item = u8>`).
+fn default_position(param: &[TokenTree]) -> usize {
+    let mut depth = 0usize;
+    for (i, token) in param.iter().enumerate() {
+        if let TokenTree::Punct(punct) = token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' => depth = depth.saturating_sub(1),
+                '=' if depth == 0 => return i,
+                _ => {}
+            }
+        }
+    }
+    param.len()
+}
+
+/// Parse the generic parameters (the contents between `<` and `>`).
+fn parse_generics(tokens: Vec<TokenTree>, input: &mut Input) {
+    for param in split_on_commas(tokens) {
+        let param = skip_attributes_and_vis(&param);
+        // Strip the default, which is not allowed in `impl` blocks.
+        let param = &param[..default_position(param)];
+        input
+            .impl_generics
+            .push(param.iter().cloned().collect::<TokenStream>());
+
+        let ty_param: TokenStream = match param {
+            [TokenTree::Punct(quote), lifetime, ..] if quote.as_char() == '\'' => {
+                [TokenTree::Punct(quote.clone()), lifetime.clone()]
+                    .into_iter()
+                    .collect()
+            }
+            [TokenTree::Ident(keyword), name, ..] if keyword.to_string() == "const" => {
+                name.clone().into()
+            }
+            [name, ..] => {
+                input.has_type_params = true;
+                name.clone().into()
+            }
+            [] => continue,
+        };
+        input.ty_generics.push(ty_param);
+    }
+}
+
+/// Parse the types of each field in a struct or union.
+fn parse_fields(group: &Group) -> Vec<TokenStream> {
+    let named = group.delimiter() == Delimiter::Brace;
+    split_on_commas(group.stream())
+        .iter()
+        .filter_map(|field| {
+            let mut field = skip_attributes_and_vis(field);
+            if named {
+                // Skip the field name and the colon.
+                field = field.get(2..)?;
+            }
+            Some(field.iter().cloned().collect())
+        })
+        .collect()
+}
+
+/// Check that each variant is fieldless.
+fn check_variants(group: &Group) -> Result<()> {
+    // Discriminants may contain `<` (e.g. `1 << 2`), so we can't use
+    // `split_on_commas` here.
+    let tokens: Vec<_> = group.stream().into_iter().collect();
+    for variant in tokens.split(|token| is_punct(Some(token), ',')) {
+        let variant = skip_attributes_and_vis(variant);
+        if let Some(TokenTree::Group(group)) = variant.get(1) {
+            if group.delimiter() != Delimiter::None {
+                return Err(error(
+                    group.span(),
+                    "`#[derive(Encode)]` only supports enums whose variants have no fields",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse(input: TokenStream) -> Result<Input> {
+    let mut tokens = input.into_iter().peekable();
+    let mut repr = Repr::default();
+    let mut encoding = None;
+
+    // Attributes
+    while let Some(TokenTree::Punct(punct)) = tokens.peek() {
+        if punct.as_char() != '#' {
+            break;
+        }
+        tokens.next();
+        let attr = match tokens.next() {
+            Some(TokenTree::Group(group)) => group,
+            _ => unreachable!("invalid attribute"),
+        };
+        let attr: Vec<_> = attr.stream().into_iter().collect();
+        match &*attr {
+            [TokenTree::Ident(ident), TokenTree::Group(group)] if ident.to_string() == "repr" => {
+                parse_repr(group, &mut repr);
+            }
+            [TokenTree::Ident(ident), TokenTree::Group(group)]
+                if ident.to_string() == "encoding" =>
+            {
+                if encoding.is_some() {
+                    return Err(error(
+                        ident.span(),
+                        "cannot specify the `encoding` attribute twice",
+                    ));
+                }
+                encoding = Some(parse_encoding_attribute(group)?);
+            }
+            _ => {}
+        }
+    }
+
+    // Visibility
+    if is_ident(tokens.peek(), "pub") {
+        tokens.next();
+        if let Some(TokenTree::Group(group)) = tokens.peek() {
+            if group.delimiter() == Delimiter::Parenthesis {
+                tokens.next();
+            }
+        }
+    }
+
+    let keyword = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident,
+        _ => unreachable!("expected struct, enum or union"),
+    };
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident,
+        _ => unreachable!("expected identifier"),
+    };
+
+    let mut input = Input {
+        repr,
+        encoding: encoding.unwrap_or_default(),
+        name,
+        impl_generics: vec![],
+        ty_generics: vec![],
+        has_type_params: false,
+        where_clause: TokenStream::new(),
+        kind: Kind::Enum,
+    };
+
+    // Generics
+    if is_punct(tokens.peek(), '<') {
+        tokens.next();
+        let mut generics = vec![];
+        let mut depth = 0usize;
+        let mut prev_is_joint_minus = false;
+        for token in tokens.by_ref() {
+            let mut is_joint_minus = false;
+            if let TokenTree::Punct(punct) = &token {
+                match punct.as_char() {
+                    '<' => depth += 1,
+                    '>' if !prev_is_joint_minus => {
+                        if depth == 0 {
+                            break;
+                        }
+                        depth -= 1;
+                    }
+                    '-' => is_joint_minus = punct.spacing() == Spacing::Joint,
+                    _ => {}
+                }
+            }
+            prev_is_joint_minus = is_joint_minus;
+            generics.push(token);
+        }
+        parse_generics(generics, &mut input);
+    }
+
+    // Body and where clause, in either order (tuple structs have the where
+    // clause after the fields).
+    let mut body = None;
+    let mut in_where_clause = false;
+    for token in tokens {
+        match token {
+            TokenTree::Group(group)
+                if body.is_none()
+                    && (group.delimiter() == Delimiter::Brace
+                        || (!in_where_clause && group.delimiter() == Delimiter::Parenthesis)) =>
+            {
+                in_where_clause = false;
+                body = Some(group);
+            }
+            TokenTree::Ident(ident) if ident.to_string() == "where" => {
+                in_where_clause = true;
+            }
+            TokenTree::Punct(punct) if punct.as_char() == ';' => break,
+            token if in_where_clause => input.where_clause.extend([token]),
+            _ => {}
+        }
+    }
+    // Ensure that the where clause ends with a comma, so that we can add
+    // more predicates to it.
+    if !input.where_clause.is_empty() && !input.where_clause.to_string().trim_end().ends_with(',') {
+        input
+            .where_clause
+            .extend([TokenTree::from(Punct::new(',', Spacing::Alone))]);
+    }
+
+    input.kind = match &*keyword.to_string() {
+        "struct" => Kind::Struct(body.as_ref().map(parse_fields).unwrap_or_default()),
+        "union" => Kind::Union(body.as_ref().map(parse_fields).unwrap_or_default()),
+        "enum" => {
+            if let Some(body) = &body {
+                check_variants(body)?;
+            }
+            Kind::Enum
+        }
+        _ => unreachable!("expected struct, enum or union"),
+    };
+
+    Ok(input)
+}
+
+fn is_phantom_data(ty: &TokenStream) -> bool {
+    let mut last_ident = None;
+    for token in ty.clone() {
+        match token {
+            TokenTree::Ident(ident) => last_ident = Some(ident.to_string()),
+            TokenTree::Punct(punct) if punct.as_char() == ':' => {}
+            _ => break,
+        }
+    }
+    last_ident.as_deref() == Some("PhantomData")
+}
+
+fn tokens(s: &str) -> TokenStream {
+    s.parse().expect("valid tokens")
+}
+
+/// Emit `<$ty as ::objc2::encode::Encode>::ENCODING`.
+fn field_encoding(ty: &TokenStream) -> TokenStream {
+    let mut res = tokens("<");
+    res.extend(ty.clone());
+    res.extend(tokens("as ::objc2::encode::Encode>::ENCODING"));
+    res
+}
+
+/// Emit `unsafe impl<...> $trait for $name<...> where ... { $body }`.
+fn impl_block(
+    input: &Input,
+    trait_: &str,
+    extra_predicates: TokenStream,
+    body: TokenStream,
+) -> TokenStream {
+    let mut generics = TokenStream::new();
+    let mut ty_generics = TokenStream::new();
+    for param in &input.impl_generics {
+        generics.extend(param.clone());
+        generics.extend(tokens(","));
+    }
+    for param in &input.ty_generics {
+        ty_generics.extend(param.clone());
+        ty_generics.extend(tokens(","));
+    }
+
+    let mut res = tokens("#[automatically_derived] unsafe impl <");
+    res.extend(generics);
+    res.extend(tokens(">"));
+    res.extend(tokens(trait_));
+    res.extend(tokens("for"));
+    res.extend([TokenTree::Ident(input.name.clone())]);
+    res.extend(tokens("<"));
+    res.extend(ty_generics);
+    res.extend(tokens("> where"));
+    res.extend(input.where_clause.clone());
+    res.extend(extra_predicates);
+    res.extend([TokenTree::Group(Group::new(Delimiter::Brace, body))]);
+    res
+}
+
+fn encode_impl(input: &Input) -> Result<TokenStream> {
+    let name_span = input.name.span();
+    if let Some(span) = input.repr.packed {
+        return Err(error(
+            span,
+            "`#[derive(Encode)]` does not support `#[repr(packed)]`, since the packing is not part of the encoding",
+        ));
+    }
+    if let Some(span) = input.repr.align {
+        return Err(error(
+            span,
+            "`#[derive(Encode)]` does not support `#[repr(align(...))]`, since the alignment is not part of the encoding",
+        ));
+    }
+    if let Some(span) = input.encoding.unsafe_enum {
+        if !matches!(input.kind, Kind::Enum) {
+            return Err(error(
+                span,
+                "`#[encoding(unsafe_enum)]` is only supported on enums",
+            ));
+        }
+    }
+
+    let encoding_name = || -> TokenStream {
+        let lit = input
+            .encoding
+            .name
+            .clone()
+            .unwrap_or_else(|| Literal::string(&input.name.to_string()));
+        TokenTree::Literal(lit).into()
+    };
+
+    // The types whose encoding is used.
+    let mut encoded_types: Vec<TokenStream> = vec![];
+
+    let encoding = match &input.kind {
+        Kind::Enum => {
+            if input.encoding.name.is_some() {
+                return Err(error(
+                    name_span,
+                    "`#[encoding(name = \"...\")]` is only supported on structs and unions",
+                ));
+            }
+            // Objective-C may hand us any value of the integer type, which
+            // would be undefined behaviour if it is not one of the variants.
+            // So the user has to acknowledge that they uphold this.
+            if input.encoding.unsafe_enum.is_none() {
+                return Err(error(
+                    name_span,
+                    "`#[derive(Encode)]` on enums requires `#[encoding(unsafe_enum)]`, to acknowledge that it is undefined behaviour if Objective-C passes a value that is not a valid variant",
+                ));
+            }
+            match &input.repr.int {
+                Some(int) => field_encoding(&TokenTree::Ident(int.clone()).into()),
+                None => {
+                    return Err(error(
+                        name_span,
+                        "`#[derive(Encode)]` requires enums to specify their integer type with e.g. `#[repr(isize)]`",
+                    ))
+                }
+            }
+        }
+        Kind::Struct(fields) if input.repr.transparent => {
+            if input.encoding.name.is_some() {
+                return Err(error(
+                    name_span,
+                    "`#[encoding(name = \"...\")]` is not supported on `#[repr(transparent)]` structs",
+                ));
+            }
+            let mut non_zst = fields.iter().filter(|ty| !is_phantom_data(ty));
+            match (non_zst.next(), non_zst.next()) {
+                (Some(ty), None) => {
+                    encoded_types.push(ty.clone());
+                    field_encoding(ty)
+                }
+                _ => {
+                    return Err(error(
+                        name_span,
+                        "`#[derive(Encode)]` requires `#[repr(transparent)]` structs to have exactly one field that is not `PhantomData`",
+                    ))
+                }
+            }
+        }
+        Kind::Struct(fields) | Kind::Union(fields) if input.repr.c => {
+            let variant = if let Kind::Union(_) = input.kind {
+                "Union"
+            } else {
+                "Struct"
+            };
+            let mut items = TokenStream::new();
+            for ty in fields {
+                encoded_types.push(ty.clone());
+                items.extend(field_encoding(ty));
+                items.extend(tokens(","));
+            }
+            let mut args = encoding_name();
+            args.extend(tokens(", &"));
+            args.extend([TokenTree::Group(Group::new(Delimiter::Bracket, items))]);
+
+            let mut res = tokens("::objc2::encode::Encoding::");
+            res.extend(tokens(variant));
+            res.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+            res
+        }
+        _ => {
+            return Err(error(
+                name_span,
+                "`#[derive(Encode)]` requires `#[repr(C)]` or `#[repr(transparent)]`, since the layout of the type must match the encoding",
+            ))
+        }
+    };
+
+    // Each field must implement `Encode` for generic types. For non-generic
+    // types, this is checked when evaluating `ENCODING` instead.
+    let mut predicates = TokenStream::new();
+    if input.has_type_params {
+        for ty in encoded_types {
+            predicates.extend(ty);
+            predicates.extend(tokens(": ::objc2::encode::Encode,"));
+        }
+    }
+
+    let mut body = tokens("const ENCODING: ::objc2::encode::Encoding =");
+    body.extend(encoding);
+    body.extend(tokens(";"));
+
+    Ok(impl_block(
+        input,
+        "::objc2::encode::Encode",
+        predicates,
+        body,
+    ))
+}
+
+fn ref_encode_impl(input: &Input) -> TokenStream {
+    impl_block(
+        input,
+        "::objc2::encode::RefEncode",
+        tokens("Self: ::objc2::encode::Encode,"),
+        tokens(
+            "const ENCODING_REF: ::objc2::encode::Encoding = \
+            ::objc2::encode::Encoding::Pointer(&<Self as ::objc2::encode::Encode>::ENCODING);",
+        ),
+    )
+}
+
+pub(crate) fn derive_encode(input: TokenStream) -> TokenStream {
+    match parse(input).and_then(|input| encode_impl(&input)) {
+        Ok(tokens) => tokens,
+        Err(err) => err,
+    }
+}
+
+pub(crate) fn derive_ref_encode(input: TokenStream) -> TokenStream {
+    match parse(input) {
+        Ok(input) => ref_encode_impl(&input),
+        Err(err) => err,
+    }
+}
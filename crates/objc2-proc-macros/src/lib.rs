@@ -17,6 +17,8 @@
 #[doc = include_str!("../README.md")]
 extern "C" {}
 
+mod derive_encode;
+
 use core::hash::{Hash, Hasher};
 
 use proc_macro::Ident;
@@ -72,3 +74,36 @@ pub fn __hash_idents(input: TokenStream) -> TokenStream {
     let s = format!("{:016x}", hasher.finish());
     TokenTree::Literal(Literal::string(&s)).into()
 }
+
+/// Derive `Encode` for a `#[repr(C)]` struct or union, a
+/// `#[repr(transparent)]` struct or a fieldless enum with an integer `repr`.
+///
+/// The encoding is computed at compile-time from the encodings of the
+/// fields. Structs and unions use the Rust name of the type as the
+/// Objective-C name by default; use `#[encoding(name = "...")]` to specify
+/// another one, which is usually needed to match the name used in C headers.
+///
+/// Types whose layout cannot be described by an encoding (e.g. those with
+/// `#[repr(Rust)]`, `#[repr(packed)]` or `#[repr(align(N))]`) are rejected.
+///
+/// Enums additionally require `#[encoding(unsafe_enum)]`. Objective-C code
+/// may pass any value of the underlying integer type, and it is undefined
+/// behaviour if that is not one of the enum's variants; the attribute is an
+/// acknowledgement that you have verified that this cannot happen.
+///
+/// This is re-exported as `objc2::encode::Encode` when the `"derive"`
+/// feature of `objc2` is enabled.
+#[proc_macro_derive(Encode, attributes(encoding))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    derive_encode::derive_encode(input)
+}
+
+/// Derive `RefEncode` for a type that implements `Encode`, such that
+/// references to it are encoded as pointers.
+///
+/// This is re-exported as `objc2::encode::RefEncode` when the `"derive"`
+/// feature of `objc2` is enabled.
+#[proc_macro_derive(RefEncode)]
+pub fn derive_ref_encode(input: TokenStream) -> TokenStream {
+    derive_encode::derive_ref_encode(input)
+}
@@ -0,0 +1,10 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Forward the `apple_new` cfg from `objc-sys`
+    if env::var("DEP_OBJC_0_3_APPLE_NEW").unwrap() == "true" {
+        println!("cargo:rustc-cfg=apple_new");
+    }
+}
@@ -0,0 +1,224 @@
+//! Support for `unstable-static-sel` and `unstable-static-class` with the
+//! GNUstep 2.0 ABI.
+//!
+//! Clang places selectors, class references and so on in special sections,
+//! and emits a constructor in each image which calls `__objc_load` with the
+//! bounds of these sections, which in turn registers everything with the
+//! runtime.
+//!
+//! We do the same here, see `CGObjCGNUstep2` in clang's `CGObjCGNU.cpp` and
+//! `loader.c` in libobjc2 for details.
+//!
+//! The init struct, the null entries and the constructor are emitted in a
+//! single `global_asm!` block, and hence always end up in the same object
+//! file. This ensures that when `.objc_init` is linked (which the
+//! `__inner_statics!` macro makes sure of), everything else is linked as
+//! well.
+//!
+//! Like clang, `.objc_init` is a weak, hidden symbol in the `.objc_init`
+//! comdat, and the constructor is placed in the `.objc_ctor` comdat, such
+//! that each image only registers its sections once, even if it also
+//! contains Objective-C code compiled by clang, and such that the symbol is
+//! not exported from dynamic libraries.
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::ptr;
+
+use crate::ffi;
+
+/// A selector placed in the `__objc_selectors` section.
+///
+/// The runtime registers this in-place when the image is loaded, which is
+/// why the name is mutable.
+#[repr(C)]
+pub struct StaticSelector {
+    name: UnsafeCell<*const u8>,
+    types: *const u8,
+}
+
+// SAFETY: The selector is only modified by the runtime while loading the
+// image, before any Rust code can access it.
+unsafe impl Sync for StaticSelector {}
+
+impl StaticSelector {
+    /// Create a new untyped selector with the given NUL-terminated name.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name: UnsafeCell::new(name.as_ptr()),
+            types: ptr::null(),
+        }
+    }
+
+    pub const fn as_ptr(&'static self) -> *const ffi::objc_selector {
+        (self as *const Self).cast()
+    }
+}
+
+/// The argument to `__objc_load`, describing the Objective-C data in the
+/// current image.
+///
+/// Mirrors `struct objc_init` in libobjc2, but the contents are only ever
+/// accessed by the runtime.
+#[repr(C)]
+pub struct ObjcInit {
+    _priv: [u8; 0],
+}
+
+extern "C" {
+    /// Defined in the assembly below.
+    #[link_name = ".objc_init"]
+    pub static OBJC_INIT: ObjcInit;
+
+    fn __objc_load(init: *mut c_void);
+}
+
+#[cfg(target_pointer_width = "64")]
+macro_rules! ptr {
+    () => {
+        ".quad"
+    };
+}
+
+#[cfg(target_pointer_width = "64")]
+macro_rules! ptr_align {
+    () => {
+        ".balign 8"
+    };
+}
+
+#[cfg(target_pointer_width = "32")]
+macro_rules! ptr {
+    () => {
+        ".long"
+    };
+}
+
+#[cfg(target_pointer_width = "32")]
+macro_rules! ptr_align {
+    () => {
+        ".balign 4"
+    };
+}
+
+// The linker defines the `__start_` and `__stop_` symbols for every section
+// whose name is a valid C identifier.
+//
+// Each section must contain at least one entry, otherwise the linker won't
+// define these symbols. So, like clang, we emit null entries of the correct
+// size, which the runtime skips. The constant string entries consist of the
+// isa pointer, four `u32`s and the data pointer.
+core::arch::global_asm!(
+    ".pushsection .data..objc_init,\"awG\",%progbits,.objc_init,comdat",
+    ".weak .objc_init",
+    ".hidden .objc_init",
+    ".type .objc_init,%object",
+    ".balign 8",
+    ".objc_init:",
+    ".quad 0",
+    concat!(ptr!(), " __start___objc_selectors, __stop___objc_selectors"),
+    concat!(ptr!(), " __start___objc_classes, __stop___objc_classes"),
+    concat!(ptr!(), " __start___objc_class_refs, __stop___objc_class_refs"),
+    concat!(ptr!(), " __start___objc_cats, __stop___objc_cats"),
+    concat!(ptr!(), " __start___objc_protocols, __stop___objc_protocols"),
+    concat!(
+        ptr!(),
+        " __start___objc_protocol_refs, __stop___objc_protocol_refs"
+    ),
+    concat!(
+        ptr!(),
+        " __start___objc_class_aliases, __stop___objc_class_aliases"
+    ),
+    concat!(
+        ptr!(),
+        " __start___objc_constant_string, __stop___objc_constant_string"
+    ),
+    ".size .objc_init, . - .objc_init",
+    ".popsection",
+    //
+    ".pushsection __objc_selectors,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0, 0"),
+    ".popsection",
+    ".pushsection __objc_classes,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0"),
+    ".popsection",
+    ".pushsection __objc_class_refs,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0"),
+    ".popsection",
+    ".pushsection __objc_cats,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0, 0, 0, 0, 0, 0, 0"),
+    ".popsection",
+    ".pushsection __objc_protocols,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0"),
+    ".popsection",
+    ".pushsection __objc_protocol_refs,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0"),
+    ".popsection",
+    ".pushsection __objc_class_aliases,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0, 0"),
+    ".popsection",
+    ".pushsection __objc_constant_string,\"awR\",%progbits",
+    ptr_align!(),
+    concat!(ptr!(), " 0"),
+    ".long 0, 0, 0, 0",
+    concat!(ptr!(), " 0"),
+    ".popsection",
+    //
+    // Run `__objc_load` when the image is loaded.
+    ".pushsection .init_array,\"awG\",%init_array,.objc_ctor,comdat",
+    ptr_align!(),
+    concat!(ptr!(), " {load}"),
+    ".popsection",
+    load = sym load,
+);
+
+extern "C" fn load() {
+    // SAFETY: The init struct describes the sections of the current image,
+    // and is only modified by the runtime.
+    unsafe { __objc_load(ptr::addr_of!(OBJC_INIT) as *mut c_void) };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem;
+
+    use super::*;
+    #[cfg(feature = "unstable-static-class")]
+    use crate::class;
+    #[cfg(feature = "unstable-static-class")]
+    use crate::runtime::Class;
+    #[cfg(feature = "unstable-static-sel")]
+    use crate::runtime::Sel;
+    #[cfg(feature = "unstable-static-sel")]
+    use crate::sel;
+
+    #[test]
+    fn test_layout() {
+        let ptr_size = mem::size_of::<usize>();
+        assert_eq!(mem::size_of::<StaticSelector>(), 2 * ptr_size);
+    }
+
+    #[test]
+    #[cfg(feature = "unstable-static-sel")]
+    fn test_static_sel_registered() {
+        let sel = sel!(staticSelectorOnlyUsedHere:withArgument:);
+        assert_eq!(
+            sel,
+            Sel::register("staticSelectorOnlyUsedHere:withArgument:")
+        );
+        assert_eq!(sel.name(), "staticSelectorOnlyUsedHere:withArgument:");
+    }
+
+    #[test]
+    #[cfg(feature = "unstable-static-class")]
+    fn test_static_class_registered() {
+        let cls = class!(NSObject);
+        assert_eq!(cls, Class::get("NSObject").unwrap());
+    }
+}
@@ -6,13 +6,18 @@ use std::collections::HashSet;
 
 use crate::declare::ClassBuilder;
 use crate::declare::MethodImplementation;
-use crate::encode::Encode;
+use crate::encode::__unstable::EncodeConvertReturn;
+#[cfg(all(debug_assertions, feature = "verify"))]
+use crate::encode::__unstable::{EncodeArguments, EncodeReturn};
+use crate::encode::{Encode, Encoding};
 use crate::message::__TupleExtender;
 use crate::rc::{Allocated, Id, Ownership};
-#[cfg(all(debug_assertions, feature = "verify"))]
-use crate::runtime::MethodDescription;
 use crate::runtime::{Class, Object, Protocol, Sel};
-use crate::{Message, MessageArguments, MessageReceiver};
+#[cfg(all(debug_assertions, feature = "verify"))]
+use crate::runtime::{MethodDescription, VerificationError};
+#[cfg(all(debug_assertions, feature = "verify"))]
+use crate::verify::{verify_method_description, Inner};
+use crate::{ClassType, Message, MessageArguments, MessageReceiver};
 use crate::{__sel_data, __sel_inner};
 
 pub use core::borrow::{Borrow, BorrowMut};
@@ -24,15 +29,28 @@ pub use core::ops::{Deref, DerefMut};
 pub use core::option::Option::{self, None, Some};
 pub use core::primitive::{bool, str, u8};
 pub use core::ptr::drop_in_place;
-pub use core::{compile_error, concat, panic, stringify};
+pub use core::{compile_error, concat, file, line, panic, stringify};
 // TODO: Use `core::cell::LazyCell`
 pub use std::sync::Once;
 
 mod cache;
 mod declare_class;
+#[cfg(all(
+    feature = "gnustep-2-0",
+    not(target_os = "windows"),
+    any(feature = "unstable-static-sel", feature = "unstable-static-class")
+))]
+mod gnustep_statics;
 
 pub use self::cache::{CachedClass, CachedSel};
 pub use self::declare_class::{MaybeOptionId, MessageRecieveId};
+#[cfg(all(
+    feature = "gnustep-2-0",
+    not(target_os = "windows"),
+    any(feature = "unstable-static-sel", feature = "unstable-static-class")
+))]
+pub use self::gnustep_statics::{ObjcInit, StaticSelector, OBJC_INIT};
+pub use crate::message::Variadic;
 
 // Common selectors.
 //
@@ -56,6 +74,48 @@ pub fn new_sel() -> Sel {
     __sel_inner!(__sel_data!(new), "new")
 }
 
+/// Run the body of a method or block implemented in Rust, converting a panic
+/// into an Objective-C exception if the `"panic-to-exception"` feature is
+/// enabled.
+#[inline]
+pub fn panic_to_exception<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "panic-to-exception")]
+    {
+        // SAFETY: The closure is run from an Objective-C method or block,
+        // which may unwind.
+        unsafe { crate::exception::panic_to_exception(f) }
+    }
+    #[cfg(not(feature = "panic-to-exception"))]
+    {
+        f()
+    }
+}
+
+/// Run the message send in `msg_send![try ...]`, catching any exception.
+///
+/// The message send itself uses the `*_no_catch` functions, so that if the
+/// `"catch-all"` feature is enabled, the exception is returned here instead
+/// of being turned into a panic.
+///
+/// # Safety
+///
+/// Same as [`exception::catch`].
+///
+/// [`exception::catch`]: crate::exception::catch
+#[cfg(feature = "exception")]
+#[inline]
+#[track_caller]
+pub unsafe fn catch_msg_send<R>(
+    f: impl FnOnce() -> R,
+) -> Result<R, Id<crate::exception::Exception>> {
+    // SAFETY: Upheld by the caller
+    match unsafe { crate::exception::catch(core::panic::AssertUnwindSafe(f)) } {
+        Ok(r) => Ok(r),
+        Err(Some(exception)) => Err(exception),
+        Err(None) => panic!("caught exception nil"),
+    }
+}
+
 /// Helper for specifying the retain semantics for a given selector family.
 ///
 /// Note that we can't actually check if a method is in a method family; only
@@ -103,7 +163,12 @@ pub const fn retain_semantics(selector: &str) -> u8 {
     }
 }
 
-pub trait MsgSendId<T, U> {
+/// Sends a message with the retain semantics of `Self`.
+///
+/// If `CATCH` is `false`, the message send is not wrapped in `@try` when the
+/// `"catch-all"` feature is enabled; this is used by `msg_send_id![try ...]`
+/// through the `__*_no_catch` methods.
+pub trait MsgSendId<T, U, const CATCH: bool = true> {
     #[track_caller]
     unsafe fn send_message_id<A: MessageArguments, R: MaybeUnwrap<Input = U>>(
         obj: T,
@@ -125,7 +190,8 @@ pub trait MsgSendId<T, U> {
     {
         let mut err: *mut E = ptr::null_mut();
         let args = args.add_argument(&mut err);
-        let res: Option<U> = unsafe { Self::send_message_id(obj, sel, args) };
+        let res: Option<U> =
+            unsafe { <Self as MsgSendId<T, U, CATCH>>::send_message_id(obj, sel, args) };
         // As per the Cocoa documentation:
         // > Success or failure is indicated by the return value of the
         // > method. Although Cocoa methods that indirectly return error
@@ -151,6 +217,54 @@ pub trait MsgSendId<T, U> {
             Err(unsafe { encountered_error(err) })
         }
     }
+
+    #[inline]
+    #[track_caller]
+    unsafe fn __send_message_id_no_catch<A: MessageArguments, R: MaybeUnwrap<Input = U>>(
+        obj: T,
+        sel: Sel,
+        args: A,
+    ) -> R
+    where
+        Self: MsgSendId<T, U, false>,
+    {
+        unsafe { <Self as MsgSendId<T, U, false>>::send_message_id(obj, sel, args) }
+    }
+
+    #[inline]
+    #[track_caller]
+    unsafe fn __send_message_id_error_no_catch<A, E>(
+        obj: T,
+        sel: Sel,
+        args: A,
+    ) -> Result<U, Id<E>>
+    where
+        Self: MsgSendId<T, U, false>,
+        *mut *mut E: Encode,
+        A: __TupleExtender<*mut *mut E>,
+        <A as __TupleExtender<*mut *mut E>>::PlusOneArgument: MessageArguments,
+        E: Message,
+        Option<U>: MaybeUnwrap<Input = U>,
+    {
+        unsafe { <Self as MsgSendId<T, U, false>>::send_message_id_error(obj, sel, args) }
+    }
+}
+
+/// Calls [`MessageReceiver::send_message`], or the variant that doesn't
+/// catch exceptions if `CATCH` is `false`.
+#[inline]
+#[track_caller]
+unsafe fn send_message<T, A, R, const CATCH: bool>(obj: T, sel: Sel, args: A) -> R
+where
+    T: MessageReceiver,
+    A: MessageArguments,
+    R: EncodeConvertReturn,
+{
+    if CATCH {
+        unsafe { MessageReceiver::send_message(obj, sel, args) }
+    } else {
+        unsafe { MessageReceiver::__send_message_no_catch(obj, sel, args) }
+    }
 }
 
 // Marked `cold` to tell the optimizer that errors are comparatively rare.
@@ -162,7 +276,9 @@ unsafe fn encountered_error<E: Message>(err: *mut E) -> Id<E> {
     unsafe { Id::retain(err) }.expect("error parameter should be set if the method returns NULL")
 }
 
-impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership> MsgSendId<T, Id<U, O>> for New {
+impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership, const CATCH: bool>
+    MsgSendId<T, Id<U, O>, CATCH> for New
+{
     #[inline]
     unsafe fn send_message_id<A: MessageArguments, R: MaybeUnwrap<Input = Id<U, O>>>(
         obj: T,
@@ -171,7 +287,7 @@ impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership> MsgSendId<T, Id<U, O
     ) -> R {
         let ptr = obj.__as_raw_receiver();
         // SAFETY: Checked by caller
-        let obj = unsafe { MessageReceiver::send_message(ptr, sel, args) };
+        let obj = unsafe { send_message::<_, _, _, CATCH>(ptr, sel, args) };
         // SAFETY: The selector is `new`, so this has +1 retain count
         let obj = unsafe { Id::new(obj) };
 
@@ -181,7 +297,7 @@ impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership> MsgSendId<T, Id<U, O
     }
 }
 
-impl<T: ?Sized + Message> MsgSendId<&'_ Class, Allocated<T>> for Alloc {
+impl<T: ?Sized + Message, const CATCH: bool> MsgSendId<&'_ Class, Allocated<T>, CATCH> for Alloc {
     #[inline]
     unsafe fn send_message_id<A: MessageArguments, R: MaybeUnwrap<Input = Allocated<T>>>(
         cls: &Class,
@@ -189,14 +305,16 @@ impl<T: ?Sized + Message> MsgSendId<&'_ Class, Allocated<T>> for Alloc {
         args: A,
     ) -> R {
         // SAFETY: Checked by caller
-        let obj = unsafe { MessageReceiver::send_message(cls, sel, args) };
+        let obj = unsafe { send_message::<_, _, _, CATCH>(cls, sel, args) };
         // SAFETY: The selector is `alloc`, so this has +1 retain count
         let obj = unsafe { Allocated::new(obj) };
         R::maybe_unwrap::<Self>(obj, (cls, sel))
     }
 }
 
-impl<T: ?Sized + Message, O: Ownership> MsgSendId<Option<Allocated<T>>, Id<T, O>> for Init {
+impl<T: ?Sized + Message, O: Ownership, const CATCH: bool>
+    MsgSendId<Option<Allocated<T>>, Id<T, O>, CATCH> for Init
+{
     #[inline]
     unsafe fn send_message_id<A: MessageArguments, R: MaybeUnwrap<Input = Id<T, O>>>(
         obj: Option<Allocated<T>>,
@@ -210,15 +328,15 @@ impl<T: ?Sized + Message, O: Ownership> MsgSendId<Option<Allocated<T>>, Id<T, O>
         //
         // We do this for efficiency, to avoid having a branch that the user
         // did not intend after every `alloc`.
-        let obj = unsafe { MessageReceiver::send_message(ptr, sel, args) };
+        let obj = unsafe { send_message::<_, _, _, CATCH>(ptr, sel, args) };
         // SAFETY: The selector is `init`, so this has +1 retain count
         let obj = unsafe { Id::new(obj) };
         R::maybe_unwrap::<Self>(obj, (ptr.cast(), sel))
     }
 }
 
-impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership> MsgSendId<T, Id<U, O>>
-    for CopyOrMutCopy
+impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership, const CATCH: bool>
+    MsgSendId<T, Id<U, O>, CATCH> for CopyOrMutCopy
 {
     #[inline]
     unsafe fn send_message_id<A: MessageArguments, R: MaybeUnwrap<Input = Id<U, O>>>(
@@ -227,7 +345,7 @@ impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership> MsgSendId<T, Id<U, O
         args: A,
     ) -> R {
         // SAFETY: Checked by caller
-        let obj = unsafe { MessageReceiver::send_message(obj, sel, args) };
+        let obj = unsafe { send_message::<_, _, _, CATCH>(obj, sel, args) };
         // SAFETY: The selector is `copy` or `mutableCopy`, so this has +1
         // retain count
         let obj = unsafe { Id::new(obj) };
@@ -235,7 +353,9 @@ impl<T: MessageReceiver, U: ?Sized + Message, O: Ownership> MsgSendId<T, Id<U, O
     }
 }
 
-impl<T: MessageReceiver, U: Message, O: Ownership> MsgSendId<T, Id<U, O>> for Other {
+impl<T: MessageReceiver, U: Message, O: Ownership, const CATCH: bool>
+    MsgSendId<T, Id<U, O>, CATCH> for Other
+{
     #[inline]
     unsafe fn send_message_id<A: MessageArguments, R: MaybeUnwrap<Input = Id<U, O>>>(
         obj: T,
@@ -244,7 +364,7 @@ impl<T: MessageReceiver, U: Message, O: Ownership> MsgSendId<T, Id<U, O>> for Ot
     ) -> R {
         let ptr = obj.__as_raw_receiver();
         // SAFETY: Checked by caller
-        let obj = unsafe { MessageReceiver::send_message(ptr, sel, args) };
+        let obj = unsafe { send_message::<_, _, _, CATCH>(ptr, sel, args) };
         // All code between the message send and the `retain_autoreleased`
         // must be able to be optimized away for this to work.
 
@@ -443,6 +563,53 @@ const fn in_selector_family(mut selector: &[u8], mut family: &[u8]) -> bool {
     }
 }
 
+/// The encodings of a method declared in `extern_methods!`.
+///
+/// This is implemented on the type the method is declared on, using a unique
+/// marker type per method, so that the argument and return types can refer
+/// to `Self`.
+pub trait ExternMethodEncodings<Marker> {
+    const ARGS: &'static [Encoding];
+    const RET: Encoding;
+}
+
+/// Helper for getting the name of the class that methods in
+/// `extern_methods!` are declared on, if the type is a [`ClassType`].
+///
+/// Uses autoref-based specialization: `(&&ClassName::<T>::new()).name()`
+/// resolves to [`ClassNameClassType`] if `T: ClassType`, and to
+/// [`ClassNameFallback`] otherwise.
+pub struct ClassName<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> ClassName<T> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+pub trait ClassNameClassType {
+    fn name(&self) -> Option<&'static str>;
+}
+
+impl<T: ClassType> ClassNameClassType for &ClassName<T> {
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        Some(T::NAME)
+    }
+}
+
+pub trait ClassNameFallback {
+    fn name(&self) -> Option<&'static str>;
+}
+
+impl<T: ?Sized> ClassNameFallback for ClassName<T> {
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
 /// Helper struct for emitting the module info that macOS 32-bit requires.
 ///
 /// <https://github.com/llvm/llvm-project/blob/release/13.x/clang/lib/CodeGen/CGObjCMac.cpp#L5211-L5234>
@@ -478,6 +645,9 @@ impl ClassBuilder {
         &'a mut self,
         protocol: Option<&'b Protocol>,
     ) -> ClassProtocolMethodsBuilder<'a, 'b> {
+        // ObjFW doesn't have protocols at runtime, so `protocol` is always
+        // `None` there.
+        #[cfg(not(feature = "unstable-objfw"))]
         if let Some(protocol) = protocol {
             self.add_protocol(protocol);
         }
@@ -488,17 +658,17 @@ impl ClassBuilder {
                 builder: self,
                 protocol,
                 required_instance_methods: protocol
-                    .map(|p| p.method_descriptions(true))
+                    .map(|p| p.method_descriptions(true, true))
                     .unwrap_or_default(),
                 optional_instance_methods: protocol
-                    .map(|p| p.method_descriptions(false))
+                    .map(|p| p.method_descriptions(false, true))
                     .unwrap_or_default(),
                 registered_instance_methods: HashSet::new(),
                 required_class_methods: protocol
-                    .map(|p| p.class_method_descriptions(true))
+                    .map(|p| p.method_descriptions(true, false))
                     .unwrap_or_default(),
                 optional_class_methods: protocol
-                    .map(|p| p.class_method_descriptions(false))
+                    .map(|p| p.method_descriptions(false, false))
                     .unwrap_or_default(),
                 registered_class_methods: HashSet::new(),
             }
@@ -545,19 +715,22 @@ impl ClassProtocolMethodsBuilder<'_, '_> {
     {
         #[cfg(all(debug_assertions, feature = "verify"))]
         if let Some(protocol) = self.protocol {
-            let _types = self
+            let res = self
                 .required_instance_methods
                 .iter()
                 .chain(&self.optional_instance_methods)
                 .find(|desc| desc.sel == sel)
-                .map(|desc| desc.types)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "failed overriding protocol method -[{} {:?}]: method not found",
-                        protocol.name(),
-                        sel
-                    )
+                .ok_or_else(|| Inner::MethodNotFound.into())
+                .and_then(|desc| {
+                    verify_method_description(desc, F::Args::ENCODINGS, &F::Ret::ENCODING_RETURN)
                 });
+            if let Err(err) = res {
+                panic!(
+                    "failed overriding protocol method -[{} {:?}]: {err}",
+                    protocol.name(),
+                    sel
+                )
+            }
         }
 
         // SAFETY: Checked by caller
@@ -576,19 +749,22 @@ impl ClassProtocolMethodsBuilder<'_, '_> {
     {
         #[cfg(all(debug_assertions, feature = "verify"))]
         if let Some(protocol) = self.protocol {
-            let _types = self
+            let res = self
                 .required_class_methods
                 .iter()
                 .chain(&self.optional_class_methods)
                 .find(|desc| desc.sel == sel)
-                .map(|desc| desc.types)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "failed overriding protocol method +[{} {:?}]: method not found",
-                        protocol.name(),
-                        sel
-                    )
+                .ok_or_else(|| Inner::MethodNotFound.into())
+                .and_then(|desc| {
+                    verify_method_description(desc, F::Args::ENCODINGS, &F::Ret::ENCODING_RETURN)
                 });
+            if let Err(err) = res {
+                panic!(
+                    "failed overriding protocol method +[{} {:?}]: {err}",
+                    protocol.name(),
+                    sel
+                )
+            }
         }
 
         // SAFETY: Checked by caller
@@ -603,27 +779,38 @@ impl ClassProtocolMethodsBuilder<'_, '_> {
     pub fn __finish(self) {
         #[cfg(all(debug_assertions, feature = "verify"))]
         if let Some(protocol) = self.protocol {
-            for desc in &self.required_instance_methods {
-                if !self.registered_instance_methods.contains(&desc.sel) {
-                    panic!(
-                        "must implement required protocol method -[{} {:?}]",
-                        protocol.name(),
-                        desc.sel
-                    )
-                }
-            }
-        }
+            let superclass = self.builder.superclass();
 
-        #[cfg(all(debug_assertions, feature = "verify"))]
-        if let Some(protocol) = self.protocol {
-            for desc in &self.required_class_methods {
-                if !self.registered_class_methods.contains(&desc.sel) {
-                    panic!(
-                        "must implement required protocol method +[{} {:?}]",
-                        protocol.name(),
-                        desc.sel
-                    )
-                }
+            // Methods inherited from the superclass also count as
+            // implemented.
+            let missing_instance_methods = self
+                .required_instance_methods
+                .iter()
+                .filter(|desc| !self.registered_instance_methods.contains(&desc.sel))
+                .filter(|desc| {
+                    superclass
+                        .and_then(|superclass| superclass.instance_method(desc.sel))
+                        .is_none()
+                })
+                .map(|desc| (desc.sel, true));
+            let missing_class_methods = self
+                .required_class_methods
+                .iter()
+                .filter(|desc| !self.registered_class_methods.contains(&desc.sel))
+                .filter(|desc| {
+                    superclass
+                        .and_then(|superclass| superclass.class_method(desc.sel))
+                        .is_none()
+                })
+                .map(|desc| (desc.sel, false));
+            let missing: Vec<_> = missing_instance_methods
+                .chain(missing_class_methods)
+                .collect();
+
+            if !missing.is_empty() {
+                let err: VerificationError =
+                    Inner::MissingProtocolMethods(protocol.name().into(), missing).into();
+                panic!("failed implementing protocol {}: {err}", protocol.name())
             }
         }
     }
@@ -856,6 +1043,16 @@ mod tests {
         let _obj: Id<Object> = unsafe { msg_send_id![obj, description] };
     }
 
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_class_name() {
+        assert_eq!(
+            (&&ClassName::<NSObject>::new()).name(),
+            Some(NSObject::NAME)
+        );
+        assert_eq!((&&ClassName::<Object>::new()).name(), None);
+    }
+
     #[test]
     fn test_in_selector_family() {
         #[track_caller]
@@ -1,8 +1,11 @@
 #![deny(deprecated, unreachable_code)]
 use core::ptr;
 
+#[cfg(not(feature = "unstable-objfw"))]
 use crate::declare::IvarEncode;
-use crate::rc::{Id, Owned};
+use crate::rc::Id;
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::rc::Owned;
 use crate::runtime::NSObject;
 use crate::{declare_class, extern_methods, sel, ClassType};
 
@@ -361,7 +364,43 @@ fn test_unreachable() {
     let _ = DeclareClassUnreachable::class();
 }
 
+#[cfg(feature = "panic-to-exception")]
+declare_class!(
+    struct DeclareClassPanic;
+
+    unsafe impl ClassType for DeclareClassPanic {
+        type Super = NSObject;
+        const NAME: &'static str = "DeclareClassPanic";
+    }
+
+    unsafe impl DeclareClassPanic {
+        #[method(panicWith:)]
+        fn panic_with(&self, value: u32) -> u32 {
+            std::panic::panic_any(value)
+        }
+    }
+);
+
+#[test]
+#[cfg(feature = "panic-to-exception")]
+fn test_panic_to_exception() {
+    use core::panic::AssertUnwindSafe;
+
+    use crate::exception::catch;
+    use crate::{msg_send, msg_send_id};
+
+    let obj: Id<DeclareClassPanic, Owned> =
+        unsafe { msg_send_id![DeclareClassPanic::class(), new] };
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+        catch(AssertUnwindSafe(|| {
+            let _: u32 = msg_send![&obj, panicWith: 7u32];
+        }))
+    }));
+    assert_eq!(*result.unwrap_err().downcast::<u32>().unwrap(), 7);
+}
+
 #[test]
+#[cfg(not(feature = "unstable-objfw"))]
 #[should_panic = "Failed to add ivar _ivar"]
 fn test_duplicate_ivar() {
     declare_class!(
@@ -382,6 +421,7 @@ fn test_duplicate_ivar() {
 }
 
 #[test]
+#[cfg(not(feature = "unstable-objfw"))]
 #[should_panic = "instance variable \"ivar\" already exists on a superclass"]
 fn test_subclass_duplicate_ivar() {
     declare_class!(
@@ -0,0 +1,198 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{add_method_inner, MethodClosure};
+use crate::encode::__unstable::{EncodeArguments, EncodeReturn};
+use crate::ffi;
+use crate::runtime::{Class, Imp, NSZone, Object, Sel};
+use crate::{msg_send, sel};
+
+/// A registered class that is disposed of when dropped.
+///
+/// This is created with [`ClassBuilder::register_disposable`], and is mostly
+/// useful for creating throwaway classes in tests.
+///
+/// The live instances of the class are counted by overriding
+/// `+allocWithZone:` and `-dealloc`, which means that instances must be
+/// created through those methods (like `+alloc` and `+new` do) for the count
+/// to be correct.
+///
+/// [`ClassBuilder::register_disposable`]: super::ClassBuilder::register_disposable
+pub struct DisposableClass {
+    cls: &'static Class,
+    instances: Arc<AtomicUsize>,
+    /// The implementations we created, which are removed again after the
+    /// class has been disposed.
+    imps: Vec<Imp>,
+}
+
+impl DisposableClass {
+    pub(super) fn new(cls: &'static Class) -> Self {
+        let mut this = Self {
+            cls,
+            instances: Arc::new(AtomicUsize::new(0)),
+            imps: Vec::new(),
+        };
+
+        // Root classes may not implement allocation and deallocation, in
+        // which case we don't count instances.
+        let metaclass = cls.metaclass();
+        if metaclass.instance_method(sel!(allocWithZone:)).is_none()
+            || cls.instance_method(sel!(dealloc)).is_none()
+        {
+            return this;
+        }
+
+        let instances = this.instances.clone();
+        let previous = own_implementation(metaclass, sel!(allocWithZone:));
+        let alloc = move |receiver: &Class, zone: *mut NSZone| -> *mut Object {
+            // Note: The receiver may be a subclass, so we must use the
+            // superclass of our class, and not of the receiver.
+            let obj: *mut Object = match previous {
+                Some(imp) => unsafe {
+                    let imp: unsafe extern "C" fn(&Class, Sel, *mut NSZone) -> *mut Object =
+                        mem::transmute(imp);
+                    imp(receiver, sel!(allocWithZone:), zone)
+                },
+                None => unsafe {
+                    let superclass = cls.superclass().unwrap().metaclass();
+                    msg_send![super(receiver, superclass), allocWithZone: zone]
+                },
+            };
+            if !obj.is_null() {
+                instances.fetch_add(1, Ordering::Relaxed);
+            }
+            obj
+        };
+        // SAFETY: The closure has the signature of `+[NSObject allocWithZone:]`.
+        unsafe { this.install(metaclass, sel!(allocWithZone:), alloc) };
+
+        let instances = this.instances.clone();
+        let previous = own_implementation(cls, sel!(dealloc));
+        let dealloc = move |obj: &Object| {
+            instances.fetch_sub(1, Ordering::Relaxed);
+            match previous {
+                Some(imp) => unsafe {
+                    let imp: unsafe extern "C" fn(&Object, Sel) = mem::transmute(imp);
+                    imp(obj, sel!(dealloc))
+                },
+                None => unsafe { msg_send![super(obj, cls.superclass().unwrap()), dealloc] },
+            }
+        };
+        // SAFETY: The closure has the signature of `-[NSObject dealloc]`.
+        unsafe { this.install(cls, sel!(dealloc), dealloc) };
+
+        this
+    }
+
+    /// Add the closure as the implementation of the method on the class,
+    /// replacing the existing implementation if the class has one itself.
+    unsafe fn install<T, A, F>(&mut self, cls: &Class, sel: Sel, closure: F)
+    where
+        T: ?Sized,
+        A: EncodeArguments,
+        F: MethodClosure<T, A>,
+    {
+        if own_implementation(cls, sel).is_some() {
+            let imp = closure.__imp();
+            self.imps.push(imp);
+            let method = cls.instance_method(sel).unwrap();
+            unsafe { method.set_implementation(imp) };
+        } else {
+            unsafe {
+                add_method_inner(
+                    cls.as_ptr() as *mut _,
+                    sel,
+                    A::ENCODINGS,
+                    &F::Ret::ENCODING_RETURN,
+                    || {
+                        let imp = closure.__imp();
+                        self.imps.push(imp);
+                        imp
+                    },
+                )
+            };
+        }
+    }
+
+    /// The number of live instances of the class.
+    pub fn instance_count(&self) -> usize {
+        self.instances.load(Ordering::Relaxed)
+    }
+}
+
+/// The implementation of the method, if the class implements it itself
+/// (instead of inheriting it).
+fn own_implementation(cls: &Class, sel: Sel) -> Option<Imp> {
+    let method = cls.instance_method(sel)?;
+    let inherited = cls
+        .superclass()
+        .and_then(|superclass| superclass.instance_method(sel));
+    if inherited == Some(method) {
+        None
+    } else {
+        Some(method.implementation())
+    }
+}
+
+impl Deref for DisposableClass {
+    type Target = Class;
+
+    fn deref(&self) -> &Class {
+        self.cls
+    }
+}
+
+impl fmt::Debug for DisposableClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DisposableClass")
+            .field("cls", &self.cls)
+            .field("instances", &self.instance_count())
+            .finish()
+    }
+}
+
+impl Drop for DisposableClass {
+    fn drop(&mut self) {
+        let count = self.instance_count();
+        if count != 0 {
+            // Leak the class, disposing it would leave the instances with a
+            // dangling class pointer.
+            if !std::thread::panicking() {
+                panic!(
+                    "cannot dispose class {:?} while it has {count} live instances",
+                    self.cls
+                );
+            }
+            return;
+        }
+
+        // Likewise for subclasses, which refer to the class as their
+        // superclass.
+        #[cfg(feature = "malloc")]
+        {
+            let subclasses = self.cls.subclasses();
+            if !subclasses.is_empty() {
+                if !std::thread::panicking() {
+                    panic!(
+                        "cannot dispose class {:?} while it has subclasses {subclasses:?}",
+                        self.cls
+                    );
+                }
+                return;
+            }
+        }
+
+        unsafe { ffi::objc_disposeClassPair(self.cls.as_ptr() as *mut _) };
+
+        // The class no longer refers to our implementations, so we can
+        // release the closures they were created from.
+        for imp in self.imps.drain(..) {
+            unsafe { ffi::imp_removeBlock(Some(imp)) };
+        }
+    }
+}
@@ -311,6 +311,8 @@ impl<T: IvarType> fmt::Pointer for Ivar<T> {
 }
 
 #[cfg(test)]
+// The tests rely on `test_utils`, which uses ivars and protocols
+#[cfg(not(feature = "unstable-objfw"))]
 mod tests {
     use core::mem;
     use core::panic::{RefUnwindSafe, UnwindSafe};
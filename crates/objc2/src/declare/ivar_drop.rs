@@ -201,6 +201,8 @@ unsafe fn box_unreachable() -> ! {
 }
 
 #[cfg(test)]
+// ObjFW doesn't support adding ivars
+#[cfg(not(feature = "unstable-objfw"))]
 mod tests {
     use super::*;
     use crate::declare::{Ivar, IvarType};
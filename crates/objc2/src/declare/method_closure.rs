@@ -0,0 +1,308 @@
+//! Using Rust closures as method implementations.
+//!
+//! The runtime can turn a block taking the receiver and the method's
+//! arguments into an [`Imp`] with `imp_implementationWithBlock`. We can't
+//! depend on `block2` (since that depends on us), so a minimal block is
+//! constructed here instead.
+//!
+//! The block is created on the stack, and copied to the heap by the runtime,
+//! which then owns the closure until the implementation is removed again.
+use core::ffi::c_void;
+use core::mem::{self, ManuallyDrop};
+use core::ptr;
+use std::os::raw::{c_int, c_ulong};
+
+use crate::encode::__unstable::EncodeReturn;
+use crate::encode::{Encode, Encoding};
+use crate::ffi;
+use crate::runtime::Imp;
+
+extern "C" {
+    // Provided by the blocks runtime; part of `libSystem` on Apple, and of
+    // `libobjc2` itself on GNUstep.
+    static _NSConcreteStackBlock: ffi::objc_class;
+}
+
+const BLOCK_HAS_COPY_DISPOSE: c_int = 1 << 25;
+const BLOCK_USE_STRET: c_int = 1 << 29;
+
+/// Whether a value of type `R` is returned through a pointer passed as a
+/// hidden first argument.
+///
+/// The runtime needs to know this to find the receiver in the arguments that
+/// its trampoline is called with. The rules are the same as those for
+/// choosing `objc_msgSend_stret`, see the `message` module.
+const fn use_stret<R: EncodeReturn>() -> bool {
+    let is_composite = matches!(
+        R::ENCODING_RETURN,
+        Encoding::Struct(_, _) | Encoding::Union(_, _)
+    );
+    let size = mem::size_of::<R>();
+    if cfg!(target_arch = "x86_64") {
+        is_composite && size > 16
+    } else if cfg!(target_arch = "x86") {
+        // Small structs are returned in registers on Apple and Windows, but
+        // all structs are returned in memory elsewhere.
+        if cfg!(any(target_vendor = "apple", windows)) {
+            is_composite && !matches!(size, 0 | 1 | 2 | 4 | 8)
+        } else {
+            is_composite
+        }
+    } else if cfg!(target_arch = "arm") {
+        is_composite && size > 4
+    } else {
+        // AArch64 passes the pointer in a separate register (x8), so the
+        // receiver is in the usual place.
+        false
+    }
+}
+
+#[repr(C)]
+struct BlockDescriptor {
+    reserved: c_ulong,
+    size: c_ulong,
+    copy: unsafe extern "C" fn(*mut c_void, *mut c_void),
+    dispose: unsafe extern "C" fn(*mut c_void),
+}
+
+/// A block with the same layout as a `block2::ConcreteBlock`.
+#[repr(C)]
+struct MethodBlock<F> {
+    isa: *const ffi::objc_class,
+    flags: c_int,
+    reserved: c_int,
+    invoke: unsafe extern "C" fn(),
+    descriptor: *const BlockDescriptor,
+    closure: F,
+}
+
+impl<F: 'static> MethodBlock<F> {
+    const DESCRIPTOR: BlockDescriptor = BlockDescriptor {
+        reserved: 0,
+        size: mem::size_of::<Self>() as c_ulong,
+        copy: block_copy,
+        dispose: block_dispose::<Self>,
+    };
+
+    /// Create an implementation from the closure and a function that
+    /// invokes it.
+    ///
+    /// # Safety
+    ///
+    /// `invoke` must be an `extern "C"` (or `extern "C-unwind"`) function,
+    /// taking `&Self` as the first argument, and otherwise the same
+    /// arguments as the method, and returning `R`.
+    unsafe fn into_imp<R: EncodeReturn>(closure: F, invoke: unsafe extern "C" fn()) -> Imp {
+        let flags = if use_stret::<R>() {
+            BLOCK_HAS_COPY_DISPOSE | BLOCK_USE_STRET
+        } else {
+            BLOCK_HAS_COPY_DISPOSE
+        };
+        let block = ManuallyDrop::new(Self {
+            isa: unsafe { &_NSConcreteStackBlock },
+            flags,
+            reserved: 0,
+            invoke,
+            descriptor: &Self::DESCRIPTOR,
+            closure,
+        });
+        let block: *const Self = &*block;
+        // SAFETY: The block is valid, and is copied to the heap by the
+        // runtime, which then owns the closure (and drops it in our dispose
+        // helper), so we must not drop the stack block.
+        let imp = unsafe { ffi::imp_implementationWithBlock(block as *mut ffi::objc_object) };
+        imp.expect("failed creating implementation from closure")
+    }
+}
+
+unsafe extern "C" fn block_copy(_dst: *mut c_void, _src: *mut c_void) {
+    // The runtime memmoves the src block into the dst block, nothing to do
+}
+
+unsafe extern "C" fn block_dispose<B>(block: *mut c_void) {
+    unsafe { ptr::drop_in_place(block.cast::<B>()) };
+}
+
+mod private {
+    pub trait Sealed<T: ?Sized, A> {}
+}
+
+/// Closures that can be used as the implementation of an Objective-C method.
+///
+/// This is implemented for `Fn` closures that take a reference to the
+/// receiver as their first argument, followed by the method's arguments
+/// (note that, unlike with [`MethodImplementation`], the selector is not
+/// passed).
+///
+/// The closure must be `Send + Sync`, since Objective-C code may invoke the
+/// method from any thread, possibly from several threads at once.
+///
+/// See [`ClassBuilder::add_method_with_closure`] for usage.
+///
+/// [`MethodImplementation`]: super::MethodImplementation
+/// [`ClassBuilder::add_method_with_closure`]: super::ClassBuilder::add_method_with_closure
+pub trait MethodClosure<T: ?Sized, A>: private::Sealed<T, A> + Send + Sync + 'static {
+    /// The return type of the method.
+    type Ret: EncodeReturn;
+
+    #[doc(hidden)]
+    fn __imp(self) -> Imp;
+}
+
+macro_rules! method_closure_impl {
+    ($f:ident $(, $a:ident: $t:ident)*) => {
+        impl<T, R, X, $($t),*> private::Sealed<T, ($($t,)*)> for X
+        where
+            T: ?Sized,
+            R: EncodeReturn,
+            X: Fn(&T $(, $t)*) -> R + Send + Sync + 'static,
+            $($t: Encode,)*
+        {}
+
+        impl<T, R, X, $($t),*> MethodClosure<T, ($($t,)*)> for X
+        where
+            T: ?Sized,
+            R: EncodeReturn,
+            X: Fn(&T $(, $t)*) -> R + Send + Sync + 'static,
+            $($t: Encode,)*
+        {
+            type Ret = R;
+
+            fn __imp(self) -> Imp {
+                crate::__extern_fn! {
+                    () fn $f<T: ?Sized, R, X, $($t),*>(
+                        block: &MethodBlock<X>,
+                        this: &T,
+                        $($a: $t,)*
+                    ) -> R
+                    where
+                        X: Fn(&T $(, $t)*) -> R,
+                    {
+                        crate::__macro_helpers::panic_to_exception(|| (block.closure)(this $(, $a)*))
+                    }
+                }
+
+                let f: crate::__extern_fn!(() fn(&MethodBlock<X>, &T $(, $t)*) -> R) = $f;
+                // SAFETY: Transmuting to a generic function pointer, which
+                // is what the runtime expects.
+                let f: unsafe extern "C" fn() = unsafe { mem::transmute(f) };
+                // SAFETY: The invoke function takes the block as the first
+                // argument, and the method's arguments after that.
+                unsafe { MethodBlock::into_imp::<R>(self, f) }
+            }
+        }
+    };
+}
+
+method_closure_impl!(method_closure_invoke_args0);
+method_closure_impl!(method_closure_invoke_args1, a: A);
+method_closure_impl!(method_closure_invoke_args2, a: A, b: B);
+method_closure_impl!(method_closure_invoke_args3, a: A, b: B, c: C);
+method_closure_impl!(method_closure_invoke_args4, a: A, b: B, c: C, d: D);
+method_closure_impl!(method_closure_invoke_args5, a: A, b: B, c: C, d: D, e: E);
+method_closure_impl!(method_closure_invoke_args6, a: A, b: B, c: C, d: D, e: E, f: F);
+method_closure_impl!(method_closure_invoke_args7, a: A, b: B, c: C, d: D, e: E, f: F, g: G);
+method_closure_impl!(
+    method_closure_invoke_args8,
+    a: A,
+    b: B,
+    c: C,
+    d: D,
+    e: E,
+    f: F,
+    g: G,
+    h: H
+);
+method_closure_impl!(
+    method_closure_invoke_args9,
+    a: A,
+    b: B,
+    c: C,
+    d: D,
+    e: E,
+    f: F,
+    g: G,
+    h: H,
+    i: I
+);
+method_closure_impl!(
+    method_closure_invoke_args10,
+    a: A,
+    b: B,
+    c: C,
+    d: D,
+    e: E,
+    f: F,
+    g: G,
+    h: H,
+    i: I,
+    j: J
+);
+method_closure_impl!(
+    method_closure_invoke_args11,
+    a: A,
+    b: B,
+    c: C,
+    d: D,
+    e: E,
+    f: F,
+    g: G,
+    h: H,
+    i: I,
+    j: J,
+    k: K
+);
+method_closure_impl!(
+    method_closure_invoke_args12,
+    a: A,
+    b: B,
+    c: C,
+    d: D,
+    e: E,
+    f: F,
+    g: G,
+    h: H,
+    i: I,
+    j: J,
+    k: K,
+    l: L
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct Large {
+        a: f64,
+        b: f64,
+        c: f64,
+        d: f64,
+    }
+
+    unsafe impl Encode for Large {
+        const ENCODING: Encoding = Encoding::Struct(
+            "Large",
+            &[
+                Encoding::Double,
+                Encoding::Double,
+                Encoding::Double,
+                Encoding::Double,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_use_stret() {
+        assert!(!use_stret::<()>());
+        assert!(!use_stret::<i32>());
+        assert!(!use_stret::<f64>());
+        assert!(!use_stret::<*const u8>());
+        let expected = cfg!(any(
+            target_arch = "x86_64",
+            target_arch = "x86",
+            target_arch = "arm"
+        ));
+        assert_eq!(use_stret::<Large>(), expected);
+    }
+}
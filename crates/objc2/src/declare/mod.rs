@@ -110,32 +110,48 @@
 
 #[cfg(test)]
 mod declare_class_tests;
+#[cfg(not(feature = "unstable-objfw"))]
+mod disposable;
 mod ivar;
 mod ivar_bool;
 mod ivar_drop;
 mod ivar_encode;
 mod ivar_forwarding_impls;
+#[cfg(not(feature = "unstable-objfw"))]
+mod method_closure;
 
 use alloc::format;
+#[cfg(not(feature = "unstable-objfw"))]
 use alloc::string::ToString;
+#[cfg(not(feature = "unstable-objfw"))]
+use alloc::vec;
+#[cfg(not(feature = "unstable-objfw"))]
+use alloc::vec::Vec;
 use core::mem;
 use core::mem::ManuallyDrop;
 use core::ptr;
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use std::ffi::CString;
 
 use crate::encode::__unstable::{EncodeArguments, EncodeReturn};
 use crate::encode::{Encode, Encoding, RefEncode};
 use crate::ffi;
 use crate::rc::Allocated;
-use crate::runtime::{Bool, Class, Imp, Object, Protocol, Sel};
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::runtime::Protocol;
+use crate::runtime::{Bool, Class, Imp, Object, Sel};
 use crate::sel;
 use crate::Message;
 
+#[cfg(not(feature = "unstable-objfw"))]
+pub use disposable::DisposableClass;
 pub use ivar::{InnerIvarType, Ivar, IvarType};
 pub use ivar_bool::IvarBool;
 pub use ivar_drop::IvarDrop;
 pub use ivar_encode::IvarEncode;
+#[cfg(not(feature = "unstable-objfw"))]
+pub use method_closure::MethodClosure;
 
 pub(crate) mod private {
     pub trait Sealed {}
@@ -155,6 +171,9 @@ pub trait MethodImplementation: private::Sealed {
 
     #[doc(hidden)]
     fn __imp(self) -> Imp;
+
+    #[doc(hidden)]
+    unsafe fn __from_imp(imp: Imp) -> Self;
 }
 
 macro_rules! method_decl_impl {
@@ -179,6 +198,10 @@ macro_rules! method_decl_impl {
             fn __imp(self) -> Imp {
                 unsafe { mem::transmute(self) }
             }
+
+            unsafe fn __from_imp(imp: Imp) -> Self {
+                unsafe { mem::transmute(imp) }
+            }
         }
     };
     (@<$($l:lifetime),*> Class, $r:ident, $f:ty, $($t:ident),*) => {
@@ -200,6 +223,10 @@ macro_rules! method_decl_impl {
             fn __imp(self) -> Imp {
                 unsafe { mem::transmute(self) }
             }
+
+            unsafe fn __from_imp(imp: Imp) -> Self {
+                unsafe { mem::transmute(imp) }
+            }
         }
     };
     (@<> Allocated<T>, $f:ty, $($t:ident),*) => {
@@ -231,6 +258,10 @@ macro_rules! method_decl_impl {
                 // the selector is `init` as checked by `MessageRecieveId`).
                 unsafe { mem::transmute(self) }
             }
+
+            unsafe fn __from_imp(imp: Imp) -> Self {
+                unsafe { mem::transmute(imp) }
+            }
         }
     };
     (# $abi:literal; $($t:ident),*) => {
@@ -294,6 +325,63 @@ fn method_type_encoding(ret: &Encoding, args: &[Encoding]) -> CString {
     CString::new(types).unwrap()
 }
 
+/// Adds a method to a class or metaclass, which may or may not have been
+/// registered yet.
+///
+/// The implementation is only created once the method has been checked.
+pub(crate) unsafe fn add_method_inner(
+    cls: *mut ffi::objc_class,
+    sel: Sel,
+    enc_args: &[Encoding],
+    enc_ret: &Encoding,
+    imp: impl FnOnce() -> Imp,
+) {
+    let sel_args = sel.number_of_arguments();
+    assert_eq!(
+        sel_args,
+        enc_args.len(),
+        "Selector {:?} accepts {} arguments, but function accepts {}",
+        sel,
+        sel_args,
+        enc_args.len(),
+    );
+
+    // SAFETY: The class pointer is valid, though it may not be finalized.
+    let is_metaclass = unsafe { Bool::from_raw(ffi::class_isMetaClass(cls)) }.as_bool();
+
+    // Verify that, if the method is present on the superclass, that the
+    // encoding is correct.
+    //
+    // Note that the superclass of a metaclass is the superclass' metaclass,
+    // so this checks class methods as well (except for the metaclass of root
+    // classes, whose superclass is the root class itself).
+    #[cfg(debug_assertions)]
+    if let Some(superclass) = unsafe { Class::superclass_raw(cls) }
+        .filter(|superclass| superclass.is_metaclass() == is_metaclass)
+    {
+        if let Some(method) = superclass.instance_method(sel) {
+            if let Err(err) = crate::verify::verify_method_signature(method, enc_args, enc_ret) {
+                panic!(
+                    "declared invalid method {}[{} {sel:?}]: {err}",
+                    if is_metaclass { "+" } else { "-" },
+                    unsafe { Class::name_raw(cls) },
+                )
+            }
+        }
+    }
+
+    let types = method_type_encoding(enc_ret, enc_args);
+    let success = Bool::from_raw(unsafe {
+        ffi::class_addMethod(cls, sel.as_ptr(), Some(imp()), types.as_ptr())
+    });
+    if is_metaclass {
+        assert!(success.as_bool(), "Failed to add class method {sel:?}");
+    } else {
+        assert!(success.as_bool(), "Failed to add method {sel:?}");
+    }
+}
+
+#[cfg(not(feature = "unstable-objfw"))]
 fn log2_align_of<T>() -> u8 {
     let align = mem::align_of::<T>();
     // Alignments are required to be powers of 2
@@ -335,7 +423,7 @@ impl ClassBuilder {
     }
 
     #[allow(unused)]
-    fn superclass(&self) -> Option<&Class> {
+    pub(crate) fn superclass(&self) -> Option<&Class> {
         // SAFETY: Though the class is not finalized, `class_getSuperclass` is
         // still safe to call.
         unsafe { Class::superclass_raw(self.cls.as_ptr()) }
@@ -362,6 +450,32 @@ impl ClassBuilder {
         Self::with_superclass(name, Some(superclass))
     }
 
+    /// Constructs a [`ClassBuilder`] with a unique name starting with the
+    /// given prefix, and the given superclass.
+    ///
+    /// This is useful for creating classes that don't need a specific name,
+    /// for example in tests, see also [`register_disposable`].
+    ///
+    /// [`register_disposable`]: Self::register_disposable
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics if the class couldn't be allocated.
+    pub fn new_unique(prefix: &str, superclass: &Class) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        loop {
+            let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let name = format!("{prefix}{n}");
+            if Class::get(&name).is_some() {
+                continue;
+            }
+            return Self::new(&name, superclass)
+                .unwrap_or_else(|| panic!("could not create new class {name}"));
+        }
+    }
+
     /// Constructs a [`ClassBuilder`] declaring a new root class with the
     /// given name.
     ///
@@ -408,41 +522,81 @@ impl ClassBuilder {
         T: Message + ?Sized,
         F: MethodImplementation<Callee = T>,
     {
-        let enc_args = F::Args::ENCODINGS;
-        let enc_ret = F::Ret::ENCODING_RETURN;
-
-        let sel_args = sel.number_of_arguments();
-        assert_eq!(
-            sel_args,
-            enc_args.len(),
-            "Selector {:?} accepts {} arguments, but function accepts {}",
-            sel,
-            sel_args,
-            enc_args.len(),
-        );
-
-        // Verify that, if the method is present on the superclass, that the
-        // encoding is correct.
-        #[cfg(debug_assertions)]
-        if let Some(superclass) = self.superclass() {
-            if let Some(method) = superclass.instance_method(sel) {
-                if let Err(err) = crate::verify::verify_method_signature(method, enc_args, &enc_ret)
-                {
-                    panic!("declared invalid method -[{} {sel:?}]: {err}", self.name())
-                }
-            }
+        unsafe {
+            add_method_inner(
+                self.as_mut_ptr(),
+                sel,
+                F::Args::ENCODINGS,
+                &F::Ret::ENCODING_RETURN,
+                || func.__imp(),
+            )
         }
+    }
 
-        let types = method_type_encoding(&enc_ret, enc_args);
-        let success = Bool::from_raw(unsafe {
-            ffi::class_addMethod(
+    /// Adds a method with the given name, implemented by a closure.
+    ///
+    /// The closure takes a reference to the receiver, followed by the
+    /// method's arguments, and the type encoding of the method is derived
+    /// from those. The closure is kept alive for as long as the class exists.
+    ///
+    /// The closure must be `Send + Sync`, since the method may be invoked
+    /// from any thread, including from several threads at the same time.
+    ///
+    /// This is only available on Apple and GNUstep, since it uses
+    /// `imp_implementationWithBlock`.
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`add_method`][Self::add_method].
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the types match those that are expected
+    /// when the method is invoked from Objective-C.
+    ///
+    /// The closure's `Send + Sync` bounds only cover the closure itself; the
+    /// caller must also ensure that the receiver and arguments are safe to
+    /// use from the threads that the method is invoked from.
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use objc2::declare::ClassBuilder;
+    /// use objc2::rc::{Id, Owned};
+    /// use objc2::runtime::{NSObject, Object};
+    /// use objc2::{msg_send, msg_send_id, sel, ClassType};
+    ///
+    /// let offset = 10;
+    ///
+    /// let mut builder = ClassBuilder::new("AddMethodWithClosureExample", NSObject::class()).unwrap();
+    /// unsafe {
+    ///     builder.add_method_with_closure(sel!(add:), move |_this: &Object, arg: i32| arg + offset);
+    /// }
+    /// let cls = builder.register();
+    ///
+    /// let obj: Id<Object, Owned> = unsafe { msg_send_id![cls, new] };
+    /// let res: i32 = unsafe { msg_send![&obj, add: 5i32] };
+    /// assert_eq!(res, 15);
+    /// ```
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn add_method_with_closure<T, A, F>(&mut self, sel: Sel, closure: F)
+    where
+        T: Message + ?Sized,
+        A: EncodeArguments,
+        F: MethodClosure<T, A>,
+    {
+        unsafe {
+            add_method_inner(
                 self.as_mut_ptr(),
-                sel.as_ptr(),
-                Some(func.__imp()),
-                types.as_ptr(),
+                sel,
+                A::ENCODINGS,
+                &F::Ret::ENCODING_RETURN,
+                || closure.__imp(),
             )
-        });
-        assert!(success.as_bool(), "Failed to add method {sel:?}");
+        }
     }
 
     fn metaclass_mut(&mut self) -> *mut ffi::objc_class {
@@ -465,41 +619,48 @@ impl ClassBuilder {
     where
         F: MethodImplementation<Callee = Class>,
     {
-        let enc_args = F::Args::ENCODINGS;
-        let enc_ret = F::Ret::ENCODING_RETURN;
-
-        let sel_args = sel.number_of_arguments();
-        assert_eq!(
-            sel_args,
-            enc_args.len(),
-            "Selector {:?} accepts {} arguments, but function accepts {}",
-            sel,
-            sel_args,
-            enc_args.len(),
-        );
-
-        // Verify that, if the method is present on the superclass, that the
-        // encoding is correct.
-        #[cfg(debug_assertions)]
-        if let Some(superclass) = self.superclass() {
-            if let Some(method) = superclass.class_method(sel) {
-                if let Err(err) = crate::verify::verify_method_signature(method, enc_args, &enc_ret)
-                {
-                    panic!("declared invalid method +[{} {sel:?}]: {err}", self.name())
-                }
-            }
+        unsafe {
+            add_method_inner(
+                self.metaclass_mut(),
+                sel,
+                F::Args::ENCODINGS,
+                &F::Ret::ENCODING_RETURN,
+                || func.__imp(),
+            )
         }
+    }
 
-        let types = method_type_encoding(&enc_ret, enc_args);
-        let success = Bool::from_raw(unsafe {
-            ffi::class_addMethod(
+    /// Adds a class method with the given name, implemented by a closure.
+    ///
+    /// See [`add_method_with_closure`][Self::add_method_with_closure] for
+    /// details.
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`add_method`][Self::add_method].
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the types match those that are expected
+    /// when the method is invoked from Objective-C, and that the arguments
+    /// are safe to use from the threads that the method is invoked from.
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn add_class_method_with_closure<A, F>(&mut self, sel: Sel, closure: F)
+    where
+        A: EncodeArguments,
+        F: MethodClosure<Class, A>,
+    {
+        unsafe {
+            add_method_inner(
                 self.metaclass_mut(),
-                sel.as_ptr(),
-                Some(func.__imp()),
-                types.as_ptr(),
+                sel,
+                A::ENCODINGS,
+                &F::Ret::ENCODING_RETURN,
+                || closure.__imp(),
             )
-        });
-        assert!(success.as_bool(), "Failed to add class method {sel:?}");
+        }
     }
 
     /// Adds an ivar with type `T` and the provided name.
@@ -509,11 +670,16 @@ impl ClassBuilder {
     ///
     /// If the ivar wasn't successfully added for some reason - this usually
     /// happens if there already was an ivar with that name.
+    ///
+    /// This is not available on ObjFW, since it doesn't support adding ivars
+    /// to classes at runtime.
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn add_ivar<T: Encode>(&mut self, name: &str) {
         // SAFETY: The encoding is correct
         unsafe { self.add_ivar_inner::<T>(name, &T::ENCODING) }
     }
 
+    #[cfg(not(feature = "unstable-objfw"))]
     unsafe fn add_ivar_inner<T>(&mut self, name: &str, encoding: &Encoding) {
         // `class_addIvar` sadly doesn't check this for us.
         //
@@ -531,6 +697,7 @@ impl ClassBuilder {
         let encoding = CString::new(encoding.to_string()).unwrap();
         let size = mem::size_of::<T>();
         let align = log2_align_of::<T>();
+
         let success = Bool::from_raw(unsafe {
             ffi::class_addIvar(
                 self.as_mut_ptr(),
@@ -549,6 +716,7 @@ impl ClassBuilder {
     /// # Panics
     ///
     /// Same as [`ClassBuilder::add_ivar`].
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn add_static_ivar<T: IvarType>(&mut self) {
         // SAFETY: The encoding is correct
         unsafe { self.add_ivar_inner::<T::Type>(T::NAME, &T::Type::ENCODING) }
@@ -559,6 +727,10 @@ impl ClassBuilder {
     /// # Panics
     ///
     /// If the protocol wasn't successfully added.
+    ///
+    /// This is not available on ObjFW, since it doesn't support adding
+    /// protocols to classes at runtime.
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn add_protocol(&mut self, proto: &Protocol) {
         let success = unsafe { ffi::class_addProtocol(self.as_mut_ptr(), proto.as_ptr()) };
         let success = Bool::from_raw(success).as_bool();
@@ -575,6 +747,62 @@ impl ClassBuilder {
         unsafe { ffi::objc_registerClassPair(this.as_mut_ptr()) };
         unsafe { this.cls.cast::<Class>().as_ref() }
     }
+
+    /// Registers the [`ClassBuilder`], consuming it, and returns a handle to
+    /// the newly registered class, which disposes of the class when dropped.
+    ///
+    /// Unlike classes returned from [`register`][Self::register], such
+    /// classes do not live for the rest of the program, which makes them
+    /// useful for creating throwaway classes in tests (together with
+    /// [`new_unique`][Self::new_unique]).
+    ///
+    /// The live instances of the class are counted (see [`DisposableClass`]
+    /// for details).
+    ///
+    /// This is only available on Apple and GNUstep.
+    ///
+    ///
+    /// # Panics
+    ///
+    /// The returned handle panics when dropped if there are live instances
+    /// or (if the `"malloc"` feature is enabled) subclasses of the class, in
+    /// which case the class is leaked instead.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// Other APIs hand out `&'static Class` references, but those are only
+    /// valid until the returned handle is dropped. The caller must ensure
+    /// that the class is not used after that, e.g. through references
+    /// obtained from [`Class::get`], from the `class` of an instance, or as
+    /// the superclass of another class.
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use objc2::declare::ClassBuilder;
+    /// use objc2::rc::Id;
+    /// use objc2::runtime::{Class, NSObject, Object};
+    /// use objc2::{msg_send_id, ClassType};
+    ///
+    /// let builder = ClassBuilder::new_unique("Throwaway", NSObject::class());
+    /// // SAFETY: The class is not used after it is dropped.
+    /// let cls = unsafe { builder.register_disposable() };
+    /// let name = cls.name().to_string();
+    ///
+    /// let obj: Id<Object> = unsafe { msg_send_id![&*cls, new] };
+    /// assert_eq!(cls.instance_count(), 1);
+    /// drop(obj);
+    /// assert_eq!(cls.instance_count(), 0);
+    ///
+    /// drop(cls);
+    /// assert!(Class::get(&name).is_none());
+    /// ```
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn register_disposable(self) -> DisposableClass {
+        DisposableClass::new(self.register())
+    }
 }
 
 impl Drop for ClassBuilder {
@@ -585,30 +813,40 @@ impl Drop for ClassBuilder {
         // Doing it this way is _technically_ a race-condition, since other
         // code could read e.g. `Class::classes()` and then pick the class
         // before it got disposed - but let's not worry about that for now.
-        #[cfg(feature = "gnustep-1-7")]
+        #[cfg(any(feature = "gnustep-1-7", feature = "unstable-objfw"))]
         unsafe {
             ffi::objc_registerClassPair(self.as_mut_ptr());
         }
 
-        unsafe { ffi::objc_disposeClassPair(self.as_mut_ptr()) }
+        // ObjFW doesn't support disposing classes, so there we just leave
+        // the class registered.
+        #[cfg(not(feature = "unstable-objfw"))]
+        unsafe {
+            ffi::objc_disposeClassPair(self.as_mut_ptr())
+        }
     }
 }
 
 /// A type for declaring a new protocol and adding new methods to it
 /// before registering it.
+#[cfg(not(feature = "unstable-objfw"))]
 #[derive(Debug)]
 pub struct ProtocolBuilder {
     proto: NonNull<Protocol>,
 }
 
+#[cfg(not(feature = "unstable-objfw"))]
 #[doc(hidden)]
 #[deprecated = "Use `ProtocolBuilder` instead."]
 pub type ProtocolDecl = ProtocolBuilder;
 
 // SAFETY: Similar to ClassBuilder
+#[cfg(not(feature = "unstable-objfw"))]
 unsafe impl Send for ProtocolBuilder {}
+#[cfg(not(feature = "unstable-objfw"))]
 unsafe impl Sync for ProtocolBuilder {}
 
+#[cfg(not(feature = "unstable-objfw"))]
 impl ProtocolBuilder {
     fn as_mut_ptr(&mut self) -> *mut ffi::objc_protocol {
         self.proto.as_ptr().cast()
@@ -679,6 +917,56 @@ impl ProtocolBuilder {
         }
     }
 
+    /// Adds a property declaration with the type `T`.
+    ///
+    /// `attributes` are the comma-separated attributes of the property,
+    /// excluding the type (`T`), e.g. `R,C` for a read-only property that
+    /// copies its value. See [`Property::attributes_str`] for the format.
+    ///
+    /// [`Property::attributes_str`]: crate::runtime::Property::attributes_str
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name or the attributes contain a NUL byte.
+    #[doc(alias = "protocol_addProperty")]
+    pub fn add_property<T: Encode>(
+        &mut self,
+        name: &str,
+        attributes: &str,
+        is_required: bool,
+        is_instance_property: bool,
+    ) {
+        let c_name = CString::new(name).unwrap();
+
+        let mut strings = vec![(
+            CString::new("T").unwrap(),
+            CString::new(T::ENCODING.to_string()).unwrap(),
+        )];
+        for attribute in attributes.split(',').filter(|s| !s.is_empty()) {
+            let (name, value) = attribute.split_at(attribute.chars().next().unwrap().len_utf8());
+            strings.push((CString::new(name).unwrap(), CString::new(value).unwrap()));
+        }
+        let attributes: Vec<_> = strings
+            .iter()
+            .map(|(name, value)| ffi::objc_property_attribute_t {
+                name: name.as_ptr(),
+                value: value.as_ptr(),
+            })
+            .collect();
+
+        unsafe {
+            ffi::protocol_addProperty(
+                self.as_mut_ptr(),
+                c_name.as_ptr(),
+                attributes.as_ptr(),
+                attributes.len() as _,
+                Bool::new(is_required).as_raw(),
+                Bool::new(is_instance_property).as_raw(),
+            );
+        }
+    }
+
     /// Registers the [`ProtocolBuilder`], consuming it and returning a reference
     /// to the newly registered [`Protocol`].
     pub fn register(mut self) -> &'static Protocol {
@@ -691,11 +979,15 @@ impl ProtocolBuilder {
 
 #[cfg(test)]
 mod tests {
+    // Most tests use ivars or protocols, and are disabled on ObjFW
+    #![cfg_attr(feature = "unstable-objfw", allow(unused_imports))]
+
     use super::*;
     use crate::rc::Id;
     use crate::runtime::{NSObject, NSZone};
+    #[cfg(not(feature = "unstable-objfw"))]
     use crate::test_utils;
-    use crate::{declare_class, extern_protocol, msg_send, ClassType, ProtocolType};
+    use crate::{declare_class, extern_protocol, msg_send, msg_send_id, ClassType, ProtocolType};
 
     extern_protocol!(
         #[allow(clippy::missing_safety_doc)]
@@ -710,6 +1002,7 @@ mod tests {
     );
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_classbuilder_duplicate() {
         let cls = test_utils::custom_class();
         let builder = ClassBuilder::new("TestClassBuilderDuplicate", cls).unwrap();
@@ -719,6 +1012,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[should_panic = "Failed to add ivar xyz"]
     fn duplicate_ivar() {
         let cls = test_utils::custom_class();
@@ -730,6 +1024,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[should_panic = "Failed to add method xyz"]
     fn duplicate_method() {
         let cls = test_utils::custom_class();
@@ -745,9 +1040,10 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[cfg_attr(
         debug_assertions,
-        should_panic = "declared invalid method -[TestClassBuilderInvalidMethod foo]: expected return to have type code 'I', but found 'i'"
+        should_panic = "declared invalid method -[TestClassBuilderInvalidMethod foo]: expected return to have type `u32`, but found `i32`"
     )]
     fn invalid_method() {
         let cls = test_utils::custom_class();
@@ -763,9 +1059,10 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[cfg_attr(
         debug_assertions,
-        should_panic = "declared invalid method +[TestClassBuilderInvalidClassMethod classFoo]: expected return to have type code 'I', but found 'i'"
+        should_panic = "declared invalid method +[TestClassBuilderInvalidClassMethod classFoo]: expected return to have type `u32`, but found `i32`"
     )]
     fn invalid_class_method() {
         let cls = test_utils::custom_class();
@@ -781,6 +1078,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[should_panic = "Failed to add protocol NSObject"]
     fn duplicate_protocol() {
         let cls = test_utils::custom_class();
@@ -794,6 +1092,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_classbuilder_drop() {
         let cls = test_utils::custom_class();
         let builder = ClassBuilder::new("TestClassBuilderDrop", cls).unwrap();
@@ -803,6 +1102,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_custom_class() {
         // Registering the custom class is in test_utils
         let mut obj = test_utils::custom_object();
@@ -812,6 +1112,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[cfg(feature = "malloc")]
     fn test_in_all_classes() {
         fn is_present(cls: *const Class) -> bool {
@@ -837,12 +1138,109 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_class_method() {
         let cls = test_utils::custom_class();
         let result: u32 = unsafe { msg_send![cls, classFoo] };
         assert_eq!(result, 7);
     }
 
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_add_method_with_closure() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        let counter = Arc::new(AtomicU32::new(0));
+
+        let mut builder = ClassBuilder::new("TestClassBuilderClosure", NSObject::class()).unwrap();
+        unsafe {
+            let counter = counter.clone();
+            builder.add_method_with_closure(sel!(addToCounter:), move |_this: &Object, n: u32| {
+                counter.fetch_add(n, Ordering::Relaxed) + n
+            });
+            builder.add_class_method_with_closure(sel!(classFoo), |_cls: &Class| 8u32);
+        }
+        let cls = builder.register();
+
+        let obj: Id<Object> = unsafe { msg_send_id![cls, new] };
+        let res: u32 = unsafe { msg_send![&obj, addToCounter: 2u32] };
+        assert_eq!(res, 2);
+        let res: u32 = unsafe { msg_send![&obj, addToCounter: 3u32] };
+        assert_eq!(res, 5);
+        assert_eq!(counter.load(Ordering::Relaxed), 5);
+
+        let res: u32 = unsafe { msg_send![cls, classFoo] };
+        assert_eq!(res, 8);
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[cfg_attr(
+        debug_assertions,
+        should_panic = "declared invalid method -[TestClassBuilderInvalidClosure foo]: expected return to have type `u32`, but found `i32`"
+    )]
+    fn invalid_method_with_closure() {
+        let cls = test_utils::custom_class();
+        let mut builder = ClassBuilder::new("TestClassBuilderInvalidClosure", cls).unwrap();
+
+        unsafe { builder.add_method_with_closure(sel!(foo), |_this: &Object| 0i32) };
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_new_unique() {
+        let cls = test_utils::custom_class();
+        let a = ClassBuilder::new_unique("TestNewUnique", cls).register();
+        let b = ClassBuilder::new_unique("TestNewUnique", cls).register();
+        assert_ne!(a, b);
+        assert!(a.name().starts_with("TestNewUnique"));
+        assert!(b.name().starts_with("TestNewUnique"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_register_disposable() {
+        let builder = ClassBuilder::new("TestRegisterDisposable", NSObject::class()).unwrap();
+        let cls = unsafe { builder.register_disposable() };
+        assert_eq!(cls.instance_count(), 0);
+
+        let obj1: Id<Object> = unsafe { msg_send_id![&*cls, new] };
+        let obj2: Id<Object> = unsafe { msg_send_id![&*cls, new] };
+        assert_eq!(cls.instance_count(), 2);
+        drop(obj1);
+        assert_eq!(cls.instance_count(), 1);
+        drop(obj2);
+        assert_eq!(cls.instance_count(), 0);
+        drop(cls);
+
+        // The name can be reused after the class has been disposed
+        assert!(Class::get("TestRegisterDisposable").is_none());
+        let builder = ClassBuilder::new("TestRegisterDisposable", NSObject::class()).unwrap();
+        let _cls = unsafe { builder.register_disposable() };
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[should_panic = "while it has 1 live instances"]
+    fn test_register_disposable_live_instance() {
+        let builder = ClassBuilder::new_unique("TestRegisterDisposableLive", NSObject::class());
+        let cls = unsafe { builder.register_disposable() };
+        let obj: Id<Object> = unsafe { msg_send_id![&*cls, new] };
+        mem::forget(obj);
+        drop(cls);
+    }
+
+    #[test]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
+    #[should_panic = "while it has subclasses"]
+    fn test_register_disposable_subclass() {
+        let builder = ClassBuilder::new_unique("TestRegisterDisposableSuper", NSObject::class());
+        let cls = unsafe { builder.register_disposable() };
+        let _subclass = ClassBuilder::new_unique("TestRegisterDisposableSub", &cls).register();
+        drop(cls);
+    }
+
     #[test]
     #[should_panic = "could not create new class TestDeclareClassDuplicate. Perhaps a class with that name already exists?"]
     fn test_declare_class_duplicate() {
@@ -891,10 +1289,101 @@ mod tests {
         assert!(cls.conforms_to(<dyn NSCopying>::protocol().unwrap()));
     }
 
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_declare_protocol() {
+        use crate::declare_protocol;
+        use crate::runtime::NSObjectProtocol;
+
+        declare_protocol!(
+            #[allow(clippy::missing_safety_doc)]
+            #[allow(dead_code)]
+            unsafe trait TestPlugin: NSObjectProtocol + NSCopying {
+                #[method_id(name)]
+                fn name(&self) -> Id<NSObject>;
+
+                #[method(version)]
+                fn version_class() -> u32;
+
+                #[optional]
+                #[method(activateWithPriority:)]
+                fn activate(&self, priority: i32);
+
+                #[method(isEnabled)]
+                fn is_enabled(&self) -> bool;
+
+                #[method(setEnabled:)]
+                fn set_enabled(&self, enabled: bool);
+            }
+
+            unsafe impl ProtocolType for dyn TestPlugin {
+                const NAME: &'static str = "TestDeclareProtocol";
+
+                property name: *mut NSObject = "R,C";
+                #[optional]
+                property enabled: Bool = "N,GisEnabled";
+            }
+        );
+
+        let protocol = <dyn TestPlugin>::protocol().unwrap();
+        assert_eq!(protocol.name(), "TestDeclareProtocol");
+        // Registering is only done once
+        assert_eq!(<dyn TestPlugin>::protocol().unwrap(), protocol);
+
+        assert!(protocol.conforms_to(<NSObject as ProtocolType>::protocol().unwrap()));
+        assert!(protocol.conforms_to(<dyn NSCopying>::protocol().unwrap()));
+
+        let desc = protocol.method_description(sel!(name), true, true).unwrap();
+        assert_eq!(
+            desc.types(),
+            method_type_encoding(&<*mut Object>::ENCODING, &[])
+                .to_str()
+                .unwrap()
+        );
+        assert!(protocol
+            .method_description(sel!(version), true, false)
+            .is_some());
+        assert!(protocol
+            .method_description(sel!(version), true, true)
+            .is_none());
+        assert!(protocol
+            .method_description(sel!(activateWithPriority:), false, true)
+            .is_some());
+        assert!(protocol
+            .method_description(sel!(activateWithPriority:), true, true)
+            .is_none());
+
+        // `bool` is converted to `Bool` in the encoding
+        let desc = protocol
+            .method_description(sel!(isEnabled), true, true)
+            .unwrap();
+        assert_eq!(
+            desc.types(),
+            method_type_encoding(&Bool::ENCODING, &[]).to_str().unwrap()
+        );
+        let desc = protocol
+            .method_description(sel!(setEnabled:), true, true)
+            .unwrap();
+        assert_eq!(
+            desc.types(),
+            method_type_encoding(&Encoding::Void, &[Bool::ENCODING])
+                .to_str()
+                .unwrap()
+        );
+
+        let name = protocol.property("name", true, true).unwrap();
+        let attributes = name.attributes().unwrap();
+        assert!(<*mut NSObject>::ENCODING.equivalent_to_box(attributes.encoding()));
+        assert!(attributes.is_readonly());
+        assert!(attributes.is_copy());
+        let enabled = protocol.property("enabled", false, true).unwrap();
+        assert_eq!(enabled.attributes().unwrap().getter(), sel!(isEnabled));
+    }
+
     #[test]
     #[cfg_attr(
         debug_assertions,
-        should_panic = "declared invalid method -[TestDeclareClassInvalidMethod description]: expected return to have type code '@', but found 'v'"
+        should_panic = "declared invalid method -[TestDeclareClassInvalidMethod description]: expected return to have type `*mut Object`, but found `()`"
     )]
     fn test_declare_class_invalid_method() {
         declare_class!(
@@ -918,7 +1407,7 @@ mod tests {
     #[test]
     #[cfg_attr(
         all(debug_assertions, feature = "verify"),
-        should_panic = "must implement required protocol method -[NSCopying copyWithZone:]"
+        should_panic = "failed implementing protocol NSCopying: missing required protocol method -[NSCopying copyWithZone:]"
     )]
     fn test_declare_class_missing_protocol_method() {
         declare_class!(
@@ -938,7 +1427,10 @@ mod tests {
     }
 
     #[test]
-    // #[cfg_attr(all(debug_assertions, feature = "verify"), should_panic = "...")]
+    #[cfg_attr(
+        all(debug_assertions, feature = "verify"),
+        should_panic = "failed overriding protocol method -[NSCopying copyWithZone:]: expected return to have type `*mut Object`, but found `u8`"
+    )]
     fn test_declare_class_invalid_protocol_method() {
         declare_class!(
             struct Custom;
@@ -989,6 +1481,61 @@ mod tests {
         let _cls = Custom::class();
     }
 
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[cfg_attr(
+        all(debug_assertions, feature = "verify"),
+        should_panic = "failed implementing protocol TestMissingProtocolMethods: missing required protocol methods -[TestMissingProtocolMethods foo], -[TestMissingProtocolMethods bar:], +[TestMissingProtocolMethods baz]"
+    )]
+    fn test_declare_class_missing_multiple_protocol_methods() {
+        use crate::declare_protocol;
+
+        declare_protocol!(
+            #[allow(clippy::missing_safety_doc)]
+            #[allow(dead_code)]
+            unsafe trait TestMissingProtocolMethods {
+                #[method(foo)]
+                fn foo(&self);
+
+                #[method(bar:)]
+                fn bar(&self, arg: i32);
+
+                #[method(baz)]
+                fn baz();
+
+                // Implemented
+                #[method(qux)]
+                fn qux(&self);
+
+                // Inherited from `NSObject`
+                #[method(hash)]
+                fn hash(&self) -> usize;
+
+                #[optional]
+                #[method(optional)]
+                fn optional(&self);
+            }
+
+            unsafe impl ProtocolType for dyn TestMissingProtocolMethods {}
+        );
+
+        declare_class!(
+            struct Custom;
+
+            unsafe impl ClassType for Custom {
+                type Super = NSObject;
+                const NAME: &'static str = "TestDeclareClassMissingMultipleProtocolMethods";
+            }
+
+            unsafe impl TestMissingProtocolMethods for Custom {
+                #[method(qux)]
+                fn qux(&self) {}
+            }
+        );
+
+        let _cls = Custom::class();
+    }
+
     // Proof-of-concept how we could make declare_class! accept generic types.
     #[test]
     fn test_generic() {
@@ -78,6 +78,20 @@ pub trait EncodeConvertArgument: convert_private::Sealed {
     #[doc(hidden)]
     type __StoredBeforeMessage: Sized;
 
+    /// A helper type for out parameters in `declare_class!`, which is
+    /// dropped after the method body has run.
+    #[doc(hidden)]
+    type __DeclaredParamGuard: Sized;
+
+    /// Prepare the parameter for use in a declared method.
+    ///
+    /// # Safety
+    ///
+    /// The parameter must have been passed from Objective-C, and must be
+    /// converted with `__from_declared_param` right afterwards.
+    #[doc(hidden)]
+    unsafe fn __declared_param_guard(inner: &Self::__Inner) -> Self::__DeclaredParamGuard;
+
     #[doc(hidden)]
     fn __from_declared_param(inner: Self::__Inner) -> Self;
 
@@ -106,6 +120,11 @@ impl<T: Encode> EncodeConvertArgument for T {
 
     type __StoredBeforeMessage = ();
 
+    type __DeclaredParamGuard = ();
+
+    #[inline]
+    unsafe fn __declared_param_guard(_inner: &Self::__Inner) -> Self::__DeclaredParamGuard {}
+
     #[inline]
     fn __from_declared_param(inner: Self::__Inner) -> Self {
         inner
@@ -136,6 +155,11 @@ impl EncodeConvertArgument for bool {
 
     type __StoredBeforeMessage = ();
 
+    type __DeclaredParamGuard = ();
+
+    #[inline]
+    unsafe fn __declared_param_guard(_inner: &Self::__Inner) -> Self::__DeclaredParamGuard {}
+
     #[inline]
     fn __from_declared_param(inner: Self::__Inner) -> Self {
         inner.as_bool()
@@ -211,6 +235,14 @@ encode_args_impl!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
 encode_args_impl!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
 encode_args_impl!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
 
+// Only the normal arguments are part of the type encoding of a variadic
+// method.
+impl<A: EncodeArguments, V> args_private::Sealed for crate::message::Variadic<A, V> {}
+
+impl<A: EncodeArguments, V> EncodeArguments for crate::message::Variadic<A, V> {
+    const ENCODINGS: &'static [Encoding] = A::ENCODINGS;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,6 +43,26 @@
 //! assert!(MyStruct::ENCODING_REF.equivalent_to_str("^{MyStruct=fs}"));
 //! ```
 //!
+//! If the `"derive"` feature is enabled, the same can be achieved with
+//! `#[derive(Encode, RefEncode)]`, which computes the encoding from the
+//! fields of the struct:
+//!
+#![cfg_attr(feature = "derive", doc = "```")]
+#![cfg_attr(not(feature = "derive"), doc = "```ignore")]
+//! use objc2::encode::{Encode, RefEncode};
+//!
+//! #[repr(C)]
+//! #[derive(Encode, RefEncode)]
+//! #[encoding(name = "MyStruct")] // Optional, defaults to the Rust name
+//! struct MyStruct {
+//!     a: f32,
+//!     b: i16,
+//! }
+//!
+//! assert!(MyStruct::ENCODING.equivalent_to_str("{MyStruct=fs}"));
+//! assert!(MyStruct::ENCODING_REF.equivalent_to_str("^{MyStruct=fs}"));
+//! ```
+//!
 //! See the [`examples`] folder for more complex usage.
 //!
 //! [`examples`]: https://github.com/madsmtm/objc2/tree/master/crates/objc2/examples
@@ -72,7 +92,11 @@ use core::sync::atomic;
 pub mod __unstable;
 
 #[doc(inline)]
-pub use objc2_encode::{Encoding, EncodingBox, ParseError};
+pub use objc2_encode::{
+    Encoding, EncodingBox, MethodArgument, MethodEncoding, ParseError, Qualifier, Target,
+};
+#[cfg(feature = "derive")]
+pub use objc2_proc_macros::{Encode, RefEncode};
 
 /// Types that have an Objective-C type-encoding.
 ///
@@ -711,6 +735,69 @@ mod tests {
         // assert_eq!(<Option<UnsafeCell<&u8>>>::ENCODING, <&u8>::ENCODING);
     }
 
+    #[test]
+    fn test_size_and_align() {
+        fn assert_layout<T: Encode>() {
+            assert_eq!(
+                T::ENCODING.size_and_align(Target::CURRENT),
+                Some((mem::size_of::<T>(), mem::align_of::<T>())),
+                "{}",
+                T::ENCODING,
+            );
+        }
+
+        assert_layout::<i8>();
+        assert_layout::<u16>();
+        assert_layout::<i32>();
+        assert_layout::<u64>();
+        assert_layout::<isize>();
+        assert_layout::<usize>();
+        assert_layout::<f32>();
+        assert_layout::<f64>();
+        assert_layout::<*const c_void>();
+        assert_layout::<&u8>();
+        assert_layout::<&&i32>();
+        assert_layout::<Option<extern "C" fn()>>();
+        assert_layout::<[u8; 3]>();
+        assert_layout::<[[f64; 2]; 4]>();
+        assert_layout::<AtomicU64>();
+        assert_layout::<NonZeroU32>();
+        assert_layout::<ManuallyDrop<i16>>();
+
+        #[repr(C)]
+        struct Inner {
+            a: u8,
+            b: u64,
+        }
+
+        unsafe impl Encode for Inner {
+            const ENCODING: Encoding = Encoding::Struct("Inner", &[u8::ENCODING, u64::ENCODING]);
+        }
+
+        #[repr(C)]
+        struct Outer {
+            a: i16,
+            inner: Inner,
+            c: [Inner; 2],
+            d: u8,
+        }
+
+        unsafe impl Encode for Outer {
+            const ENCODING: Encoding = Encoding::Struct(
+                "Outer",
+                &[
+                    i16::ENCODING,
+                    Inner::ENCODING,
+                    <[Inner; 2]>::ENCODING,
+                    u8::ENCODING,
+                ],
+            );
+        }
+
+        assert_layout::<Inner>();
+        assert_layout::<Outer>();
+    }
+
     #[test]
     fn test_extern_fn_pointer() {
         assert_eq!(
@@ -7,7 +7,9 @@
 //! preventing Objective-C from unwinding into Rust.
 //!
 //! The `@try`/`@catch` functionality in this module is only available when
-//! the `"exception"` feature is enabled.
+//! the `"exception"` feature is enabled. The hooks for observing thrown and
+//! uncaught exceptions (such as `set_uncaught_exception_handler`) are
+//! available regardless, on the runtimes that support them.
 //!
 //! See the following links for more information:
 //! - [Exception Programming Topics for Cocoa](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/Exceptions/Exceptions.html)
@@ -16,28 +18,68 @@
 //!
 //! [`msg_send!`]: crate::msg_send
 
+#[cfg(feature = "exception")]
+mod backtrace;
+#[cfg(any(feature = "panic-to-exception", all(feature = "exception", apple_new)))]
+mod holder;
+#[cfg(any(apple_new, feature = "unstable-objfw"))]
+mod hooks;
+
+#[cfg(all(feature = "exception", apple_new))]
+pub use self::backtrace::record_backtraces;
+#[cfg(feature = "exception")]
+pub use self::backtrace::Backtrace;
+
+#[cfg(all(apple_new, target_os = "macos"))]
+pub use self::hooks::{add_exception_handler, ExceptionHandlerGuard};
+#[cfg(apple_new)]
+pub use self::hooks::{
+    set_exception_matcher, set_exception_preprocessor, ExceptionMatcherGuard,
+    ExceptionPreprocessorGuard,
+};
+#[cfg(any(apple_new, feature = "unstable-objfw"))]
+pub use self::hooks::{set_uncaught_exception_handler, UncaughtExceptionHandlerGuard};
+
 // TODO: Test this with panic=abort, and ensure that the code-size is
 // reasonable in that case.
 
+#[cfg(feature = "panic-to-exception")]
+use alloc::boxed::Box;
+#[cfg(feature = "panic-to-exception")]
+use alloc::string::String;
+#[cfg(feature = "panic-to-exception")]
+use core::any::Any;
 #[cfg(feature = "exception")]
 use core::ffi::c_void;
 use core::fmt;
 #[cfg(feature = "exception")]
 use core::mem;
 use core::ops::Deref;
+#[cfg(feature = "panic-to-exception")]
+use core::panic::AssertUnwindSafe;
 use core::panic::RefUnwindSafe;
 use core::panic::UnwindSafe;
 #[cfg(feature = "exception")]
 use core::ptr;
 use std::error::Error;
 
+#[cfg(feature = "panic-to-exception")]
+use self::holder::ValueHolder;
 use crate::encode::{Encoding, RefEncode};
 #[cfg(feature = "exception")]
 use crate::ffi;
+#[cfg(feature = "panic-to-exception")]
+use crate::rc::Shared;
 use crate::rc::{autoreleasepool_leaking, Id};
+#[cfg(feature = "panic-to-exception")]
+use crate::runtime::__nsstring::nsstring_new;
 use crate::runtime::__nsstring::nsstring_to_str;
+#[cfg(feature = "panic-to-exception")]
+use crate::runtime::AssociatedKey;
 use crate::runtime::{Class, NSObject, NSObjectProtocol, Object};
 use crate::{extern_methods, sel, Message};
+#[cfg(feature = "panic-to-exception")]
+use crate::{msg_send_id, ClassType};
 
 /// An Objective-C exception.
 ///
@@ -83,6 +125,29 @@ impl Exception {
             Some(false)
         }
     }
+
+    /// The call stack of the point where the exception was thrown, if
+    /// available.
+    ///
+    /// For instances of `NSException`, this is retrieved from
+    /// `callStackReturnAddresses`. Other objects only have a backtrace if it
+    /// was captured with `exception::record_backtraces` while the exception
+    /// was thrown.
+    #[cfg(feature = "exception")]
+    pub fn backtrace(&self) -> Option<Backtrace> {
+        if let Some(true) = self.is_nsexception() {
+            // SAFETY: Just checked that object is an NSException
+            let addresses = unsafe { self.call_stack_return_addresses() };
+            // SAFETY: The array is guaranteed to contain NSNumbers.
+            let backtrace = addresses.map(|array| unsafe { Backtrace::from_nsarray(&array) });
+            if let Some(backtrace) = backtrace {
+                if !backtrace.addresses().is_empty() {
+                    return Some(backtrace);
+                }
+            }
+        }
+        backtrace::recorded_backtrace(self)
+    }
 }
 
 extern_methods!(
@@ -96,6 +161,12 @@ extern_methods!(
         // Returns NSString
         #[method_id(reason)]
         unsafe fn reason(&self) -> Option<Id<NSObject>>;
+
+        // Only safe on NSException
+        // Returns NSArray<NSNumber *>
+        #[cfg(feature = "exception")]
+        #[method_id(callStackReturnAddresses)]
+        unsafe fn call_stack_return_addresses(&self) -> Option<Id<NSObject>>;
     }
 );
 
@@ -129,11 +200,18 @@ impl fmt::Debug for Exception {
                     write!(f, " reason:(NULL)")?;
                 }
                 Ok(())
-            })
+            })?;
         } else {
             // Fall back to `Object` Debug
-            write!(f, "{:?}", self.0)
+            write!(f, "{:?}", self.0)?;
         }
+
+        #[cfg(feature = "exception")]
+        if let Some(backtrace) = self.backtrace() {
+            write!(f, "\nbacktrace:\n{backtrace:?}")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -187,6 +265,98 @@ pub unsafe fn throw(exception: Id<Exception>) -> ! {
     unsafe { ffi::objc_exception_throw(ptr) }
 }
 
+/// The name of the exceptions that panics are converted into.
+#[cfg(feature = "panic-to-exception")]
+const PANIC_EXCEPTION_NAME: &str = "RustPanic";
+
+#[cfg(feature = "panic-to-exception")]
+static PANIC_PAYLOAD: AssociatedKey<Id<ValueHolder, Shared>> = AssociatedKey::new();
+
+#[cfg(feature = "panic-to-exception")]
+fn panic_exception(payload: Box<dyn Any + Send>) -> Id<Exception> {
+    let reason = if let Some(s) = payload.downcast_ref::<&'static str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+
+    let exception: Option<Id<Exception>> = Class::get("NSException").and_then(|cls| {
+        let name = nsstring_new(PANIC_EXCEPTION_NAME)?;
+        let reason = nsstring_new(reason)?;
+        unsafe {
+            msg_send_id![
+                cls,
+                exceptionWithName: &*name,
+                reason: &*reason,
+                userInfo: ptr::null::<Object>(),
+            ]
+        }
+    });
+
+    let holder = ValueHolder::new(payload);
+
+    if let Some(exception) = exception {
+        exception.set_associated(&PANIC_PAYLOAD, Some(holder));
+        exception
+    } else {
+        // If `NSException` is not available, we throw the helper object
+        // directly.
+        // SAFETY: Any object can be thrown as an exception.
+        unsafe { Id::cast(holder) }
+    }
+}
+
+/// Take the panic payload out of the exception, if it was created by
+/// [`panic_to_exception`].
+#[cfg(feature = "panic-to-exception")]
+fn take_panic_payload(exception: &Exception) -> Option<Box<dyn Any + Send>> {
+    // Only panics throw the holder directly.
+    let holder = if exception.class() == ValueHolder::class() {
+        let ptr: *const Exception = exception;
+        // SAFETY: Just checked that the exception is a `ValueHolder`.
+        unsafe { Id::retain(ptr as *mut ValueHolder) }
+    } else {
+        exception.associated(&PANIC_PAYLOAD)
+    }?;
+    holder.take()
+}
+
+/// Runs the given closure, and converts a panic into an Objective-C
+/// exception that is then thrown.
+///
+/// The exception is an `NSException` with the name `RustPanic`, and the panic
+/// message as the reason. The original panic payload is stored alongside the
+/// exception, and when the exception is caught by [`catch`], the panic is
+/// resumed with that payload.
+///
+/// This is done automatically for methods in [`declare_class!`] and for
+/// blocks from `block2` when the `"panic-to-exception"` feature is enabled,
+/// such that panics don't unwind into (or abort) the Objective-C code that
+/// called them.
+///
+/// To make it sound to throw the exception out of those methods and blocks,
+/// the `"panic-to-exception"` feature enables the `"unstable-c-unwind"`
+/// feature, which changes their ABI to `"C-unwind"` (see [RFC-2945]). This
+/// means that the feature currently requires a nightly compiler.
+///
+/// [`declare_class!`]: crate::declare_class
+/// [RFC-2945]: https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html
+///
+///
+/// # Safety
+///
+/// Same as [`throw`], the exception must be caught using an Objective-C
+/// exception handler.
+#[cfg(feature = "panic-to-exception")]
+pub unsafe fn panic_to_exception<R>(closure: impl FnOnce() -> R) -> R {
+    match std::panic::catch_unwind(AssertUnwindSafe(closure)) {
+        Ok(value) => value,
+        Err(payload) => unsafe { throw(panic_exception(payload)) },
+    }
+}
+
 #[cfg(feature = "exception")]
 unsafe fn try_no_ret<F: FnOnce()>(closure: F) -> Result<(), Option<Id<Exception>>> {
     #[cfg(not(feature = "unstable-c-unwind"))]
@@ -262,10 +432,16 @@ unsafe fn try_no_ret<F: FnOnce()>(closure: F) -> Result<(), Option<Id<Exception>
 /// [`catch_unwind`]: std::panic::catch_unwind
 ///
 ///
+/// If the `"panic-to-exception"` feature is enabled, panics in the closure
+/// are converted into exceptions, and exceptions created from panics (see
+/// [`panic_to_exception`]) are resumed as panics instead of being returned.
+///
+///
 /// # Safety
 ///
 /// The given closure must not panic (e.g. normal Rust unwinding into this
-/// causes undefined behaviour).
+/// causes undefined behaviour), unless the `"panic-to-exception"` feature is
+/// enabled.
 ///
 /// Additionally, this unwinds through the closure from Objective-C, which is
 /// undefined behaviour until `C-unwind` is stabilized, see [RFC-2945] - you
@@ -281,7 +457,15 @@ pub unsafe fn catch<R>(
     let closure = move || {
         *value_ref = Some(closure());
     };
+    #[cfg(feature = "panic-to-exception")]
+    let closure = move || unsafe { panic_to_exception(closure) };
     let result = unsafe { try_no_ret(closure) };
+    #[cfg(feature = "panic-to-exception")]
+    if let Err(Some(exception)) = &result {
+        if let Some(payload) = take_panic_payload(exception) {
+            std::panic::resume_unwind(payload);
+        }
+    }
     // If the try succeeded, this was set so it's safe to unwrap
     result.map(|()| value.unwrap())
 }
@@ -294,7 +478,40 @@ mod tests {
 
     use super::*;
     use crate::runtime::NSObject;
-    use crate::{msg_send_id, ClassType};
+    use crate::{declare_class, extern_methods, msg_send, msg_send_id, ClassType};
+
+    declare_class!(
+        struct Thrower;
+
+        unsafe impl ClassType for Thrower {
+            type Super = NSObject;
+            const NAME: &'static str = "__Objc2ExceptionThrower";
+        }
+
+        unsafe impl Thrower {
+            #[method(checkValue:)]
+            fn check_value(value: i32) -> i32 {
+                if value < 0 {
+                    let obj: Id<Exception> = unsafe { msg_send_id![NSObject::class(), new] };
+                    unsafe { throw(obj) }
+                }
+                value
+            }
+
+            #[method(callCheckValue:)]
+            fn call_check_value(value: i32) -> i32 {
+                unsafe { msg_send![Self::class(), checkValue: value] }
+            }
+        }
+    );
+
+    extern_methods!(
+        unsafe impl Thrower {
+            #[method(checkValue:)]
+            #[throws]
+            fn try_check_value(value: i32) -> Result<i32, Id<Exception>>;
+        }
+    );
 
     #[test]
     fn test_catch() {
@@ -340,4 +557,72 @@ mod tests {
 
         assert!(ptr::eq(&*obj, ptr));
     }
+
+    #[test]
+    fn test_msg_send_try() {
+        let cls = Thrower::class();
+        let res: Result<i32, _> = unsafe { msg_send![try cls, checkValue: 5i32] };
+        assert_eq!(res.unwrap(), 5);
+        let res: Result<i32, _> = unsafe { msg_send![try cls, checkValue: -1i32] };
+        assert!(res.is_err());
+
+        let res: Result<Id<NSObject>, _> = unsafe { msg_send_id![try NSObject::class(), new] };
+        assert!(res.is_ok());
+
+        assert_eq!(Thrower::try_check_value(5).unwrap(), 5);
+        assert!(Thrower::try_check_value(-1).is_err());
+
+        let res: Result<i32, _> = unsafe { msg_send![try cls, callCheckValue: 5i32] };
+        assert_eq!(res.unwrap(), 5);
+    }
+
+    /// The exception is raised by a message send made from a Rust method, so
+    /// `"catch-all"` must handle it there instead of letting it unwind
+    /// through the Rust method into the outer `msg_send![try ...]`.
+    #[test]
+    #[cfg(all(feature = "catch-all", feature = "panic-to-exception"))]
+    fn test_msg_send_try_callback_throws() {
+        let cls = Thrower::class();
+        let result = std::panic::catch_unwind(|| {
+            let _: Result<i32, _> = unsafe { msg_send![try cls, callCheckValue: -1i32] };
+        });
+        let payload = result
+            .unwrap_err()
+            .downcast::<alloc::string::String>()
+            .unwrap();
+        assert!(payload.starts_with("uncaught exception"), "{payload}");
+    }
+
+    #[test]
+    #[cfg(feature = "panic-to-exception")]
+    fn test_panic_to_exception() {
+        let result = unsafe { try_no_ret(|| panic_to_exception(|| panic!("my panic"))) };
+        let exception = result.unwrap_err().unwrap();
+        if Class::get("NSException").is_some() {
+            assert_eq!(exception.to_string(), "my panic");
+        }
+
+        let payload = take_panic_payload(&exception).unwrap();
+        assert_eq!(*payload.downcast::<&str>().unwrap(), "my panic");
+        // The payload can only be taken once
+        assert!(take_panic_payload(&exception).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "panic-to-exception")]
+    fn test_catch_resumes_panic() {
+        let result = std::panic::catch_unwind(|| unsafe {
+            catch(|| {
+                panic_to_exception(|| std::panic::panic_any(42u32));
+            })
+        });
+        assert_eq!(*result.unwrap_err().downcast::<u32>().unwrap(), 42);
+
+        // Panics in the closure itself are also transported
+        let result = std::panic::catch_unwind(|| unsafe { catch(|| panic!("in closure")) });
+        assert_eq!(
+            *result.unwrap_err().downcast::<&str>().unwrap(),
+            "in closure"
+        );
+    }
 }
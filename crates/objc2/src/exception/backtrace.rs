@@ -0,0 +1,185 @@
+//! Call stacks of thrown exceptions.
+#[cfg(apple_new)]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+#[cfg(apple_new)]
+use core::ffi::c_void;
+use core::fmt;
+#[cfg(apple_new)]
+use core::ptr;
+#[cfg(apple_new)]
+use std::os::raw::c_int;
+
+use crate::ffi::NSUInteger;
+use crate::msg_send;
+#[cfg(unix)]
+use crate::runtime::dladdr;
+use crate::runtime::NSObject;
+#[cfg(apple_new)]
+use crate::{
+    rc::{Id, Shared},
+    runtime::AssociatedKey,
+};
+
+#[cfg(apple_new)]
+use super::holder::ValueHolder;
+use super::Exception;
+#[cfg(apple_new)]
+use super::{set_exception_preprocessor, ExceptionPreprocessorGuard};
+
+/// The call stack of the point where an exception was thrown.
+///
+/// This is retrieved with [`Exception::backtrace`], and is symbolicated
+/// when formatted with [`Debug`][fmt::Debug].
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Backtrace {
+    addresses: Vec<usize>,
+}
+
+impl Backtrace {
+    /// The return addresses of the frames in the call stack, starting with
+    /// the innermost frame.
+    pub fn addresses(&self) -> &[usize] {
+        &self.addresses
+    }
+
+    /// Read the addresses from an `NSArray<NSNumber *>`, such as the one
+    /// returned by `-[NSException callStackReturnAddresses]`.
+    ///
+    /// # Safety
+    ///
+    /// The array must be an `NSArray` containing `NSNumber`s.
+    pub(super) unsafe fn from_nsarray(array: &NSObject) -> Self {
+        let count: NSUInteger = unsafe { msg_send![array, count] };
+        let addresses = (0..count)
+            .map(|i| {
+                let number: &NSObject = unsafe { msg_send![array, objectAtIndex: i] };
+                let address: NSUInteger = unsafe { msg_send![number, unsignedIntegerValue] };
+                address as usize
+            })
+            .collect();
+        Self { addresses }
+    }
+
+    #[cfg(apple_new)]
+    fn capture() -> Self {
+        extern "C" {
+            // From `execinfo.h`
+            fn backtrace(array: *mut *mut c_void, size: c_int) -> c_int;
+        }
+
+        let mut buf = [ptr::null_mut(); 128];
+        let len = unsafe { backtrace(buf.as_mut_ptr(), buf.len() as c_int) };
+        let addresses = buf[..len.max(0) as usize]
+            .iter()
+            .map(|&address| address as usize)
+            .collect();
+        Self { addresses }
+    }
+}
+
+/// Write the image and the nearest symbol of the given address, if found.
+///
+/// Rust symbols are demangled.
+#[cfg(unix)]
+fn fmt_symbol(f: &mut fmt::Formatter<'_>, address: usize) -> fmt::Result {
+    // SAFETY: The strings are only used in this function, and we assume that
+    // images in the call stack aren't unloaded while it is being formatted.
+    let info = match unsafe { dladdr::lookup(address as *const _) } {
+        Some(info) => info,
+        None => return Ok(()),
+    };
+
+    if let Some(path) = info.image {
+        let path = path.to_string_lossy();
+        let image = path.rsplit('/').next().unwrap_or(&path);
+        write!(f, " {image}")?;
+    }
+    if let Some((name, symbol_address)) = info.symbol {
+        let name = name.to_string_lossy();
+        let offset = address.wrapping_sub(symbol_address);
+        // The alternate format leaves out the hash, like `std` does
+        write!(f, " {:#} + {offset:#x}", rustc_demangle::demangle(&name))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn fmt_symbol(_f: &mut fmt::Formatter<'_>, _address: usize) -> fmt::Result {
+    Ok(())
+}
+
+impl fmt::Debug for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, &address) in self.addresses.iter().enumerate() {
+            write!(f, "{i:>4}: {address:#018x}")?;
+            fmt_symbol(f, address)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(apple_new)]
+static BACKTRACE: AssociatedKey<Id<ValueHolder, Shared>> = AssociatedKey::new();
+
+/// Get the backtrace recorded by [`record_backtraces`], if any.
+#[cfg(apple_new)]
+pub(super) fn recorded_backtrace(exception: &Exception) -> Option<Backtrace> {
+    exception.associated(&BACKTRACE)?.get_cloned()
+}
+
+#[cfg(not(apple_new))]
+pub(super) fn recorded_backtrace(_exception: &Exception) -> Option<Backtrace> {
+    None
+}
+
+/// Record the call stack of every exception that is thrown and which is not
+/// an `NSException`, until the returned guard is dropped.
+///
+/// `NSException` records its call stack by itself, but other objects can be
+/// thrown as well, and this allows [`Exception::backtrace`] to work for
+/// those too (at the cost of capturing a call stack for each such exception).
+///
+/// This is implemented with [`set_exception_preprocessor`].
+///
+///
+/// # Example
+///
+/// ```no_run
+/// use objc2::exception;
+///
+/// let _guard = exception::record_backtraces();
+///
+/// // Exceptions thrown from now on will have a backtrace
+/// ```
+#[cfg(apple_new)]
+pub fn record_backtraces() -> ExceptionPreprocessorGuard {
+    set_exception_preprocessor(|exception| {
+        if exception.is_nsexception() != Some(true) {
+            let holder = ValueHolder::new(Box::new(Backtrace::capture()));
+            exception.set_associated(&BACKTRACE, Some(holder));
+        }
+        exception
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_debug() {
+        let backtrace = Backtrace {
+            addresses: vec![test_debug as fn() as usize, 0],
+        };
+        let s = format!("{backtrace:?}");
+        let mut lines = s.lines();
+        assert!(lines.next().unwrap().starts_with("   0: 0x"));
+        assert_eq!(lines.next().unwrap(), "   1: 0x0000000000000000");
+        assert_eq!(lines.next(), None);
+    }
+}
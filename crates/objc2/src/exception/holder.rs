@@ -0,0 +1,50 @@
+//! A helper object for attaching Rust values to exceptions.
+use alloc::boxed::Box;
+use core::any::Any;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::declare::IvarDrop;
+use crate::rc::{Id, Owned, Shared};
+use crate::runtime::NSObject;
+use crate::{declare_class, msg_send_id, ClassType};
+
+declare_class!(
+    /// Helper object that stores a Rust value, such that it can be attached
+    /// to an exception as an associated object (or thrown directly).
+    pub(super) struct ValueHolder {
+        value: IvarDrop<Option<Box<Mutex<Option<Box<dyn Any + Send>>>>>, "_value">,
+    }
+
+    mod value_holder_ivars;
+
+    unsafe impl ClassType for ValueHolder {
+        type Super = NSObject;
+        const NAME: &'static str = "__ObjC2ValueHolder";
+    }
+);
+
+impl ValueHolder {
+    pub(super) fn new(value: Box<dyn Any + Send>) -> Id<Self, Shared> {
+        let mut holder: Id<Self, Owned> = unsafe { msg_send_id![Self::class(), new] };
+        *holder.value = Some(Box::new(Mutex::new(Some(value))));
+        Id::into_shared(holder)
+    }
+
+    fn lock(&self) -> Option<MutexGuard<'_, Option<Box<dyn Any + Send>>>> {
+        let value = (*self.value).as_ref()?;
+        // The value is always in a consistent state, so poisoning is fine.
+        Some(value.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// Take the value out of the holder, if it hasn't been taken already.
+    #[cfg(feature = "panic-to-exception")]
+    pub(super) fn take(&self) -> Option<Box<dyn Any + Send>> {
+        self.lock()?.take()
+    }
+
+    /// Get a clone of the value, if it has type `T`.
+    #[cfg(apple_new)]
+    pub(super) fn get_cloned<T: Any + Clone>(&self) -> Option<T> {
+        self.lock()?.as_ref()?.downcast_ref::<T>().cloned()
+    }
+}
@@ -0,0 +1,555 @@
+//! Hooks that are run by the runtime when exceptions are thrown.
+//!
+//! The runtime only stores a single function pointer for each hook, so the
+//! Rust closures are stored globally, and the function pointers we install
+//! just look up the current closure.
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+#[cfg(all(apple_new, target_os = "macos"))]
+use core::ffi::c_void;
+use core::fmt;
+#[cfg(all(apple_new, target_os = "macos"))]
+use core::marker::PhantomData;
+use core::panic::AssertUnwindSafe;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(apple_new)]
+use std::os::raw::c_int;
+use std::panic;
+use std::process;
+use std::sync::{Mutex, MutexGuard, Once};
+
+use super::Exception;
+use crate::ffi;
+#[cfg(apple_new)]
+use crate::rc::Id;
+#[cfg(apple_new)]
+use crate::runtime::Class;
+
+type UncaughtExceptionHandler = dyn Fn(&Exception) + Send + Sync;
+#[cfg(apple_new)]
+type ExceptionPreprocessor = dyn Fn(Id<Exception>) -> Id<Exception> + Send + Sync;
+#[cfg(apple_new)]
+type ExceptionMatcher = dyn Fn(&Class, &Exception) -> Option<bool> + Send + Sync;
+
+type RawUncaughtExceptionHandler = unsafe extern "C" fn(*mut ffi::objc_object);
+
+/// The currently installed closures, along with the hooks that were
+/// installed before we installed ours (and which we forward to).
+#[derive(Default)]
+struct Hooks {
+    uncaught: Option<Arc<UncaughtExceptionHandler>>,
+    uncaught_previous: Option<RawUncaughtExceptionHandler>,
+    #[cfg(apple_new)]
+    preprocessor: Option<Arc<ExceptionPreprocessor>>,
+    #[cfg(apple_new)]
+    preprocessor_previous: Option<ffi::objc_exception_preprocessor>,
+    #[cfg(apple_new)]
+    matcher: Option<Arc<ExceptionMatcher>>,
+    #[cfg(apple_new)]
+    matcher_previous: Option<ffi::objc_exception_matcher>,
+}
+
+fn hooks() -> MutexGuard<'static, Hooks> {
+    // TODO: Use `Mutex::new` in the static once our MSRV allows it
+    static HOOKS: AtomicPtr<Mutex<Hooks>> = AtomicPtr::new(ptr::null_mut());
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        let hooks = Box::new(Mutex::new(Hooks::default()));
+        HOOKS.store(Box::into_raw(hooks), Ordering::Release);
+    });
+
+    // SAFETY: Initialized above, and never deallocated.
+    let hooks = unsafe { &*HOOKS.load(Ordering::Acquire) };
+    // The hooks are always in a consistent state, so poisoning is fine.
+    hooks.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Call a closure from one of the hooks, aborting if it panics, since
+/// unwinding into the runtime is undefined behaviour.
+fn abort_on_panic<R>(f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(_) => process::abort(),
+    }
+}
+
+#[cfg(apple_new)]
+fn raw_uncaught(
+    handler: ffi::objc_uncaught_exception_handler,
+) -> Option<RawUncaughtExceptionHandler> {
+    Some(handler)
+}
+
+#[cfg(feature = "unstable-objfw")]
+fn raw_uncaught(
+    handler: ffi::objc_uncaught_exception_handler,
+) -> Option<RawUncaughtExceptionHandler> {
+    handler
+}
+
+unsafe extern "C" fn uncaught_exception_handler(exception: *mut ffi::objc_object) {
+    let (handler, previous) = {
+        let hooks = hooks();
+        (hooks.uncaught.clone(), hooks.uncaught_previous)
+    };
+    // SAFETY: The exception is a valid object or NULL.
+    if let (Some(handler), Some(exception)) =
+        (handler, unsafe { exception.cast::<Exception>().as_ref() })
+    {
+        abort_on_panic(|| handler(exception));
+    }
+    if let Some(previous) = previous {
+        unsafe { previous(exception) };
+    }
+}
+
+/// A guard that restores the previous uncaught exception handler when
+/// dropped.
+///
+/// See [`set_uncaught_exception_handler`].
+#[must_use = "the handler is removed again when the guard is dropped"]
+pub struct UncaughtExceptionHandlerGuard {
+    handler: Option<Arc<UncaughtExceptionHandler>>,
+    previous: ffi::objc_uncaught_exception_handler,
+}
+
+/// Sets a closure that is called with exceptions that are not caught by any
+/// exception handler, right before the program is terminated.
+///
+/// The previously installed handler (e.g. one installed by a crash reporter,
+/// or the runtime's default handler that logs the exception) is called
+/// after the closure.
+///
+/// The handler is removed again when the returned guard is dropped. Nested
+/// guards should be dropped in the reverse order of how they were created.
+///
+/// If the closure panics, the process is aborted.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// use objc2::exception;
+///
+/// let _guard = exception::set_uncaught_exception_handler(|exception| {
+///     eprintln!("uncaught {exception:?}");
+/// });
+/// ```
+#[doc(alias = "objc_setUncaughtExceptionHandler")]
+pub fn set_uncaught_exception_handler<F>(handler: F) -> UncaughtExceptionHandlerGuard
+where
+    F: Fn(&Exception) + Send + Sync + 'static,
+{
+    let mut hooks = hooks();
+    let handler = hooks.uncaught.replace(Arc::new(handler));
+
+    #[cfg(apple_new)]
+    let new = uncaught_exception_handler;
+    #[cfg(feature = "unstable-objfw")]
+    let new = Some(uncaught_exception_handler as RawUncaughtExceptionHandler);
+    let previous = unsafe { ffi::objc_setUncaughtExceptionHandler(new) };
+
+    // Only forward to the previous handler if it isn't our own.
+    let raw_previous = raw_uncaught(previous);
+    if raw_previous.map(|f| f as usize)
+        != Some(uncaught_exception_handler as RawUncaughtExceptionHandler as usize)
+    {
+        hooks.uncaught_previous = raw_previous;
+    }
+
+    UncaughtExceptionHandlerGuard { handler, previous }
+}
+
+impl Drop for UncaughtExceptionHandlerGuard {
+    fn drop(&mut self) {
+        let mut hooks = hooks();
+        hooks.uncaught = self.handler.take();
+        unsafe { ffi::objc_setUncaughtExceptionHandler(self.previous) };
+    }
+}
+
+impl fmt::Debug for UncaughtExceptionHandlerGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UncaughtExceptionHandlerGuard")
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(apple_new)]
+unsafe extern "C" fn exception_preprocessor(
+    exception: *mut ffi::objc_object,
+) -> *mut ffi::objc_object {
+    let (preprocessor, previous) = {
+        let hooks = hooks();
+        (hooks.preprocessor.clone(), hooks.preprocessor_previous)
+    };
+    let mut exception = exception;
+    if let Some(preprocessor) = preprocessor {
+        // SAFETY: The exception is a valid object or NULL.
+        if let Some(obj) = unsafe { Id::<Exception>::retain(exception.cast()) } {
+            let new = abort_on_panic(|| preprocessor(obj));
+            if !ptr::eq(Id::as_ptr(&new), exception.cast()) {
+                // The runtime doesn't take ownership of the returned
+                // exception, so we have to keep it alive until it has been
+                // caught.
+                exception = Id::autorelease_return(new).cast();
+            }
+        }
+    }
+    match previous {
+        Some(previous) => unsafe { previous(exception) },
+        None => exception,
+    }
+}
+
+/// A guard that restores the previous exception preprocessor when dropped.
+///
+/// See [`set_exception_preprocessor`].
+#[cfg(apple_new)]
+#[must_use = "the preprocessor is removed again when the guard is dropped"]
+pub struct ExceptionPreprocessorGuard {
+    preprocessor: Option<Arc<ExceptionPreprocessor>>,
+    previous: ffi::objc_exception_preprocessor,
+}
+
+/// Sets a closure that is called with every exception when it is thrown,
+/// before the stack is unwound.
+///
+/// This includes exceptions thrown by frameworks, regardless of whether
+/// they're caught later on, which makes it useful for logging exceptions,
+/// or for annotating them with extra information. The closure returns the
+/// exception that should be thrown instead, which will usually just be the
+/// exception that was passed in.
+///
+/// The handler is removed again when the returned guard is dropped. Nested
+/// guards should be dropped in the reverse order of how they were created.
+///
+/// Note that the closure must not throw an exception. If it panics, the
+/// process is aborted.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// use objc2::exception;
+///
+/// let _guard = exception::set_exception_preprocessor(|exception| {
+///     eprintln!("thrown {exception:?}");
+///     exception
+/// });
+/// ```
+#[cfg(apple_new)]
+#[doc(alias = "objc_setExceptionPreprocessor")]
+pub fn set_exception_preprocessor<F>(preprocessor: F) -> ExceptionPreprocessorGuard
+where
+    F: Fn(Id<Exception>) -> Id<Exception> + Send + Sync + 'static,
+{
+    let mut hooks = hooks();
+    let preprocessor = hooks.preprocessor.replace(Arc::new(preprocessor));
+    let previous = unsafe { ffi::objc_setExceptionPreprocessor(exception_preprocessor) };
+    if previous as usize != exception_preprocessor as ffi::objc_exception_preprocessor as usize {
+        hooks.preprocessor_previous = Some(previous);
+    }
+    ExceptionPreprocessorGuard {
+        preprocessor,
+        previous,
+    }
+}
+
+#[cfg(apple_new)]
+impl Drop for ExceptionPreprocessorGuard {
+    fn drop(&mut self) {
+        let mut hooks = hooks();
+        hooks.preprocessor = self.preprocessor.take();
+        unsafe { ffi::objc_setExceptionPreprocessor(self.previous) };
+    }
+}
+
+#[cfg(apple_new)]
+impl fmt::Debug for ExceptionPreprocessorGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExceptionPreprocessorGuard")
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(apple_new)]
+unsafe extern "C" fn exception_matcher(
+    catch_type: *mut ffi::objc_class,
+    exception: *mut ffi::objc_object,
+) -> c_int {
+    let (matcher, previous) = {
+        let hooks = hooks();
+        (hooks.matcher.clone(), hooks.matcher_previous)
+    };
+    // SAFETY: The class and the exception are valid or NULL.
+    let cls = unsafe { catch_type.cast::<Class>().as_ref() };
+    let obj = unsafe { exception.cast::<Exception>().as_ref() };
+    if let (Some(matcher), Some(cls), Some(obj)) = (matcher, cls, obj) {
+        if let Some(matches) = abort_on_panic(|| matcher(cls, obj)) {
+            return matches as c_int;
+        }
+    }
+    match previous {
+        Some(previous) => unsafe { previous(catch_type, exception) },
+        None => 0,
+    }
+}
+
+/// A guard that restores the previous exception matcher when dropped.
+///
+/// See [`set_exception_matcher`].
+#[cfg(apple_new)]
+#[must_use = "the matcher is removed again when the guard is dropped"]
+pub struct ExceptionMatcherGuard {
+    matcher: Option<Arc<ExceptionMatcher>>,
+    previous: ffi::objc_exception_matcher,
+}
+
+/// Sets a closure that decides whether an exception handler catching the
+/// given class (e.g. `@catch (NSException *e)`) should catch the exception.
+///
+/// If the closure returns [`None`], the previous matcher decides instead
+/// (by default, that is whether the exception is an instance of the class).
+///
+/// The handler is removed again when the returned guard is dropped. Nested
+/// guards should be dropped in the reverse order of how they were created.
+///
+/// If the closure panics, the process is aborted.
+///
+///
+/// # Safety
+///
+/// The closure must only return `Some(true)` if the exception handler can
+/// handle the exception as-if it was an instance of the given class.
+#[cfg(apple_new)]
+#[doc(alias = "objc_setExceptionMatcher")]
+pub unsafe fn set_exception_matcher<F>(matcher: F) -> ExceptionMatcherGuard
+where
+    F: Fn(&Class, &Exception) -> Option<bool> + Send + Sync + 'static,
+{
+    let mut hooks = hooks();
+    let matcher = hooks.matcher.replace(Arc::new(matcher));
+    let previous = unsafe { ffi::objc_setExceptionMatcher(exception_matcher) };
+    if previous as usize != exception_matcher as ffi::objc_exception_matcher as usize {
+        hooks.matcher_previous = Some(previous);
+    }
+    ExceptionMatcherGuard { matcher, previous }
+}
+
+#[cfg(apple_new)]
+impl Drop for ExceptionMatcherGuard {
+    fn drop(&mut self) {
+        let mut hooks = hooks();
+        hooks.matcher = self.matcher.take();
+        unsafe { ffi::objc_setExceptionMatcher(self.previous) };
+    }
+}
+
+#[cfg(apple_new)]
+impl fmt::Debug for ExceptionMatcherGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExceptionMatcherGuard")
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(apple_new, target_os = "macos"))]
+unsafe extern "C" fn exception_handler(exception: *mut ffi::objc_object, context: *mut c_void) {
+    // SAFETY: The context is the handler we passed in
+    // `add_exception_handler`, which is alive until the handler is removed.
+    let handler = unsafe { &*context.cast::<Box<UncaughtExceptionHandler>>() };
+    // SAFETY: The exception is a valid object or NULL.
+    if let Some(exception) = unsafe { exception.cast::<Exception>().as_ref() } {
+        abort_on_panic(|| handler(exception));
+    }
+}
+
+/// A guard that removes an exception handler when dropped.
+///
+/// See [`add_exception_handler`].
+#[cfg(all(apple_new, target_os = "macos"))]
+#[must_use = "the handler is removed again when the guard is dropped"]
+pub struct ExceptionHandlerGuard {
+    token: usize,
+    handler: *mut Box<UncaughtExceptionHandler>,
+    // The handler is registered for the current thread.
+    p: PhantomData<*mut ()>,
+}
+
+/// Adds a closure that is called when an exception unwinds through the
+/// current stack frame on the current thread, until the returned guard is
+/// dropped.
+///
+/// Unlike [`set_exception_preprocessor`], this is not called for exceptions
+/// that are caught before reaching the frame.
+///
+/// If the closure panics, the process is aborted.
+#[cfg(all(apple_new, target_os = "macos"))]
+#[doc(alias = "objc_addExceptionHandler")]
+pub fn add_exception_handler<F>(handler: F) -> ExceptionHandlerGuard
+where
+    F: Fn(&Exception) + Send + Sync + 'static,
+{
+    let handler: Box<Box<UncaughtExceptionHandler>> = Box::new(Box::new(handler));
+    let handler = Box::into_raw(handler);
+    let token = unsafe { ffi::objc_addExceptionHandler(exception_handler, handler.cast()) };
+    ExceptionHandlerGuard {
+        token,
+        handler,
+        p: PhantomData,
+    }
+}
+
+#[cfg(all(apple_new, target_os = "macos"))]
+impl Drop for ExceptionHandlerGuard {
+    fn drop(&mut self) {
+        unsafe { ffi::objc_removeExceptionHandler(self.token) };
+        // SAFETY: The handler was created with `Box::into_raw`, and the
+        // runtime no longer references it.
+        drop(unsafe { Box::from_raw(self.handler) });
+    }
+}
+
+#[cfg(all(apple_new, target_os = "macos"))]
+impl fmt::Debug for ExceptionHandlerGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExceptionHandlerGuard")
+            .field("token", &self.token)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(apple_new, feature = "exception"))]
+mod tests {
+    use core::sync::atomic::AtomicUsize;
+
+    use alloc::format;
+    use alloc::string::String;
+    use std::env;
+    use std::process::Command;
+
+    use super::*;
+    use crate::exception::{catch, throw};
+    use crate::runtime::NSObject;
+    use crate::{msg_send_id, ClassType};
+
+    fn new_exception() -> Id<Exception> {
+        unsafe { msg_send_id![NSObject::class(), new] }
+    }
+
+    #[test]
+    fn test_uncaught_exception_handler() {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let obj = new_exception();
+        let ptr = Id::as_ptr(&obj) as usize;
+        let guard = set_uncaught_exception_handler(move |exception| {
+            assert_eq!(exception as *const Exception as usize, ptr);
+            COUNT.fetch_add(1, Ordering::Relaxed);
+        });
+
+        // The runtime calls our hook
+        let installed =
+            unsafe { ffi::objc_setUncaughtExceptionHandler(uncaught_exception_handler) };
+        assert_eq!(
+            installed as usize,
+            uncaught_exception_handler as RawUncaughtExceptionHandler as usize
+        );
+
+        // Call the hook like the runtime would for an uncaught exception
+        unsafe { uncaught_exception_handler(Id::as_ptr(&obj) as *mut _) };
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+
+        drop(guard);
+
+        unsafe { uncaught_exception_handler(Id::as_ptr(&obj) as *mut _) };
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_exception_matcher() {
+        let obj = new_exception();
+        let exception = Id::as_ptr(&obj) as *mut ffi::objc_object;
+        let nsobject = NSObject::class() as *const Class as *mut ffi::objc_class;
+        let nsproxy = crate::runtime::__NSProxy::class() as *const Class as *mut ffi::objc_class;
+
+        let guard = unsafe {
+            set_exception_matcher(|cls, _exception| {
+                if cls == crate::runtime::__NSProxy::class() {
+                    Some(true)
+                } else {
+                    None
+                }
+            })
+        };
+
+        // The closure decides for `NSProxy` handlers
+        assert_eq!(unsafe { exception_matcher(nsproxy, exception) }, 1);
+        // Otherwise the default matcher is used
+        assert_eq!(unsafe { exception_matcher(nsobject, exception) }, 1);
+
+        drop(guard);
+
+        assert_eq!(unsafe { exception_matcher(nsproxy, exception) }, 0);
+        assert_eq!(unsafe { exception_matcher(nsobject, exception) }, 1);
+    }
+
+    #[test]
+    fn test_abort_on_panic() {
+        const ENV: &str = "OBJC2_TEST_ABORT_ON_PANIC";
+
+        if env::var_os(ENV).is_some() {
+            // Running in the child process
+            let _guard = unsafe { set_exception_matcher(|_, _| std::panic!("matcher panicked")) };
+            let obj = new_exception();
+            let cls = NSObject::class() as *const Class as *mut ffi::objc_class;
+            unsafe { exception_matcher(cls, Id::as_ptr(&obj) as *mut _) };
+            unreachable!("did not abort");
+        }
+
+        // Re-run this test in a child process, since it aborts
+        let (_, test_name) = module_path!().split_once("::").unwrap();
+        let output = Command::new(env::current_exe().unwrap())
+            .args([&format!("{test_name}::test_abort_on_panic"), "--exact"])
+            .args(["--nocapture", "--test-threads=1"])
+            .env(ENV, "1")
+            .output()
+            .unwrap();
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("matcher panicked"), "{stderr}");
+        assert!(!stderr.contains("did not abort"), "{stderr}");
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            // SIGABRT
+            assert_eq!(output.status.signal(), Some(6));
+        }
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn test_exception_preprocessor() {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let guard = set_exception_preprocessor(|exception| {
+            COUNT.fetch_add(1, Ordering::Relaxed);
+            exception
+        });
+
+        let obj: Id<Exception> = unsafe { msg_send_id![NSObject::class(), new] };
+        let ptr: *const Exception = &*obj;
+        let res = unsafe { catch(|| throw(obj)) }.unwrap_err().unwrap();
+        assert!(ptr::eq(&*res, ptr));
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+
+        drop(guard);
+
+        let _ = unsafe { catch(|| throw(res)) };
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+    }
+}
@@ -187,7 +187,9 @@ pub use objc_sys as ffi;
 pub use self::class_type::ClassType;
 #[doc(no_inline)]
 pub use self::encode::{Encode, Encoding, RefEncode};
-pub use self::message::{Message, MessageArguments, MessageReceiver};
+pub use self::message::{
+    Message, MessageArguments, MessageReceiver, VariadicArgument, VariadicArguments,
+};
 pub use self::protocol_type::ProtocolType;
 
 #[cfg(feature = "objc2-proc-macros")]
@@ -216,8 +218,10 @@ mod protocol_type;
 pub mod rc;
 pub mod runtime;
 #[cfg(test)]
+// ObjFW doesn't support adding ivars or protocols
+#[cfg(not(feature = "unstable-objfw"))]
 mod test_utils;
-mod verify;
+pub mod verify;
 
 // Link to Foundation to make NSObject work
 #[cfg_attr(
@@ -47,8 +47,8 @@ macro_rules! __extract_and_apply_cfg_attributes {
     };
 }
 
-/// Extract `#[method(...)]` or `#[method_id(...)]` and the `#[optional]`
-/// attribute, and send it to another macro.
+/// Extract `#[method(...)]` or `#[method_id(...)]` and the `#[optional]` and
+/// `#[throws]` attributes, and send it to another macro.
 ///
 /// This will ensure that there is one and only one of the method attributes
 /// present.
@@ -61,7 +61,8 @@ macro_rules! __extract_and_apply_cfg_attributes {
 ///
 /// And will call the output macro with the given arguments, along with the
 /// following extra arguments:
-/// - The `method` or `method_id` attribute.
+/// - The `method` or `method_id` attribute, followed by the `throws`
+///   attribute, if any.
 /// - The `optional` attribute, if any.
 /// - The rest of the attributes.
 #[doc(hidden)]
@@ -80,6 +81,7 @@ macro_rules! __extract_custom_attributes {
             ()
             ()
             ()
+            ()
             ($name)
 
             ($out_macro)
@@ -99,6 +101,7 @@ macro_rules! __extract_custom_attributes_inner {
         ()
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -117,6 +120,7 @@ macro_rules! __extract_custom_attributes_inner {
         ($($m_method:tt)*)
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -126,7 +130,7 @@ macro_rules! __extract_custom_attributes_inner {
         $out_macro! {
             $($macro_args)*
             // Append attributes to the end of the macro arguments
-            ($($m_method)*)
+            ($($m_method)* $($m_throws)*)
             ($($m_optional)*)
             ($($m_checked)*)
         }
@@ -142,6 +146,7 @@ macro_rules! __extract_custom_attributes_inner {
         ()
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -153,6 +158,7 @@ macro_rules! __extract_custom_attributes_inner {
             (#[method($($args)*)])
             ($($m_optional)*)
             ($($m_checked)*)
+            ($($m_throws)*)
             ($name)
 
             ($out_macro)
@@ -168,6 +174,7 @@ macro_rules! __extract_custom_attributes_inner {
         ($($m_method:tt)*)
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -188,6 +195,7 @@ macro_rules! __extract_custom_attributes_inner {
         ()
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -199,6 +207,7 @@ macro_rules! __extract_custom_attributes_inner {
             (#[method_id($($args)*)])
             ($($m_optional)*)
             ($($m_checked)*)
+            ($($m_throws)*)
             ($name)
 
             ($out_macro)
@@ -214,6 +223,7 @@ macro_rules! __extract_custom_attributes_inner {
         ($($m_method:tt)*)
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -234,6 +244,7 @@ macro_rules! __extract_custom_attributes_inner {
         // If no existing `optional` attributes exist
         ()
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -245,6 +256,7 @@ macro_rules! __extract_custom_attributes_inner {
             // Add optional attribute
             (#[optional])
             ($($m_checked)*)
+            ($($m_throws)*)
             ($name)
 
             ($out_macro)
@@ -260,6 +272,7 @@ macro_rules! __extract_custom_attributes_inner {
         ($($m_method:tt)*)
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -270,6 +283,55 @@ macro_rules! __extract_custom_attributes_inner {
         }
     };
 
+    // `throws` attribute
+    {
+        (
+            #[throws]
+            $($rest:tt)*
+        )
+        ($($m_method:tt)*)
+        ($($m_optional:tt)*)
+        ($($m_checked:tt)*)
+        // If no existing `throws` attributes exist
+        ()
+        ($name:ident)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $crate::__extract_custom_attributes_inner! {
+            ($($rest)*)
+            ($($m_method)*)
+            ($($m_optional)*)
+            ($($m_checked)*)
+            // Add throws attribute
+            (#[throws])
+            ($name)
+
+            ($out_macro)
+            $($macro_args)*
+        }
+    };
+    // Duplicate `throws` attributes
+    {
+        (
+            #[throws]
+            $($rest:tt)*
+        )
+        ($($m_method:tt)*)
+        ($($m_optional:tt)*)
+        ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
+        ($name:ident)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        fn $name() {
+            compile_error!("cannot specify the `throws` attribute twice")
+        }
+    };
+
     // Other attributes
     {
         (
@@ -279,6 +341,7 @@ macro_rules! __extract_custom_attributes_inner {
         ($($m_method:tt)*)
         ($($m_optional:tt)*)
         ($($m_checked:tt)*)
+        ($($m_throws:tt)*)
         ($name:ident)
 
         ($out_macro:path)
@@ -294,6 +357,7 @@ macro_rules! __extract_custom_attributes_inner {
                 // been consuming the attributes from the front.
                 #[$($checked)*]
             )
+            ($($m_throws)*)
             ($name)
 
             ($out_macro)
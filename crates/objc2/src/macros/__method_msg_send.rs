@@ -1,4 +1,5 @@
-/// Forward selector and arguments to `MessageReceiver::send_message[_error]`.
+/// Forward selector and arguments to the given `MessageReceiver` function
+/// and error function (usually `send_message` and `__send_message_error`).
 ///
 /// Note: We can't forward to `msg_send!` since that doesn't support selectors
 /// with space between.
@@ -7,6 +8,7 @@
 macro_rules! __method_msg_send {
     // Selector with no arguments
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($sel:ident)
         ()
@@ -15,15 +17,31 @@ macro_rules! __method_msg_send {
         ()
     ) => {
         $crate::__msg_send_helper! {
-            @(send_message)
+            @($fn)
             @($receiver)
             @($sel)
             @()
         }
     };
 
+    // Variadic method with error return
+    (
+        ($fn:ident, $error_fn:ident)
+        ($receiver:expr)
+        ($sel:ident : _)
+        ($variadic:ident : ... $(,)?)
+
+        ($($sel_parsed:tt)*)
+        ($($arg_parsed:tt)*)
+    ) => ({
+        $crate::__macro_helpers::compile_error!(
+            "variadic methods cannot use the error `_` parameter"
+        )
+    });
+
     // Parse each argument-selector pair
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($($sel:ident)? : $($sel_rest:tt)*)
         ($arg:ident : $_arg_ty:ty $(, $($args_rest:tt)*)?)
@@ -32,6 +50,7 @@ macro_rules! __method_msg_send {
         ($($arg_parsed:tt)*)
     ) => {
         $crate::__method_msg_send! {
+            ($fn, $error_fn)
             ($receiver)
             ($($sel_rest)*)
             ($($($args_rest)*)?)
@@ -42,6 +61,7 @@ macro_rules! __method_msg_send {
     };
     // Handle path separator token
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($($sel:ident)? :: $($sel_rest:tt)*)
         ($arg1:ident : $_arg_ty1:ty, $arg2:ident : $_arg_ty2:ty $(, $($args_rest:tt)*)?)
@@ -50,6 +70,7 @@ macro_rules! __method_msg_send {
         ($($arg_parsed:tt)*)
     ) => {
         $crate::__method_msg_send! {
+            ($fn, $error_fn)
             ($receiver)
             ($($sel_rest)*)
             ($($($args_rest)*)?)
@@ -61,6 +82,7 @@ macro_rules! __method_msg_send {
 
     // Normal return
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ()
         ()
@@ -71,15 +93,36 @@ macro_rules! __method_msg_send {
         ($($arg_parsed:tt)*)
     ) => {
         $crate::__msg_send_helper! {
-            @(send_message)
+            @($fn)
+            @($receiver)
+            @($($sel_parsed)*)
+            @($($arg_parsed)*)
+        }
+    };
+
+    // Variadic return
+    (
+        ($fn:ident, $error_fn:ident)
+        ($receiver:expr)
+        ()
+        // The variadic arguments after all the normal arguments
+        ($variadic:ident : ... $(,)?)
+
+        ($($sel_parsed:tt)+)
+        ($($arg_parsed:tt)*)
+    ) => {
+        $crate::__msg_send_helper! {
+            @($fn)
             @($receiver)
             @($($sel_parsed)*)
             @($($arg_parsed)*)
+            @($variadic)
         }
     };
 
     // Error return
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         // `sel:_` without a corresponding argument
         ($sel:ident : _)
@@ -90,29 +133,17 @@ macro_rules! __method_msg_send {
     ) => {
         $crate::__msg_send_helper! {
             // Use error method
-            @(__send_message_error)
+            @($error_fn)
             @($receiver)
             @($($sel_parsed)* $sel :)
             @($($arg_parsed)*)
         }
     };
 
-    // Variadic method
-    (
-        ($receiver:expr)
-        ($($sel:ident : _)?)
-        ($($arg:ident :)? ...)
-
-        ($($sel_parsed:tt)*)
-        ($($arg_parsed:tt)*)
-    ) => ({
-        $crate::__macro_helpers::compile_error!(
-            "variadic methods are not yet supported"
-        )
-    });
 
     // Mismatched selector/argument
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($($sel_rest:tt)*)
         ($($args_rest:tt)*)
@@ -132,6 +163,7 @@ macro_rules! __method_msg_send {
 macro_rules! __method_msg_send_id {
     // Selector with no arguments
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($(@__retain_semantics $retain_semantics:ident)? $sel:ident)
         ()
@@ -141,7 +173,7 @@ macro_rules! __method_msg_send_id {
         ()
     ) => {
         $crate::__msg_send_id_helper! {
-            @(send_message_id)
+            @($fn)
             @($receiver)
             @($($retain_semantics)?)
             @($sel)
@@ -154,6 +186,7 @@ macro_rules! __method_msg_send_id {
     // Note: While this is not public, it is still a breaking change to change
     // this, since `icrate` relies on it.
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         (@__retain_semantics $retain_semantics:ident $($sel_rest:tt)*)
         ($($args_rest:tt)*)
@@ -163,6 +196,7 @@ macro_rules! __method_msg_send_id {
         ()
     ) => {
         $crate::__method_msg_send_id! {
+            ($fn, $error_fn)
             ($receiver)
             ($($sel_rest)*)
             ($($args_rest)*)
@@ -173,8 +207,25 @@ macro_rules! __method_msg_send_id {
         }
     };
 
+    // Variadic method with error return
+    (
+        ($fn:ident, $error_fn:ident)
+        ($receiver:expr)
+        ($sel:ident : _)
+        ($variadic:ident : ... $(,)?)
+
+        ($($sel_parsed:tt)*)
+        ($($arg_parsed:tt)*)
+        ($($retain_semantics:ident)?)
+    ) => ({
+        $crate::__macro_helpers::compile_error!(
+            "variadic methods cannot use the error `_` parameter"
+        )
+    });
+
     // Parse each argument-selector pair
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($($sel:ident)? : $($sel_rest:tt)*)
         ($arg:ident : $_arg_ty:ty $(, $($args_rest:tt)*)?)
@@ -184,6 +235,7 @@ macro_rules! __method_msg_send_id {
         ($($retain_semantics:ident)?)
     ) => {
         $crate::__method_msg_send_id! {
+            ($fn, $error_fn)
             ($receiver)
             ($($sel_rest)*)
             ($($($args_rest)*)?)
@@ -195,6 +247,7 @@ macro_rules! __method_msg_send_id {
     };
     // Handle path separator token
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($($sel:ident)? :: $($sel_rest:tt)*)
         ($arg1:ident : $_arg_ty1:ty, $arg2:ident : $_arg_ty2:ty $(, $($args_rest:tt)*)?)
@@ -204,6 +257,7 @@ macro_rules! __method_msg_send_id {
         ($($retain_semantics:ident)?)
     ) => {
         $crate::__method_msg_send_id! {
+            ($fn, $error_fn)
             ($receiver)
             ($($sel_rest)*)
             ($($($args_rest)*)?)
@@ -216,6 +270,7 @@ macro_rules! __method_msg_send_id {
 
     // Normal return
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ()
         ()
@@ -227,7 +282,7 @@ macro_rules! __method_msg_send_id {
         ($($retain_semantics:ident)?)
     ) => {
         $crate::__msg_send_id_helper! {
-            @(send_message_id)
+            @($fn)
             @($receiver)
             @($($retain_semantics)?)
             @($($sel_parsed)*)
@@ -235,44 +290,54 @@ macro_rules! __method_msg_send_id {
         }
     };
 
-    // Error return
+    // Variadic return
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
-        // `sel:_` without a corresponding argument
-        ($sel:ident : _)
         ()
+        // The variadic arguments after all the normal arguments
+        ($variadic:ident : ... $(,)?)
 
-        ($($sel_parsed:tt)*)
+        ($($sel_parsed:tt)+)
         ($($arg_parsed:tt)*)
         ($($retain_semantics:ident)?)
     ) => {
         $crate::__msg_send_id_helper! {
-            // Use error method
-            @(send_message_id_error)
+            @($fn)
             @($receiver)
             @($($retain_semantics)?)
-            @($($sel_parsed)* $sel :)
+            @($($sel_parsed)*)
             @($($arg_parsed)*)
+            @($variadic)
         }
     };
 
-    // Variadic method
+    // Error return
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
-        ($($sel:ident : _)?)
-        ($($arg:ident :)? ...)
+        // `sel:_` without a corresponding argument
+        ($sel:ident : _)
+        ()
 
         ($($sel_parsed:tt)*)
         ($($arg_parsed:tt)*)
         ($($retain_semantics:ident)?)
-    ) => ({
-        $crate::__macro_helpers::compile_error!(
-            "variadic methods are not yet supported"
-        )
-    });
+    ) => {
+        $crate::__msg_send_id_helper! {
+            // Use error method
+            @($error_fn)
+            @($receiver)
+            @($($retain_semantics)?)
+            @($($sel_parsed)* $sel :)
+            @($($arg_parsed)*)
+        }
+    };
+
 
     // Mismatched selector/argument
     (
+        ($fn:ident, $error_fn:ident)
         ($receiver:expr)
         ($($sel_rest:tt)*)
         ($($args_rest:tt)*)
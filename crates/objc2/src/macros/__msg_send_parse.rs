@@ -59,6 +59,38 @@ macro_rules! __msg_send_parse {
             $($macro_args)*
         }
     };
+    // The last `selector: argument` pair may be followed by variadic
+    // arguments, either as `...(a, b, c)` or as `...tuple`.
+    {
+        ($out_macro:path)
+        @($_error_fn:ident)
+        @($($selector_output:tt)*)
+        @($($argument_output:tt)*)
+        @($selector:ident : $argument:expr, ...($($vararg:expr),* $(,)?) $(,)?)
+        $($macro_args:tt)*
+    } => ({
+        $out_macro! {
+            $($macro_args)*
+            @($($selector_output)* $selector:)
+            @($($argument_output)* $argument,)
+            @(($($vararg,)*))
+        }
+    });
+    {
+        ($out_macro:path)
+        @($_error_fn:ident)
+        @($($selector_output:tt)*)
+        @($($argument_output:tt)*)
+        @($selector:ident : $argument:expr, ...$varargs:expr $(,)?)
+        $($macro_args:tt)*
+    } => ({
+        $out_macro! {
+            $($macro_args)*
+            @($($selector_output)* $selector:)
+            @($($argument_output)* $argument,)
+            @($varargs)
+        }
+    });
     {
         ($out_macro:path)
         @($error_fn:ident)
@@ -0,0 +1,154 @@
+/// Parse the arguments of a function, to find the variadic argument
+/// `name: ...`, which must be the last argument.
+///
+/// Will add:
+/// ```ignore
+/// (args*)
+/// (variadic_name?)
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __parse_variadic_arg {
+    // Variadic argument
+    {
+        ($($parsed:tt)*)
+        ($variadic:ident : ... $(,)?)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $out_macro! {
+            $($macro_args)*
+
+            ($($parsed)*)
+            ($variadic)
+        }
+    };
+    {
+        ($($parsed:tt)*)
+        (... $(,)?)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $crate::__macro_helpers::compile_error!(
+            "variadic arguments must be named, e.g. `args: ...`"
+        );
+    };
+
+    // No more arguments
+    {
+        ($($parsed:tt)*)
+        ()
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $out_macro! {
+            $($macro_args)*
+
+            ($($parsed)*)
+            ()
+        }
+    };
+
+    // Receivers without a type
+    {
+        ($($parsed:tt)*)
+        (&mut $self:ident $(, $($rest:tt)*)?)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $crate::__parse_variadic_arg! {
+            ($($parsed)* &mut $self,)
+            ($($($rest)*)?)
+
+            ($out_macro)
+            $($macro_args)*
+        }
+    };
+    {
+        ($($parsed:tt)*)
+        (&$self:ident $(, $($rest:tt)*)?)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $crate::__parse_variadic_arg! {
+            ($($parsed)* &$self,)
+            ($($($rest)*)?)
+
+            ($out_macro)
+            $($macro_args)*
+        }
+    };
+
+    // Normal arguments
+    {
+        ($($parsed:tt)*)
+        (mut $arg:ident : $arg_ty:ty $(, $($rest:tt)*)?)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $crate::__parse_variadic_arg! {
+            ($($parsed)* mut $arg: $arg_ty,)
+            ($($($rest)*)?)
+
+            ($out_macro)
+            $($macro_args)*
+        }
+    };
+    {
+        ($($parsed:tt)*)
+        ($arg:tt : $arg_ty:ty $(, $($rest:tt)*)?)
+
+        ($out_macro:path)
+        $($macro_args:tt)*
+    } => {
+        $crate::__parse_variadic_arg! {
+            ($($parsed)* $arg: $arg_ty,)
+            ($($($rest)*)?)
+
+            ($out_macro)
+            $($macro_args)*
+        }
+    };
+}
+
+/// Replace the variadic argument `name: ...` in a function signature with
+/// `name: impl VariadicArguments`, and forward the function to
+/// `__rewrite_self_arg!`.
+///
+/// Used as the output of `__parse_variadic_arg!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rewrite_variadic_fn {
+    {
+        ($out_macro:path)
+        ($($m:tt)*)
+        ($name:ident)
+        ($($fn_start:tt)*)
+        ($($fn_end:tt)*)
+
+        ($($args:tt)*)
+        ($($variadic:ident)?)
+    } => {
+        $crate::__rewrite_self_arg! {
+            ($($args)* $($variadic: ...)?)
+
+            ($crate::__extract_custom_attributes)
+            ($($m)*)
+            ($name)
+
+            ($out_macro)
+            (
+                $($fn_start)*(
+                    $($args)*
+                    $($variadic: impl $crate::VariadicArguments)?
+                ) $($fn_end)*
+            )
+        }
+    };
+}
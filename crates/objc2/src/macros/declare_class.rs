@@ -84,8 +84,13 @@
 /// make it behave similarly to the Objective-C `BOOL`. Use [`runtime::Bool`]
 /// if you want to control this manually.
 ///
-/// Note that `&mut Id<_, _>` and other such out parameters are not yet
-/// supported, and may generate a panic at runtime.
+/// Out parameters like `&mut Id<_, _>` and `Option<&mut Option<Id<_, _>>>`
+/// are supported as arguments, with the same semantics as described in
+/// [`msg_send!`]; that is, the value passed by the caller is retained before
+/// the method body is run, and the value left in the parameter afterwards is
+/// autoreleased, so that the caller receives it with +0 retain count. As an
+/// example, a method taking an `NSError**` can be declared with an argument
+/// of type `Option<&mut Option<Id<NSError, Shared>>>`.
 ///
 /// ["associated functions"]: https://doc.rust-lang.org/reference/items/associated-items.html#methods
 /// ["methods"]: https://doc.rust-lang.org/reference/items/associated-items.html#methods
@@ -903,6 +908,11 @@ macro_rules! __declare_class_rewrite_args {
             ($($args_converted)* $param : <$param_ty as $crate::encode::__unstable::EncodeConvertArgument>::__Inner,)
             (
                 $($body_prefix)*
+                // Dropped after the method body, to handle out parameters.
+                #[allow(unused_unsafe, clippy::let_unit_value)]
+                let __objc2_guard = unsafe {
+                    <$param_ty as $crate::encode::__unstable::EncodeConvertArgument>::__declared_param_guard(&$param)
+                };
                 let mut $param = <$param_ty as $crate::encode::__unstable::EncodeConvertArgument>::__from_declared_param($param);
             )
 
@@ -924,6 +934,11 @@ macro_rules! __declare_class_rewrite_args {
             ($($args_converted)* $param : <$param_ty as $crate::encode::__unstable::EncodeConvertArgument>::__Inner,)
             (
                 $($body_prefix)*
+                // Dropped after the method body, to handle out parameters.
+                #[allow(unused_unsafe, clippy::let_unit_value)]
+                let __objc2_guard = unsafe {
+                    <$param_ty as $crate::encode::__unstable::EncodeConvertArgument>::__declared_param_guard(&$param)
+                };
                 let $param = <$param_ty as $crate::encode::__unstable::EncodeConvertArgument>::__from_declared_param($param);
             )
 
@@ -952,6 +967,31 @@ macro_rules! __declare_class_rewrite_args {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __declare_class_method_out_inner {
+    // #[throws]
+    {
+        ($($qualifiers:tt)*)
+        ($name:ident)
+        ($($__ret:ty)?)
+        ($__body:block)
+
+        ($__builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+
+        (#[$__method:ident($($__sel:tt)*)] #[throws])
+        ($($__m_optional:tt)*)
+        ($($m_checked:tt)*)
+
+        ($($__args_converted:tt)*)
+        ($($__body_prefix:tt)*)
+    } => {
+        $($m_checked)*
+        $($qualifiers)* extern "C" fn $name() {
+            compile_error!("`#[throws]` is only supported in `extern_methods!`")
+        }
+    };
+
     // #[method(...)]
     {
         ($($qualifiers:tt)*)
@@ -971,14 +1011,18 @@ macro_rules! __declare_class_method_out_inner {
         ($($args_converted:tt)*)
         ($($body_prefix:tt)*)
     } => {
-        $($m_checked)*
-        $($qualifiers)* extern "C" fn $name(
-            $($args_prefix)*
-            $($args_converted)*
-        ) $(-> <$ret as $crate::encode::__unstable::EncodeConvertReturn>::__Inner)? {
-            $($body_prefix)*
-            $crate::__convert_result! {
-                $body $(; $ret)?
+        $crate::__extern_fn! {
+            $($m_checked)*
+            ($($qualifiers)*) fn $name(
+                $($args_prefix)*
+                $($args_converted)*
+            ) $(-> <$ret as $crate::encode::__unstable::EncodeConvertReturn>::__Inner)? {
+                $crate::__macro_helpers::panic_to_exception(move || {
+                    $($body_prefix)*
+                    $crate::__convert_result! {
+                        $body $(; $ret)?
+                    }
+                })
             }
         }
     };
@@ -1002,27 +1046,31 @@ macro_rules! __declare_class_method_out_inner {
         ($($args_converted:tt)*)
         ($($body_prefix:tt)*)
     } => {
-        $($m_checked)*
-        $($qualifiers)* extern "C" fn $name(
-            $($args_prefix)*
-            $($args_converted)*
-        ) -> $crate::declare::__IdReturnValue {
-            $($body_prefix)*
-
-            let __objc2_result = $body;
-
-            #[allow(unreachable_code)]
-            <$crate::__macro_helpers::RetainSemantics<{
-                $crate::__macro_helpers::retain_semantics(
-                    $crate::__sel_helper! {
-                        @()
-                        $($sel)*
-                    }
-                )
-            }> as $crate::__macro_helpers::MessageRecieveId<
-                $receiver_ty,
-                $ret,
-            >>::into_return(__objc2_result)
+        $crate::__extern_fn! {
+            $($m_checked)*
+            ($($qualifiers)*) fn $name(
+                $($args_prefix)*
+                $($args_converted)*
+            ) -> $crate::declare::__IdReturnValue {
+                $crate::__macro_helpers::panic_to_exception(move || {
+                    $($body_prefix)*
+
+                    let __objc2_result = $body;
+
+                    #[allow(unreachable_code)]
+                    <$crate::__macro_helpers::RetainSemantics<{
+                        $crate::__macro_helpers::retain_semantics(
+                            $crate::__sel_helper! {
+                                @()
+                                $($sel)*
+                            }
+                        )
+                    }> as $crate::__macro_helpers::MessageRecieveId<
+                        $receiver_ty,
+                        $ret,
+                    >>::into_return(__objc2_result)
+                })
+            }
         }
     };
 
@@ -1161,6 +1209,25 @@ macro_rules! __declare_class_register_out {
         }
     };
 
+    // #[throws], reported by `__declare_class_method_out_inner!`
+    {
+        ($builder:ident)
+        ($($qualifiers:tt)*)
+        ($name:ident)
+        ($($__ret:ty)?)
+        ($__body:block)
+
+        ($builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($args_rest:tt)*)
+
+        (#[$method:ident($($sel:tt)*)] #[throws])
+        ($($m_optional:tt)*)
+        ($($m_checked:tt)*)
+    } => {};
+
     // #[optional]
     {
         ($builder:ident)
@@ -1221,6 +1288,42 @@ macro_rules! __get_method_id_sel {
     };
 }
 
+/// Create a function or function pointer type with the `"C"` ABI, or the
+/// `"C-unwind"` ABI if the `"unstable-c-unwind"` feature is enabled.
+///
+/// Used for functions that may throw exceptions created by
+/// `panic_to_exception`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "unstable-c-unwind"))]
+macro_rules! __extern_fn {
+    (
+        $(#[$($m:tt)*])*
+        ($($qualifiers:tt)*) fn $($rest:tt)*
+    ) => {
+        $(#[$($m)*])*
+        $($qualifiers)* extern "C" fn $($rest)*
+    };
+}
+
+/// Create a function or function pointer type with the `"C"` ABI, or the
+/// `"C-unwind"` ABI if the `"unstable-c-unwind"` feature is enabled.
+///
+/// Used for functions that may throw exceptions created by
+/// `panic_to_exception`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "unstable-c-unwind")]
+macro_rules! __extern_fn {
+    (
+        $(#[$($m:tt)*])*
+        ($($qualifiers:tt)*) fn $($rest:tt)*
+    ) => {
+        $(#[$($m)*])*
+        $($qualifiers)* extern "C-unwind" fn $($rest)*
+    };
+}
+
 /// Create function pointer type with inferred arguments.
 #[doc(hidden)]
 #[macro_export]
@@ -1230,7 +1333,7 @@ macro_rules! __fn_ptr {
         ($($output:tt)*)
         $(,)?
     ) => {
-        $($qualifiers)* extern "C" fn($($output)*) -> _
+        $crate::__extern_fn!(($($qualifiers)*) fn($($output)*) -> _)
     };
     (
         ($($qualifiers:tt)*)
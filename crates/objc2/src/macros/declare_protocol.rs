@@ -0,0 +1,405 @@
+/// Declare a new Objective-C protocol.
+///
+/// This is similar to a `@protocol` declaration in Objective-C, except that
+/// where [`extern_protocol!`] refers to a protocol that already exists in the
+/// runtime, this macro declares and registers the protocol itself, such that
+/// it can be discovered by Objective-C code (e.g. with
+/// `NSProtocolFromString` or `class_conformsToProtocol`).
+///
+///
+/// # Specification
+///
+/// The syntax and the generated trait and implementations are the same as for
+/// [`extern_protocol!`], see that for details. The difference is that
+/// [`ProtocolType::protocol`] will register the protocol the first time it is
+/// called (unless a protocol with that name already exists, in which case that
+/// is used instead).
+///
+/// The protocol will contain a method description for each method in the
+/// trait, where methods marked `#[optional]` are not required to be
+/// implemented by classes conforming to the protocol. Methods that take a
+/// receiver (e.g. `&self`) are instance methods, and methods that don't are
+/// class methods.
+///
+/// The protocol will also be declared as conforming to the protocols
+/// specified as supertraits, which must themselves be registered.
+///
+/// Finally, properties can be declared in the `unsafe impl ProtocolType`
+/// block with `property name: Type = "attributes";`. The type must implement
+/// [`Encode`], and the attributes are the attribute string of the property
+/// without the type, see [`ProtocolBuilder::add_property`] for details. The
+/// attributes can be left out, and the property can be marked `#[optional]`.
+/// Note that this only declares the property, the corresponding getter (and
+/// setter) methods must still be declared in the trait.
+///
+/// [`extern_protocol!`]: crate::extern_protocol
+/// [`ProtocolType::protocol`]: crate::ProtocolType::protocol
+/// [`Encode`]: crate::Encode
+/// [`ProtocolBuilder::add_property`]: crate::declare::ProtocolBuilder::add_property
+///
+///
+/// # Safety
+///
+/// The following are required for using the macro itself:
+/// - The protocol must actually inherit/conform to the protocols specified
+///   as supertraits.
+/// - The methods and properties must be correctly specified. In particular,
+///   if a protocol with the same name already exists, the methods must match
+///   those of that protocol.
+///
+/// While the following are required when implementing the `unsafe` trait for
+/// a new type:
+/// - The type must represent an object that implements the protocol.
+///
+///
+/// # Examples
+///
+/// Declare a protocol for plugins that Objective-C code can implement and
+/// discover.
+///
+/// ```
+/// use objc2::ffi::NSInteger;
+/// use objc2::rc::Id;
+/// use objc2::runtime::{NSObject, NSObjectProtocol};
+/// use objc2::{declare_protocol, ProtocolType};
+///
+/// // Assume this was correctly defined, as if it came from `icrate`
+/// type NSString = NSObject;
+///
+/// declare_protocol!(
+///     pub unsafe trait MyPlugin: NSObjectProtocol {
+///         #[method_id(name)]
+///         fn name(&self) -> Id<NSString>;
+///
+///         #[method(version)]
+///         fn version_class() -> NSInteger;
+///
+///         #[optional]
+///         #[method(activateWithPriority:)]
+///         fn activate(&self, priority: NSInteger);
+///     }
+///
+///     unsafe impl ProtocolType for dyn MyPlugin {
+///         const NAME: &'static str = "MYPlugin";
+///
+///         // `@property (readonly, copy) NSString *name;`
+///         property name: *mut NSString = "R,C";
+///     }
+/// );
+///
+/// let protocol = <dyn MyPlugin>::protocol().unwrap();
+/// assert_eq!(protocol.name(), "MYPlugin");
+/// ```
+#[doc(alias = "@protocol")]
+#[macro_export]
+macro_rules! declare_protocol {
+    (
+        $(#[$m:meta])*
+        $v:vis unsafe trait $name:ident $(: $conforms_to:ident $(+ $conforms_to_rest:ident)*)? {
+            $($methods:tt)*
+        }
+
+        $(#[$impl_m:meta])*
+        unsafe impl ProtocolType for dyn $for:ident {
+            $(const NAME: &'static str = $name_const:literal;)?
+
+            $(
+                $(#[$prop_optional:ident])?
+                property $prop:ident: $prop_ty:ty $(= $prop_attributes:literal)?;
+            )*
+        }
+    ) => {
+        $(#[$m])*
+        $v unsafe trait $name $(: $conforms_to $(+ $conforms_to_rest)*)? {
+            $crate::__extern_protocol_rewrite_methods! {
+                $($methods)*
+            }
+        }
+
+        $(#[$impl_m])*
+        unsafe impl<T> $name for $crate::runtime::ProtocolObject<T>
+        where
+            T: ?$crate::__macro_helpers::Sized + $crate::ProtocolType + $name
+        {}
+
+        // SAFETY: The protocol is registered with the methods and properties
+        // specified, which are ensured by the caller to be correct.
+        $(#[$impl_m])*
+        unsafe impl ProtocolType for dyn $for {
+            const NAME: &'static $crate::__macro_helpers::str = $crate::__select_name!($name; $($name_const)?);
+            const __INNER: () = ();
+
+            fn protocol() -> $crate::__macro_helpers::Option<&'static $crate::runtime::Protocol> {
+                // TODO: Use `core::cell::LazyCell`
+                static REGISTER_PROTOCOL: $crate::__macro_helpers::Once = $crate::__macro_helpers::Once::new();
+
+                REGISTER_PROTOCOL.call_once(|| {
+                    // If the protocol already exists, we use that instead.
+                    let mut __objc2_builder = match $crate::declare::ProtocolBuilder::new(<Self as ProtocolType>::NAME) {
+                        $crate::__macro_helpers::Some(builder) => builder,
+                        $crate::__macro_helpers::None => return,
+                    };
+
+                    $(
+                        $crate::__declare_protocol_add_protocol!(__objc2_builder, $name, $conforms_to);
+                        $($crate::__declare_protocol_add_protocol!(__objc2_builder, $name, $conforms_to_rest);)*
+                    )?
+
+                    $crate::__declare_protocol_register_methods! {
+                        (__objc2_builder)
+
+                        $($methods)*
+                    }
+
+                    $(
+                        __objc2_builder.add_property::<$prop_ty>(
+                            $crate::__macro_helpers::stringify!($prop),
+                            $crate::__macro_helpers::concat!("" $(, $prop_attributes)?),
+                            $crate::__declare_protocol_is_required!($(#[$prop_optional])?),
+                            true,
+                        );
+                    )*
+
+                    let _ = __objc2_builder.register();
+                });
+
+                $crate::runtime::Protocol::get(<Self as ProtocolType>::NAME)
+            }
+        }
+
+        // SAFETY: Anything that implements the protocol `$name` is valid to
+        // convert to `ProtocolObject<dyn $name>`.
+        $(#[$impl_m])*
+        unsafe impl<T> $crate::runtime::ImplementedBy<T> for dyn $for
+        where
+            T: ?$crate::__macro_helpers::Sized + $crate::Message + $name
+        {
+            const __INNER: () = ();
+        }
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_protocol_add_protocol {
+    // The `NSObject` protocol is represented by `NSObject` itself
+    ($builder:ident, $name:ident, NSObjectProtocol) => {
+        $builder
+            .add_protocol(<$crate::runtime::NSObject as $crate::ProtocolType>::protocol().unwrap());
+    };
+    ($builder:ident, $name:ident, $conforms_to:ident) => {
+        $builder.add_protocol(
+            <dyn $conforms_to as $crate::ProtocolType>::protocol().unwrap_or_else(|| {
+                $crate::__macro_helpers::panic!(
+                    "could not find protocol {}, which {} conforms to",
+                    <dyn $conforms_to as $crate::ProtocolType>::NAME,
+                    $crate::__macro_helpers::stringify!($name),
+                )
+            }),
+        );
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_protocol_is_required {
+    () => {
+        true
+    };
+    (#[optional]) => {
+        false
+    };
+    (#[$($m:tt)*]) => {
+        $crate::__macro_helpers::compile_error!(
+            "only the `#[optional]` attribute is supported on properties"
+        )
+    };
+}
+
+/// tt-munch each protocol method, and add a description of it to the
+/// protocol builder.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_protocol_register_methods {
+    // Base case
+    {
+        ($builder:ident)
+    } => {};
+
+    // Unsafe variant
+    {
+        ($builder:ident)
+
+        $(#[$($m:tt)*])*
+        $v:vis unsafe fn $name:ident($($args:tt)*) $(-> $ret:ty)?;
+
+        $($rest:tt)*
+    } => {
+        $crate::__rewrite_self_arg! {
+            ($($args)*)
+
+            ($crate::__extract_custom_attributes)
+            ($(#[$($m)*])*)
+            ($name)
+
+            ($crate::__declare_protocol_register_out)
+            ($builder)
+            ($($ret)?)
+        }
+
+        $crate::__declare_protocol_register_methods! {
+            ($builder)
+
+            $($rest)*
+        }
+    };
+
+    // Safe variant
+    {
+        ($builder:ident)
+
+        $(#[$($m:tt)*])*
+        $v:vis fn $name:ident($($args:tt)*) $(-> $ret:ty)?;
+
+        $($rest:tt)*
+    } => {
+        $crate::__rewrite_self_arg! {
+            ($($args)*)
+
+            ($crate::__extract_custom_attributes)
+            ($(#[$($m)*])*)
+            ($name)
+
+            ($crate::__declare_protocol_register_out)
+            ($builder)
+            ($($ret)?)
+        }
+
+        $crate::__declare_protocol_register_methods! {
+            ($builder)
+
+            $($rest)*
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_protocol_register_out {
+    // #[method(...)]
+    {
+        ($builder:ident)
+        ($($ret:ty)?)
+
+        ($builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($arg:tt: $arg_ty:ty),* $(,)?)
+
+        (#[method($($sel:tt)*)])
+        ($($m_optional:tt)*)
+        ($($m_checked:tt)*)
+    } => {
+        $crate::__extract_and_apply_cfg_attributes! {
+            @($($m_checked)*)
+            @(
+                $crate::__declare_protocol_method_description! {
+                    ($builder_method)
+                    ($builder)
+                    ($($sel)*)
+                    ($($arg_ty),*)
+                    ($($ret)?)
+                    ($($m_optional)*)
+                }
+            )
+        }
+    };
+
+    // #[method_id(...)]
+    {
+        ($builder:ident)
+        ($($__ret:ty)?)
+
+        ($builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($arg:tt: $arg_ty:ty),* $(,)?)
+
+        (#[method_id($($sel:tt)*)])
+        ($($m_optional:tt)*)
+        ($($m_checked:tt)*)
+    } => {
+        $crate::__extract_and_apply_cfg_attributes! {
+            @($($m_checked)*)
+            @(
+                $crate::__declare_protocol_method_description! {
+                    ($builder_method)
+                    ($builder)
+                    ($($sel)*)
+                    ($($arg_ty),*)
+                    ($crate::declare::__IdReturnValue)
+                    ($($m_optional)*)
+                }
+            )
+        }
+    };
+
+    // #[throws], reported by `__extern_protocol_method_out!`
+    {
+        ($builder:ident)
+        ($($__ret:ty)?)
+
+        ($builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($args_rest:tt)*)
+
+        (#[$method:ident($($sel:tt)*)] #[throws])
+        ($($m_optional:tt)*)
+        ($($m_checked:tt)*)
+    } => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_protocol_method_description {
+    {
+        (add_method)
+        ($builder:ident)
+        ($($sel:tt)*)
+        ($($arg_ty:ty),*)
+        ($($ret:ty)?)
+        ($($m_optional:tt)*)
+    } => {
+        // The return type may be wrapped in parentheses
+        #[allow(unused_parens)]
+        $builder.add_method_description::<
+            ($($arg_ty,)*),
+            <($($ret)?) as $crate::encode::__unstable::EncodeConvertReturn>::__Inner,
+        >(
+            $crate::sel!($($sel)*),
+            $crate::__declare_protocol_is_required!($($m_optional)*),
+        );
+    };
+    {
+        (add_class_method)
+        ($builder:ident)
+        ($($sel:tt)*)
+        ($($arg_ty:ty),*)
+        ($($ret:ty)?)
+        ($($m_optional:tt)*)
+    } => {
+        // The return type may be wrapped in parentheses
+        #[allow(unused_parens)]
+        $builder.add_class_method_description::<
+            ($($arg_ty,)*),
+            <($($ret)?) as $crate::encode::__unstable::EncodeConvertReturn>::__Inner,
+        >(
+            $crate::sel!($($sel)*),
+            $crate::__declare_protocol_is_required!($($m_optional)*),
+        );
+    };
+}
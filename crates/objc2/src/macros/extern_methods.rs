@@ -26,6 +26,18 @@
 /// [`Result`]. See the error section in [`msg_send!`] and [`msg_send_id!`]
 /// for details.
 ///
+/// Variadic methods can be declared by making the last argument
+/// `name: ...`, as in `fn string_with_format(format: &NSString, args: ...)`.
+/// The argument is then exposed as `impl VariadicArguments`, which means it
+/// must be passed as a tuple by the caller. See the variadic section in
+/// [`msg_send!`] for details.
+///
+/// Methods that may throw an exception can be marked with `#[throws]`, in
+/// which case exceptions are caught as with `msg_send![try ...]`, and the
+/// return type must be written as `Result<R, Id<Exception>>`. See the
+/// exceptions section in [`msg_send!`] for details. This requires the
+/// `"exception"` feature.
+///
 /// Putting other attributes on the method such as `cfg`, `allow`, `doc`,
 /// `deprecated` and so on is supported. However, note that `cfg_attr` may not
 /// work correctly, due to implementation difficulty - if you have a concrete
@@ -189,19 +201,26 @@ macro_rules! extern_methods {
     // Non-generic impls
     (
         $(
-            $(#[$impl_m:meta])*
+            $(#[$($impl_m:tt)*])*
             unsafe impl $type:ty {
                 $($methods:tt)*
             }
         )+
     ) => {
         $(
-            $(#[$impl_m])*
+            $(#[$($impl_m)*])*
             impl $type {
                 $crate::__extern_methods_rewrite_methods! {
                     $($methods)*
                 }
             }
+
+            $crate::__extern_methods_register! {
+                ($type)
+                ($(#[$($impl_m)*])*)
+
+                $($methods)*
+            }
         )+
     };
 }
@@ -219,15 +238,16 @@ macro_rules! __extern_methods_rewrite_methods {
 
         $($rest:tt)*
     } => {
-        $crate::__rewrite_self_arg! {
+        $crate::__parse_variadic_arg! {
+            ()
             ($($args)*)
 
-            ($crate::__extract_custom_attributes)
+            ($crate::__rewrite_variadic_fn)
+            ($crate::__extern_methods_method_out)
             ($(#[$($m)*])*)
             ($name)
-
-            ($crate::__extern_methods_method_out)
-            ($v unsafe fn $name($($args)*) $(-> $ret)?)
+            ($v unsafe fn $name)
+            ($(-> $ret)?)
         }
 
         $crate::__extern_methods_rewrite_methods! {
@@ -242,15 +262,16 @@ macro_rules! __extern_methods_rewrite_methods {
 
         $($rest:tt)*
     } => {
-        $crate::__rewrite_self_arg! {
+        $crate::__parse_variadic_arg! {
+            ()
             ($($args)*)
 
-            ($crate::__extract_custom_attributes)
+            ($crate::__rewrite_variadic_fn)
+            ($crate::__extern_methods_method_out)
             ($(#[$($m)*])*)
             ($name)
-
-            ($crate::__extern_methods_method_out)
-            ($v fn $name($($args)*) $(-> $ret)?)
+            ($v fn $name)
+            ($(-> $ret)?)
         }
 
         $crate::__extern_methods_rewrite_methods! {
@@ -295,6 +316,7 @@ macro_rules! __extern_methods_method_out {
             #[allow(unused_unsafe)]
             unsafe {
                 $crate::__method_msg_send! {
+                    (send_message, __send_message_error)
                     ($receiver)
                     ($($sel)*)
                     ($($args_rest)*)
@@ -325,6 +347,7 @@ macro_rules! __extern_methods_method_out {
             #[allow(unused_unsafe)]
             unsafe {
                 $crate::__method_msg_send_id! {
+                    (send_message_id, send_message_id_error)
                     ($receiver)
                     ($($sel)*)
                     ($($args_rest)*)
@@ -337,6 +360,73 @@ macro_rules! __extern_methods_method_out {
         }
     };
 
+    // #[method(...)] #[throws]
+    {
+        ($($function_start:tt)*)
+
+        ($__builder_method:ident)
+        ($receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($args_rest:tt)*)
+
+        (#[method($($sel:tt)*)] #[throws])
+        () // No `optional`
+        ($($m_checked:tt)*)
+    } => {
+        $($m_checked)*
+        $($function_start)* {
+            #[allow(unused_unsafe)]
+            unsafe {
+                $crate::__msg_send_try! {
+                    $crate::__method_msg_send! {
+                        (__send_message_no_catch, __send_message_error_no_catch)
+                        ($receiver)
+                        ($($sel)*)
+                        ($($args_rest)*)
+
+                        ()
+                        ()
+                    }
+                }
+            }
+        }
+    };
+
+    // #[method_id(...)] #[throws]
+    {
+        ($($function_start:tt)*)
+
+        ($__builder_method:ident)
+        ($receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($args_rest:tt)*)
+
+        (#[method_id($($sel:tt)*)] #[throws])
+        () // No `optional`
+        ($($m_checked:tt)*)
+    } => {
+        $($m_checked)*
+        $($function_start)* {
+            #[allow(unused_unsafe)]
+            unsafe {
+                $crate::__msg_send_try! {
+                    $crate::__method_msg_send_id! {
+                        (__send_message_id_no_catch, __send_message_id_error_no_catch)
+                        ($receiver)
+                        ($($sel)*)
+                        ($($args_rest)*)
+
+                        ()
+                        ()
+                        ()
+                    }
+                }
+            }
+        }
+    };
+
     // #[optional]
     {
         ($($function_start:tt)*)
@@ -357,3 +447,324 @@ macro_rules! __extern_methods_method_out {
         }
     };
 }
+
+/// Register each method for `verify::verify_all`.
+///
+/// This is a tt-muncher similar to `__extern_methods_rewrite_methods!`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(all(feature = "verify", unix))]
+macro_rules! __extern_methods_register {
+    // Base case
+    {
+        ($type:ty)
+        ($($impl_m:tt)*)
+    } => {};
+
+    // Method
+    {
+        ($type:ty)
+        ($($impl_m:tt)*)
+
+        $(#[$($m:tt)*])*
+        $v:vis $(unsafe)? fn $name:ident($($args:tt)*) $(-> $ret:ty)?;
+
+        $($rest:tt)*
+    } => {
+        $crate::__parse_variadic_arg! {
+            ()
+            ($($args)*)
+
+            ($crate::__extern_methods_register_fn)
+            ($type)
+            ($($impl_m)*)
+            ($(#[$($m)*])*)
+            ($name)
+            ($($ret)?)
+        }
+
+        $crate::__extern_methods_register! {
+            ($type)
+            ($($impl_m)*)
+
+            $($rest)*
+        }
+    };
+
+    // Other items
+    {
+        ($type:ty)
+        ($($impl_m:tt)*)
+
+        $associated_item:item
+
+        $($rest:tt)*
+    } => {
+        $crate::__extern_methods_register! {
+            ($type)
+            ($($impl_m)*)
+
+            $($rest)*
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(all(feature = "verify", unix)))]
+macro_rules! __extern_methods_register {
+    ($($t:tt)*) => {};
+}
+
+/// The variadic arguments are not part of the type encoding, so they are
+/// not registered.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_register_fn {
+    {
+        ($type:ty)
+        ($($impl_m:tt)*)
+        ($($m:tt)*)
+        ($name:ident)
+        ($($ret:ty)?)
+
+        ($($args:tt)*)
+        ($($_variadic:ident)?)
+    } => {
+        $crate::__rewrite_self_arg! {
+            ($($args)*)
+
+            ($crate::__extract_custom_attributes)
+            ($($m)*)
+            ($name)
+
+            ($crate::__extern_methods_register_out)
+            ($type)
+            ($($impl_m)*)
+            ($($ret)?)
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_register_out {
+    // #[method(...)] or #[method_id(...)]
+    {
+        ($type:ty)
+        ($($impl_m:tt)*)
+        ($($ret:ty)?)
+
+        ($builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($arg:tt: $arg_ty:ty),* $(,)?)
+
+        (#[$method:ident($($sel:tt)*)])
+        () // No `optional`
+        ($($m_checked:tt)*)
+    } => {
+        $crate::__extern_methods_register_sel! {
+            ($($sel)*)
+            ()
+
+            ($type)
+            ($($impl_m)* $($m_checked)*)
+            ($builder_method)
+            ($($arg_ty),*)
+            ($method)
+            ($($ret)?)
+        }
+    };
+
+    // `#[throws]` methods are not registered, since their return type is
+    // wrapped in `Result`.
+    //
+    // Errors are reported by `__extern_methods_method_out!`
+    {
+        $($rest:tt)*
+    } => {};
+}
+
+/// Parse the selector, to figure out whether the method uses the error
+/// convention (`#[method(my:error:_)]`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_register_sel {
+    // Error selector
+    {
+        ($sel:ident : _)
+        ($($sel_parsed:tt)*)
+
+        $($rest:tt)*
+    } => {
+        $crate::__extern_methods_register_method! {
+            ($($sel_parsed)* $sel :)
+            (error)
+            $($rest)*
+        }
+    };
+
+    // Parse each part of the selector
+    {
+        ($($sel:ident)? : $($sel_rest:tt)*)
+        ($($sel_parsed:tt)*)
+
+        $($rest:tt)*
+    } => {
+        $crate::__extern_methods_register_sel! {
+            ($($sel_rest)*)
+            ($($sel_parsed)* $($sel)? :)
+
+            $($rest)*
+        }
+    };
+    // Handle path separator token
+    {
+        ($($sel:ident)? :: $($sel_rest:tt)*)
+        ($($sel_parsed:tt)*)
+
+        $($rest:tt)*
+    } => {
+        $crate::__extern_methods_register_sel! {
+            ($($sel_rest)*)
+            ($($sel_parsed)* $($sel)? : :)
+
+            $($rest)*
+        }
+    };
+
+    // Selector with arguments
+    {
+        ()
+        ($($sel_parsed:tt)+)
+
+        $($rest:tt)*
+    } => {
+        $crate::__extern_methods_register_method! {
+            ($($sel_parsed)*)
+            (normal)
+            $($rest)*
+        }
+    };
+
+    // Selector with no arguments
+    {
+        ($sel:ident)
+        ()
+
+        $($rest:tt)*
+    } => {
+        $crate::__extern_methods_register_method! {
+            ($sel)
+            (normal)
+            $($rest)*
+        }
+    };
+
+    // Invalid selectors are reported by `__extern_methods_method_out!`
+    {
+        ($($sel_rest:tt)*)
+        ($($sel_parsed:tt)*)
+
+        $($rest:tt)*
+    } => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_register_method {
+    {
+        ($($sel:tt)*)
+        ($kind:ident)
+
+        ($type:ty)
+        ($($m:tt)*)
+        ($builder_method:ident)
+        ($($arg_ty:ty),*)
+        ($method:ident)
+        ($($ret:ty)?)
+    } => {
+        const _: () = {
+            $crate::__extract_and_apply_cfg_attributes! {
+                @($($m)*)
+                @(
+                    struct __Objc2Method;
+
+                    impl $crate::__macro_helpers::ExternMethodEncodings<__Objc2Method> for $type {
+                        const ARGS: &'static [$crate::encode::Encoding] =
+                            $crate::__extern_methods_register_args!(($kind) ($($arg_ty),*));
+                        const RET: $crate::encode::Encoding =
+                            $crate::__extern_methods_register_ret!(($kind) ($method) ($($ret)?));
+                    }
+
+                    #[used]
+                    #[cfg_attr(
+                        target_vendor = "apple",
+                        link_section = "__DATA,__objc2_methods,regular,no_dead_strip"
+                    )]
+                    #[cfg_attr(not(target_vendor = "apple"), link_section = "objc2_methods")]
+                    static DECLARATION: $crate::verify::__MethodDeclaration =
+                        $crate::verify::__MethodDeclaration {
+                            class_name: || {
+                                // Only one of these is used, depending on
+                                // whether the type is a `ClassType`.
+                                #[allow(unused_imports)]
+                                use $crate::__macro_helpers::{ClassNameClassType as _, ClassNameFallback as _};
+                                (&&$crate::__macro_helpers::ClassName::<$type>::new()).name()
+                            },
+                            sel: || $crate::sel!($($sel)*),
+                            is_instance_method: $crate::__extern_methods_is_instance_method!($builder_method),
+                            args: <$type as $crate::__macro_helpers::ExternMethodEncodings<__Objc2Method>>::ARGS,
+                            ret: &<$type as $crate::__macro_helpers::ExternMethodEncodings<__Objc2Method>>::RET,
+                            file: $crate::__macro_helpers::file!(),
+                            line: $crate::__macro_helpers::line!(),
+                        };
+                )
+            }
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_register_args {
+    ((normal) ($($arg_ty:ty),*)) => {
+        <($($arg_ty,)*) as $crate::encode::__unstable::EncodeArguments>::ENCODINGS
+    };
+    // The error is passed as an extra `NSError**` argument
+    ((error) ($($arg_ty:ty),*)) => {
+        <($($arg_ty,)* *mut *mut $crate::runtime::Object,) as $crate::encode::__unstable::EncodeArguments>::ENCODINGS
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_register_ret {
+    ((normal) (method) ()) => {
+        $crate::encode::Encoding::Void
+    };
+    ((normal) (method) ($ret:ty)) => {
+        <<$ret as $crate::encode::__unstable::EncodeConvertReturn>::__Inner as $crate::encode::__unstable::EncodeReturn>::ENCODING_RETURN
+    };
+    // Errors are signalled by returning `NO`
+    ((error) (method) ($($ret:ty)?)) => {
+        <$crate::runtime::Bool as $crate::encode::Encode>::ENCODING
+    };
+    // `msg_send_id!` always returns an object (or `NULL`)
+    (($kind:ident) (method_id) ($($ret:ty)?)) => {
+        $crate::encode::Encoding::Object
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extern_methods_is_instance_method {
+    (add_method) => {
+        true
+    };
+    (add_class_method) => {
+        false
+    };
+}
@@ -49,6 +49,7 @@
 /// [`ProtocolObject<dyn T>`]: crate::runtime::ProtocolObject
 /// [`ProtocolType`]: crate::ProtocolType
 /// [`declare_class!`]: crate::declare_class
+/// [`declare_protocol!`]: crate::declare_protocol
 /// [`extern_class!`]: crate::extern_class
 /// [`extern_methods!`]: crate::extern_methods
 ///
@@ -56,7 +57,8 @@
 /// # Safety
 ///
 /// The following are required for using the macro itself:
-/// - The specified name must be an exisiting Objective-C protocol.
+/// - The specified name must be an exisiting Objective-C protocol (use
+///   [`declare_protocol!`] to declare a new protocol).
 /// - The protocol must actually inherit/conform to the protocols specified
 ///   as supertraits.
 /// - The protocol's methods must be correctly specified.
@@ -204,15 +206,16 @@ macro_rules! __extern_protocol_rewrite_methods {
 
         $($rest:tt)*
     } => {
-        $crate::__rewrite_self_arg! {
+        $crate::__parse_variadic_arg! {
+            ()
             ($($args)*)
 
-            ($crate::__extract_custom_attributes)
+            ($crate::__rewrite_variadic_fn)
+            ($crate::__extern_protocol_method_out)
             ($(#[$($m)*])*)
             ($name)
-
-            ($crate::__extern_protocol_method_out)
-            ($v unsafe fn $name($($args)*) $(-> $ret)?)
+            ($v unsafe fn $name)
+            ($(-> $ret)?)
         }
 
         $crate::__extern_protocol_rewrite_methods! {
@@ -227,15 +230,16 @@ macro_rules! __extern_protocol_rewrite_methods {
 
         $($rest:tt)*
     } => {
-        $crate::__rewrite_self_arg! {
+        $crate::__parse_variadic_arg! {
+            ()
             ($($args)*)
 
-            ($crate::__extract_custom_attributes)
+            ($crate::__rewrite_variadic_fn)
+            ($crate::__extern_protocol_method_out)
             ($(#[$($m)*])*)
             ($name)
-
-            ($crate::__extern_protocol_method_out)
-            ($v fn $name($($args)*) $(-> $ret)?)
+            ($v fn $name)
+            ($(-> $ret)?)
         }
 
         $crate::__extern_protocol_rewrite_methods! {
@@ -247,6 +251,29 @@ macro_rules! __extern_protocol_rewrite_methods {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __extern_protocol_method_out {
+    // #[throws]
+    {
+        ($($function_start:tt)*)
+
+        ($__builder_method:ident)
+        ($__receiver:expr)
+        ($__receiver_ty:ty)
+        ($($__args_prefix:tt)*)
+        ($($__args_rest:tt)*)
+
+        (#[$__method:ident($($__sel:tt)*)] #[throws])
+        ($($__m_optional:tt)*)
+        ($($m_checked:tt)*)
+    } => {
+        $($m_checked)*
+        $($function_start)*
+        where
+            Self: $crate::__macro_helpers::Sized
+        {
+            compile_error!("`#[throws]` is only supported in `extern_methods!`")
+        }
+    };
+
     // Instance #[method(...)]
     {
         ($($function_start:tt)*)
@@ -269,6 +296,7 @@ macro_rules! __extern_protocol_method_out {
             #[allow(unused_unsafe)]
             unsafe {
                 $crate::__method_msg_send! {
+                    (send_message, __send_message_error)
                     ($receiver)
                     ($($sel)*)
                     ($($args_rest)*)
@@ -302,6 +330,7 @@ macro_rules! __extern_protocol_method_out {
             #[allow(unused_unsafe)]
             unsafe {
                 $crate::__method_msg_send_id! {
+                    (send_message_id, send_message_id_error)
                     ($receiver)
                     ($($sel)*)
                     ($($args_rest)*)
@@ -336,6 +365,7 @@ macro_rules! __extern_protocol_method_out {
             #[allow(unused_unsafe)]
             unsafe {
                 $crate::__method_msg_send! {
+                    (send_message, __send_message_error)
                     ($receiver)
                     ($($sel)*)
                     ($($args_rest)*)
@@ -369,6 +399,7 @@ macro_rules! __extern_protocol_method_out {
             #[allow(unused_unsafe)]
             unsafe {
                 $crate::__method_msg_send_id! {
+                    (send_message_id, send_message_id_error)
                     ($receiver)
                     ($($sel)*)
                     ($($args_rest)*)
@@ -3,7 +3,10 @@ mod __field_helpers;
 mod __method_msg_send;
 mod __msg_send_parse;
 mod __rewrite_self_arg;
+mod __rewrite_variadic_arg;
 mod declare_class;
+#[cfg(not(feature = "unstable-objfw"))]
+mod declare_protocol;
 mod extern_class;
 mod extern_methods;
 mod extern_protocol;
@@ -33,6 +36,9 @@ mod extern_protocol;
 /// will emit special statics that will be replaced by dyld when the program
 /// starts up.
 ///
+/// On GNUStep, this requires the `"gnustep-2-0"` feature, and the class to
+/// have been compiled for the GNUStep 2.0 ABI.
+///
 /// Errors that were previously runtime panics may now turn into linker errors
 /// if you try to use a class which is not available. Additionally, you may
 /// have to call `msg_send![cls, class]` on the result if you want to use it
@@ -130,6 +136,12 @@ macro_rules! __class_inner {
 ///
 /// Enabling LTO greatly increases the chance that these features work.
 ///
+/// On GNUStep, these features require the `"gnustep-2-0"` feature (and are
+/// not supported on Windows). In that case, the selectors are instead
+/// registered by the runtime when the image is loaded, in the same manner as
+/// Objective-C code compiled with `-fobjc-runtime=gnustep-2.0`. This
+/// requires Rust 1.66 or above.
+///
 /// [rust-lang/rust#53929]: https://github.com/rust-lang/rust/issues/53929
 ///
 ///
@@ -574,23 +586,73 @@ macro_rules! __inner_statics {
     };
 }
 
+// These sections are found by reading clang/LLVM sources, see also
+// `__macro_helpers/gnustep_statics.rs`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(all(feature = "gnustep-2-0", not(target_os = "windows")))]
+macro_rules! __inner_statics {
+    (@image_info $hash:expr) => {
+        /// Make sure that the init struct and the constructor that register
+        /// the selectors and classes with the runtime when the image is
+        /// loaded are linked.
+        #[used] // Make sure this reaches the linker
+        static _IMAGE_INFO: &$crate::__macro_helpers::ObjcInit =
+            unsafe { &$crate::__macro_helpers::OBJC_INIT };
+    };
+    (@sel $data:expr, $hash:expr) => {
+        use $crate::__macro_helpers::{StaticSelector, UnsafeCell};
+        use $crate::runtime::Sel;
+
+        /// The selector itself, which the runtime registers in-place when
+        /// the image is loaded.
+        #[link_section = "__objc_selectors"]
+        static SELECTOR: StaticSelector = StaticSelector::new($data);
+
+        /// SAFETY: Same as `REF` in `__inner_statics_apple_generic!`, though
+        /// here it is the pointee that is modified by the runtime.
+        static mut REF: UnsafeCell<Sel> =
+            unsafe { UnsafeCell::new(Sel::__internal_from_ptr(SELECTOR.as_ptr())) };
+    };
+    (@class $name:expr, $hash:expr) => {
+        use $crate::__macro_helpers::UnsafeCell;
+        use $crate::runtime::Class;
+
+        extern "C" {
+            /// Link to the Objective-C class static, as emitted by clang for
+            /// the GNUstep 2.0 ABI.
+            ///
+            /// An unknown class is a static linker error.
+            #[link_name = $crate::__macro_helpers::concat!("._OBJC_CLASS_", $name)]
+            static CLASS: Class;
+        }
+
+        /// SAFETY: Same as `REF` in `__inner_statics_apple_generic!`. The
+        /// runtime may update this when the image is loaded, e.g. to resolve
+        /// class aliases.
+        #[link_section = "__objc_class_refs"]
+        static mut REF: UnsafeCell<&Class> = unsafe { UnsafeCell::new(&CLASS) };
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
-#[cfg(not(feature = "apple"))]
+#[cfg(all(
+    not(feature = "apple"),
+    not(all(feature = "gnustep-2-0", not(target_os = "windows")))
+))]
 macro_rules! __inner_statics {
     (@image_info $($args:tt)*) => {
         // TODO
     };
     (@sel $($args:tt)*) => {
-        // TODO
         $crate::__macro_helpers::compile_error!(
-            "The `\"unstable-static-sel\"` feature is not yet supported on GNUStep!"
+            "The `\"unstable-static-sel\"` feature is only supported on Apple platforms, and on GNUStep with the `\"gnustep-2-0\"` feature (not on Windows)!"
         )
     };
     (@class $($args:tt)*) => {
-        // TODO
         $crate::__macro_helpers::compile_error!(
-            "The `\"unstable-static-class\"` feature is not yet supported on GNUStep!"
+            "The `\"unstable-static-class\"` feature is only supported on Apple platforms, and on GNUStep with the `\"gnustep-2-0\"` feature (not on Windows)!"
         )
     };
 }
@@ -734,7 +796,11 @@ macro_rules! __class_inner {
 /// references, try refactoring into a separate method or reborrowing the
 /// reference.
 ///
-/// Variadic arguments are currently not supported.
+/// The last argument may be followed by `...(a, b, c)` or `...args`, in
+/// which case the method is called as a variadic method, see below.
+///
+/// The receiver may be prefixed with `try`, in which case exceptions thrown
+/// by the method are caught, see below.
 ///
 /// [`MessageReceiver`]: crate::MessageReceiver
 /// [`rc::Id`]: crate::rc::Id
@@ -782,6 +848,29 @@ macro_rules! __class_inner {
 /// [clang-out-params]: https://clang.llvm.org/docs/AutomaticReferenceCounting.html#passing-to-an-out-parameter-by-writeback
 ///
 ///
+/// # Variadic methods
+///
+/// Methods declared with `...` in Objective-C, such as
+/// `+[NSString stringWithFormat:]` or `-[NSArray initWithObjects:]`, take a
+/// variable number of extra arguments after the last named argument.
+///
+/// These can be passed to the macro as a parenthesized list after the last
+/// argument, like `msg_send![obj, stringWithFormat: format, ...(a, b)]`, or
+/// as a single tuple expression, like `msg_send![obj, stringWithFormat:
+/// format, ...args]`. Up to 16 variadic arguments are supported, and each
+/// must implement [`VariadicArgument`]. Note that the C default argument
+/// promotions are applied, so e.g. an `f32` is passed as a `double`.
+///
+/// Only the normal arguments are part of the method's type encoding, so the
+/// variadic arguments are not checked by the `"verify"` feature.
+///
+/// Methods that instead take a `va_list`, such as
+/// `-[NSString initWithFormat:arguments:]`, are not supported; use their
+/// variadic counterpart instead.
+///
+/// [`VariadicArgument`]: crate::VariadicArgument
+///
+///
 /// # Errors
 ///
 /// The most common place you'll see out-parameters is as `NSError**` the last
@@ -809,18 +898,44 @@ macro_rules! __class_inner {
 /// [swift-error]: https://developer.apple.com/documentation/swift/about-imported-cocoa-error-parameters
 ///
 ///
+/// # Exceptions
+///
+/// Some methods, like `-[NSArray objectAtIndex:]`, are known to throw
+/// exceptions on invalid input. Calls to these can be guarded individually
+/// by prefixing the receiver with `try`, as in
+/// `msg_send![try obj, objectAtIndex: index]`, which makes the macro return
+/// a `Result<R, Id<Exception>>` instead of `R`. This is a shorthand for
+/// wrapping the call in [`exception::catch`], and requires the `"exception"`
+/// feature.
+///
+/// Exceptions thrown while evaluating the arguments are caught as well. If
+/// the `"catch-all"` feature is enabled, the exception is returned instead
+/// of panicking.
+///
+#[cfg_attr(
+    feature = "exception",
+    doc = "[`exception::catch`]: crate::exception::catch"
+)]
+#[cfg_attr(
+    not(feature = "exception"),
+    doc = "[`exception::catch`]: crate::exception#feature-not-enabled"
+)]
+///
+///
 /// # Panics
 ///
 /// Panics if the `"catch-all"` feature is enabled and the Objective-C method
-/// throws an exception. Exceptions may still cause UB until
-/// `extern "C-unwind"` is stable, see [RFC-2945].
+/// throws an exception, unless `try` is used. Exceptions may still cause UB
+/// until `extern "C-unwind"` is stable, see [RFC-2945].
 ///
 /// Panics if `debug_assertions` are enabled and the Objective-C method's
 /// encoding does not match the encoding of the given arguments and return.
 ///
-/// And panics if the `NSError**` handling functionality described above is
+/// Panics if the `NSError**` handling functionality described above is
 /// used, and the error object was unexpectedly `NULL`.
 ///
+/// And panics if `try` is used, and the method threw `nil`.
+///
 /// [RFC-2945]: https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html
 ///
 ///
@@ -845,7 +960,7 @@ macro_rules! __class_inner {
 ///    are allowed (though heavily discouraged), but _only_ if the return type
 ///    itself is a pointer.
 ///
-/// 6. The method must not (yet) throw an exception.
+/// 6. The method must not (yet) throw an exception, unless `try` is used.
 ///
 /// 7. You must uphold any additional safety requirements (explicit and
 ///    implicit) that the method has. For example:
@@ -920,6 +1035,22 @@ macro_rules! __class_inner {
 /// let arg3: u32 = unsafe { msg_send![super(obj, superclass), getArg3] };
 /// ```
 ///
+/// Sending a message to a variadic method.
+///
+/// ```no_run
+/// use objc2::msg_send;
+/// use objc2::runtime::Object;
+///
+/// # type NSString = Object;
+/// let cls: &objc2::runtime::Class;
+/// # cls = objc2::class!(NSString);
+/// let format: &NSString;
+/// # format = todo!();
+/// let string: *mut NSString = unsafe {
+///     msg_send![cls, stringWithFormat: format, ...(42i32, 1.5f64)]
+/// };
+/// ```
+///
 /// Sending a message with automatic error handling.
 ///
 /// ```no_run
@@ -966,6 +1097,11 @@ macro_rules! __class_inner {
 /// ```
 #[macro_export]
 macro_rules! msg_send {
+    [try $($obj_selector_and_arguments:tt)+] => {
+        $crate::__msg_send_try! {
+            $crate::__msg_send_no_catch![$($obj_selector_and_arguments)+]
+        }
+    };
     [super($obj:expr), $($selector_and_arguments:tt)+] => {
         $crate::__msg_send_parse! {
             ($crate::__msg_send_helper)
@@ -1012,6 +1148,7 @@ macro_rules! __msg_send_helper {
         @($($fn_args:tt)+)
         @($($selector:tt)*)
         @($($argument:expr,)*)
+        $(@($varargs:expr))?
     } => ({
         // Assign to intermediary variable for better UI, and to prevent
         // miscompilation on older Rust versions.
@@ -1023,11 +1160,28 @@ macro_rules! __msg_send_helper {
         // 1-tuple if there is only one.
         //
         // And use `::<_, _>` for better UI
-        result = $crate::MessageReceiver::$fn::<_, _>($($fn_args)+, $crate::sel!($($selector)*), ($($argument,)*));
+        result = $crate::MessageReceiver::$fn::<_, _>(
+            $($fn_args)+,
+            $crate::sel!($($selector)*),
+            $crate::__msg_send_arguments!(($($argument,)*) $(, $varargs)?),
+        );
         result
     });
 }
 
+/// Create the arguments to a message send, wrapping them together with the
+/// variadic arguments if there are any.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msg_send_arguments {
+    (($($argument:expr,)*)) => {
+        ($($argument,)*)
+    };
+    (($($argument:expr,)*), $varargs:expr) => {
+        $crate::__macro_helpers::Variadic(($($argument,)*), $varargs)
+    };
+}
+
 /// Deprecated. Use [`msg_send!`] instead.
 #[macro_export]
 #[deprecated = "use a normal msg_send! instead, it will perform the conversion for you"]
@@ -1153,6 +1307,13 @@ macro_rules! msg_send_bool {
 /// specify `E` yourself, usually you'd use `icrate::Foundation::NSError`).
 ///
 ///
+/// # Exceptions
+///
+/// Like [`msg_send!`], the receiver may be prefixed with `try` to catch any
+/// exception thrown by the method, in which case the macro returns a
+/// `Result<Id<T, O>, Id<Exception>>`.
+///
+///
 /// # Panics
 ///
 /// Panics if the return type is specified as `Id<_, _>` and the method
@@ -1203,6 +1364,11 @@ macro_rules! msg_send_bool {
 /// ```
 #[macro_export]
 macro_rules! msg_send_id {
+    [try $($obj_selector_and_arguments:tt)+] => {
+        $crate::__msg_send_try! {
+            $crate::__msg_send_id_no_catch![$($obj_selector_and_arguments)+]
+        }
+    };
     [$obj:expr, new $(,)?] => ({
         let sel = $crate::sel!(new);
         let result;
@@ -1236,6 +1402,109 @@ macro_rules! msg_send_id {
     };
 }
 
+/// Catch exceptions in `msg_send![try ...]` and `msg_send_id![try ...]`.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "exception")]
+macro_rules! __msg_send_try {
+    ($($msg_send:tt)*) => {
+        $crate::__macro_helpers::catch_msg_send(|| $($msg_send)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "exception"))]
+macro_rules! __msg_send_try {
+    ($($msg_send:tt)*) => {
+        $crate::__macro_helpers::compile_error!(
+            "catching exceptions with `try` requires the \"exception\" feature"
+        )
+    };
+}
+
+/// Same as `msg_send!`, but the message send is not wrapped in `@try` if the
+/// `"catch-all"` feature is enabled, since `__msg_send_try!` catches the
+/// exception instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msg_send_no_catch {
+    [super($obj:expr), $($selector_and_arguments:tt)+] => {
+        $crate::__msg_send_parse! {
+            ($crate::__msg_send_helper)
+            @(__send_super_message_static_error_no_catch)
+            @()
+            @()
+            @($($selector_and_arguments)+)
+            @(__send_super_message_static_no_catch)
+
+            @($obj)
+        }
+    };
+    [super($obj:expr, $superclass:expr), $($selector_and_arguments:tt)+] => {
+        $crate::__msg_send_parse! {
+            ($crate::__msg_send_helper)
+            @(__send_super_message_error_no_catch)
+            @()
+            @()
+            @($($selector_and_arguments)+)
+            @(__send_super_message_no_catch)
+
+            @($obj, $superclass)
+        }
+    };
+    [$obj:expr, $($selector_and_arguments:tt)+] => {
+        $crate::__msg_send_parse! {
+            ($crate::__msg_send_helper)
+            @(__send_message_error_no_catch)
+            @()
+            @()
+            @($($selector_and_arguments)+)
+            @(__send_message_no_catch)
+
+            @($obj)
+        }
+    };
+}
+
+/// Same as `msg_send_id!`, but the message send is not wrapped in `@try` if
+/// the `"catch-all"` feature is enabled, see `__msg_send_no_catch!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msg_send_id_no_catch {
+    [$obj:expr, new $(,)?] => ({
+        let sel = $crate::sel!(new);
+        let result;
+        result = <$crate::__macro_helpers::New as $crate::__macro_helpers::MsgSendId<_, _>>::__send_message_id_no_catch($obj, sel, ());
+        result
+    });
+    [$obj:expr, alloc $(,)?] => ({
+        let sel = $crate::sel!(alloc);
+        let result;
+        result = <$crate::__macro_helpers::Alloc as $crate::__macro_helpers::MsgSendId<_, _>>::__send_message_id_no_catch($obj, sel, ());
+        result
+    });
+    [$obj:expr, init $(,)?] => ({
+        let sel = $crate::sel!(init);
+        let result;
+        result = <$crate::__macro_helpers::Init as $crate::__macro_helpers::MsgSendId<_, _>>::__send_message_id_no_catch($obj, sel, ());
+        result
+    });
+    [$obj:expr, $($selector_and_arguments:tt)+] => {
+        $crate::__msg_send_parse! {
+            ($crate::__msg_send_id_helper)
+            @(__send_message_id_error_no_catch)
+            @()
+            @()
+            @($($selector_and_arguments)+)
+            @(__send_message_id_no_catch)
+
+            @($obj)
+            @()
+        }
+    };
+}
+
 /// Helper macro to avoid exposing these in the docs for [`msg_send_id!`].
 #[doc(hidden)]
 #[macro_export]
@@ -1290,11 +1559,12 @@ macro_rules! __msg_send_id_helper {
         @($retain_semantics:ident)
         @($($selector:tt)*)
         @($($argument:expr,)*)
+        $(@($varargs:expr))?
     } => ({
         <$crate::__macro_helpers::$retain_semantics as $crate::__macro_helpers::MsgSendId<_, _>>::$fn::<_, _>(
             $obj,
             $crate::sel!($($selector)*),
-            ($($argument,)*),
+            $crate::__msg_send_arguments!(($($argument,)*) $(, $varargs)?),
         )
     });
     {
@@ -1303,6 +1573,7 @@ macro_rules! __msg_send_id_helper {
         @()
         @($($selector:tt)*)
         @($($argument:expr,)*)
+        $(@($varargs:expr))?
     } => ({
         // Don't use `sel!`, otherwise we'd end up with defining this data twice.
         const __SELECTOR_DATA: &$crate::__macro_helpers::str = $crate::__sel_data!(
@@ -1317,7 +1588,7 @@ macro_rules! __msg_send_id_helper {
                 __SELECTOR_DATA,
                 $crate::__hash_idents!($($selector)*)
             ),
-            ($($argument,)*),
+            $crate::__msg_send_arguments!(($($argument,)*) $(, $varargs)?),
         );
         result
     });
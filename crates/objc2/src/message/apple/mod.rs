@@ -32,7 +32,7 @@ where
     R: EncodeReturn,
 {
     let msg_send_fn = R::MSG_SEND;
-    unsafe { conditional_try!(|| A::__invoke(msg_send_fn, receiver, sel, args)) }
+    unsafe { A::__invoke(msg_send_fn, receiver, sel, args) }
 }
 
 #[inline]
@@ -56,5 +56,5 @@ where
     let receiver = receiver.cast();
 
     let msg_send_fn = R::MSG_SEND_SUPER;
-    unsafe { conditional_try!(|| A::__invoke(msg_send_fn, receiver, sel, args)) }
+    unsafe { A::__invoke(msg_send_fn, receiver, sel, args) }
 }
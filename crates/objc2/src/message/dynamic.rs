@@ -0,0 +1,822 @@
+//! Dynamically typed message sending.
+//!
+//! The types of the arguments and the return value are taken from the type
+//! encoding of the method, and the values are then marshalled into a single
+//! canonical call that fills every argument register the platform's C
+//! calling convention has available.
+//!
+//! Unused registers are simply ignored by the callee, which means that the
+//! same call works for every method whose arguments fit in registers. Only
+//! the return type needs to be known when calling, and this is handled by
+//! choosing between a fixed set of monomorphized return types - which also
+//! makes the existing backends pick the correct `stret` / `fpret` variant.
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use core::ffi::c_void;
+use core::fmt;
+use core::mem;
+use core::slice;
+use std::error::Error;
+use std::os::raw::c_char;
+
+use super::send_unverified;
+use crate::encode::{Encode, Encoding, EncodingBox};
+use crate::runtime::{parse_signature, Class, EncodingParseError, Object, Sel};
+
+/// A dynamically typed value.
+///
+/// This is used as the argument and return type of
+/// [`MessageReceiver::send_message_dyn`], and its variants mirror those of
+/// [`EncodingBox`].
+///
+/// [`MessageReceiver::send_message_dyn`]: crate::MessageReceiver::send_message_dyn
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+    /// No value, returned from methods returning `void`.
+    Void,
+    /// A C `char`.
+    Char(i8),
+    /// A C `short`.
+    Short(i16),
+    /// A C `int`.
+    Int(i32),
+    /// A C `long`.
+    ///
+    /// Note that this is always 32 bits in type encodings, a 64-bit `long`
+    /// is encoded as a `long long` instead.
+    Long(i32),
+    /// A C `long long`.
+    LongLong(i64),
+    /// A C `unsigned char`.
+    UChar(u8),
+    /// A C `unsigned short`.
+    UShort(u16),
+    /// A C `unsigned int`.
+    UInt(u32),
+    /// A C `unsigned long`.
+    ///
+    /// See [`Value::Long`] for details on the size.
+    ULong(u32),
+    /// A C `unsigned long long`.
+    ULongLong(u64),
+    /// A C `float`.
+    Float(f32),
+    /// A C `double`.
+    Double(f64),
+    /// A C `_Bool`, or Objective-C `BOOL` on platforms where that is a
+    /// boolean.
+    Bool(bool),
+    /// A C string, `char*`.
+    String(*mut c_char),
+    /// An object, `id`, or a block.
+    Object(*mut Object),
+    /// A class, `Class`.
+    Class(*const Class),
+    /// A selector, `SEL`.
+    Sel(Option<Sel>),
+    /// Any other pointer.
+    Pointer(*mut c_void),
+    /// A struct, represented by its bytes as laid out in memory.
+    Struct(Vec<u8>),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Void => "void",
+            Self::Char(_) => "char",
+            Self::Short(_) => "short",
+            Self::Int(_) => "int",
+            Self::Long(_) => "long",
+            Self::LongLong(_) => "long long",
+            Self::UChar(_) => "unsigned char",
+            Self::UShort(_) => "unsigned short",
+            Self::UInt(_) => "unsigned int",
+            Self::ULong(_) => "unsigned long",
+            Self::ULongLong(_) => "unsigned long long",
+            Self::Float(_) => "float",
+            Self::Double(_) => "double",
+            Self::Bool(_) => "bool",
+            Self::String(_) => "string",
+            Self::Object(_) => "object",
+            Self::Class(_) => "class",
+            Self::Sel(_) => "selector",
+            Self::Pointer(_) => "pointer",
+            Self::Struct(_) => "struct",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Inner {
+    NullReceiver,
+    MethodNotFound,
+    EncodingParseError(EncodingParseError),
+    MismatchedArgumentsCount(usize, usize),
+    MismatchedArgument(usize, EncodingBox, &'static str),
+    MismatchedStructSize(usize, usize, usize),
+    UnsupportedArgument(usize, EncodingBox),
+    UnsupportedReturn(EncodingBox),
+    TooManyArguments,
+    UnsupportedArchitecture,
+}
+
+impl fmt::Display for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NullReceiver => write!(f, "cannot look up method on nil"),
+            Self::MethodNotFound => write!(f, "method not found"),
+            Self::EncodingParseError(e) => write!(f, "{e}"),
+            Self::MismatchedArgumentsCount(expected, actual) => {
+                write!(f, "expected {expected} arguments, but {actual} were given")
+            }
+            Self::MismatchedArgument(i, expected, actual) => {
+                write!(
+                    f,
+                    "expected argument at index {i} to have type `{}`, but found {actual} value",
+                    expected.rust_type(),
+                )
+            }
+            Self::MismatchedStructSize(i, expected, actual) => {
+                write!(
+                    f,
+                    "expected struct argument at index {i} to have {expected} bytes, but found {actual}",
+                )
+            }
+            Self::UnsupportedArgument(i, enc) => {
+                write!(
+                    f,
+                    "argument at index {i} with type code '{enc}' cannot be sent dynamically",
+                )
+            }
+            Self::UnsupportedReturn(enc) => {
+                write!(
+                    f,
+                    "return type with type code '{enc}' cannot be received dynamically",
+                )
+            }
+            Self::TooManyArguments => {
+                write!(f, "arguments do not fit in the available registers")
+            }
+            Self::UnsupportedArchitecture => {
+                write!(
+                    f,
+                    "dynamic message sending is not supported on this platform"
+                )
+            }
+        }
+    }
+}
+
+/// Failed sending a dynamically typed message.
+///
+/// This is returned in the error case of
+/// [`MessageReceiver::send_message_dyn`], see that for details.
+///
+/// This implements [`Error`], and a description of the error can be retrieved
+/// using [`fmt::Display`].
+///
+/// [`MessageReceiver::send_message_dyn`]: crate::MessageReceiver::send_message_dyn
+#[derive(Debug, PartialEq)]
+pub struct DynMessageError(Inner);
+
+impl From<EncodingParseError> for DynMessageError {
+    fn from(e: EncodingParseError) -> Self {
+        Self(Inner::EncodingParseError(e))
+    }
+}
+
+impl From<Inner> for DynMessageError {
+    fn from(inner: Inner) -> Self {
+        Self(inner)
+    }
+}
+
+impl fmt::Display for DynMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Delegate to inner
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for DynMessageError {}
+
+/// The calling conventions we know how to marshal arguments for.
+// Only one of these is used on each platform.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Abi {
+    /// The System V AMD64 ABI.
+    X86_64,
+    /// The ARM 64-bit Procedure Call Standard, including Apple's variant of
+    /// it (which only differs in how arguments on the stack are laid out).
+    Aarch64,
+}
+
+impl Abi {
+    #[cfg(all(target_arch = "x86_64", not(windows)))]
+    const CURRENT: Option<Self> = Some(Self::X86_64);
+    #[cfg(target_arch = "aarch64")]
+    const CURRENT: Option<Self> = Some(Self::Aarch64);
+    #[cfg(not(any(all(target_arch = "x86_64", not(windows)), target_arch = "aarch64")))]
+    const CURRENT: Option<Self> = None;
+
+    /// The number of integer registers available after the receiver and the
+    /// selector have been passed.
+    fn int_registers(self, indirect_return: bool) -> usize {
+        match self {
+            // rdi, rsi, rdx, rcx, r8 and r9, of which the first is taken by
+            // the return pointer if the return value is passed in memory.
+            Self::X86_64 if indirect_return => 3,
+            Self::X86_64 => 4,
+            // x0 to x7, the return pointer is passed in x8.
+            Self::Aarch64 => 6,
+        }
+    }
+}
+
+/// The canonical argument list, large enough to fill every integer and
+/// floating point argument register on the supported platforms.
+type Args = (
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+    f64,
+);
+
+const INT_ARGS: usize = 6;
+const FLOAT_ARGS: usize = 8;
+
+/// A scalar member of a struct.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    Int,
+    Float,
+    Double,
+}
+
+/// The C layout of a type, flattened into its scalar members.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Layout {
+    size: usize,
+    align: usize,
+    scalars: Vec<(usize, Scalar, usize)>,
+}
+
+impl Layout {
+    fn new(enc: &EncodingBox) -> Option<Self> {
+        let (size, scalar) = match enc {
+            EncodingBox::Char | EncodingBox::UChar | EncodingBox::Bool => (1, Scalar::Int),
+            EncodingBox::Short | EncodingBox::UShort => (2, Scalar::Int),
+            EncodingBox::Int | EncodingBox::UInt | EncodingBox::Long | EncodingBox::ULong => {
+                (4, Scalar::Int)
+            }
+            EncodingBox::LongLong | EncodingBox::ULongLong => (8, Scalar::Int),
+            EncodingBox::Float => (4, Scalar::Float),
+            EncodingBox::Double => (8, Scalar::Double),
+            EncodingBox::String
+            | EncodingBox::Object
+            | EncodingBox::Block
+            | EncodingBox::Class
+            | EncodingBox::Sel
+            | EncodingBox::Pointer(_) => (mem::size_of::<*const c_void>(), Scalar::Int),
+            EncodingBox::Array(len, item) => {
+                let item = Self::new(item)?;
+                let mut layout = Self::empty();
+                for _ in 0..*len {
+                    layout.push(&item);
+                }
+                layout.finish();
+                return Some(layout);
+            }
+            EncodingBox::Struct(_, Some(fields)) => {
+                let mut layout = Self::empty();
+                for field in fields {
+                    layout.push(&Self::new(field)?);
+                }
+                layout.finish();
+                return Some(layout);
+            }
+            _ => return None,
+        };
+        Some(Self {
+            size,
+            align: size,
+            scalars: vec![(0, scalar, size)],
+        })
+    }
+
+    fn empty() -> Self {
+        Self {
+            size: 0,
+            align: 1,
+            scalars: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, other: &Self) {
+        let offset = round_up(self.size, other.align);
+        self.scalars.extend(
+            other
+                .scalars
+                .iter()
+                .map(|&(inner, scalar, size)| (offset + inner, scalar, size)),
+        );
+        self.size = offset + other.size;
+        self.align = cmp::max(self.align, other.align);
+    }
+
+    fn finish(&mut self) {
+        self.size = round_up(self.size, self.align);
+    }
+
+    /// The number of eightbyte words the value occupies.
+    fn words(&self) -> usize {
+        round_up(self.size, 8) / 8
+    }
+
+    /// Whether the value is passed in memory when returned.
+    fn is_indirect(&self) -> bool {
+        self.size > 16
+    }
+
+    /// How a struct is split into registers.
+    fn pieces(&self, abi: Abi) -> Vec<(usize, Scalar)> {
+        match abi {
+            // Each eightbyte is passed in an SSE register if it only
+            // contains floating point members, otherwise in an integer
+            // register.
+            Abi::X86_64 => (0..self.words())
+                .map(|word| {
+                    let offset = word * 8;
+                    let is_sse = self
+                        .scalars
+                        .iter()
+                        .filter(|(inner, _, _)| offset <= *inner && *inner < offset + 8)
+                        .all(|(_, scalar, _)| *scalar != Scalar::Int);
+                    (offset, if is_sse { Scalar::Double } else { Scalar::Int })
+                })
+                .collect(),
+            // Homogenous floating point aggregates are passed with one
+            // member per register, other structs are passed in words.
+            Abi::Aarch64 => match self.homogenous_float() {
+                Some(scalar) => self
+                    .scalars
+                    .iter()
+                    .map(|&(offset, _, _)| (offset, scalar))
+                    .collect(),
+                None => (0..self.words())
+                    .map(|word| (word * 8, Scalar::Int))
+                    .collect(),
+            },
+        }
+    }
+
+    fn homogenous_float(&self) -> Option<Scalar> {
+        let (_, first, _) = *self.scalars.first()?;
+        if first == Scalar::Int
+            || self.scalars.len() > 4
+            || self.scalars.iter().any(|(_, scalar, _)| *scalar != first)
+        {
+            return None;
+        }
+        Some(first)
+    }
+}
+
+fn round_up(size: usize, align: usize) -> usize {
+    match size % align {
+        0 => size,
+        rem => size + align - rem,
+    }
+}
+
+/// Read up to `size` bytes at `offset` into the low bits of a word.
+fn read_word(bytes: &[u8], offset: usize, size: usize) -> u64 {
+    let end = cmp::min(bytes.len(), offset + size);
+    let mut buf = [0; 8];
+    buf[..end - offset].copy_from_slice(&bytes[offset..end]);
+    u64::from_ne_bytes(buf)
+}
+
+#[derive(Debug, Default)]
+struct Registers {
+    ints: Vec<u64>,
+    floats: Vec<f64>,
+    /// Copies of structs passed by reference, kept alive for the call.
+    buffers: Vec<Vec<u64>>,
+}
+
+impl Registers {
+    fn push_float(&mut self, value: f32) {
+        self.floats.push(f64::from_bits(u64::from(value.to_bits())));
+    }
+
+    fn push_struct(
+        &mut self,
+        abi: Abi,
+        layout: &Layout,
+        bytes: &[u8],
+    ) -> Result<(), Option<Inner>> {
+        if layout.is_indirect() {
+            match abi {
+                // Passed on the stack, which we do not handle.
+                Abi::X86_64 => return Err(None),
+                // Passed as a pointer to a copy.
+                Abi::Aarch64 => {
+                    let copy: Vec<u64> = (0..layout.words())
+                        .map(|word| read_word(bytes, word * 8, 8))
+                        .collect();
+                    self.ints.push(copy.as_ptr() as usize as u64);
+                    self.buffers.push(copy);
+                    return Ok(());
+                }
+            }
+        }
+        for (offset, scalar) in layout.pieces(abi) {
+            match scalar {
+                Scalar::Int => self.ints.push(read_word(bytes, offset, 8)),
+                Scalar::Float => {
+                    self.push_float(f32::from_bits(read_word(bytes, offset, 4) as u32))
+                }
+                Scalar::Double => self
+                    .floats
+                    .push(f64::from_bits(read_word(bytes, offset, 8))),
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, abi: Abi, i: usize, enc: &EncodingBox, value: &Value) -> Result<(), Inner> {
+        let int = match (enc, value) {
+            // Integers are sign- or zero-extended to the full register.
+            (EncodingBox::Char, Value::Char(v)) => *v as i64 as u64,
+            (EncodingBox::Short, Value::Short(v)) => *v as i64 as u64,
+            (EncodingBox::Int, Value::Int(v)) | (EncodingBox::Long, Value::Long(v)) => {
+                *v as i64 as u64
+            }
+            (EncodingBox::LongLong, Value::LongLong(v)) => *v as u64,
+            (EncodingBox::UChar, Value::UChar(v)) => u64::from(*v),
+            (EncodingBox::UShort, Value::UShort(v)) => u64::from(*v),
+            (EncodingBox::UInt, Value::UInt(v)) | (EncodingBox::ULong, Value::ULong(v)) => {
+                u64::from(*v)
+            }
+            (EncodingBox::ULongLong, Value::ULongLong(v)) => *v,
+            (EncodingBox::Bool, Value::Bool(v)) => u64::from(*v),
+            (EncodingBox::String, Value::String(ptr)) => *ptr as usize as u64,
+            (EncodingBox::Object | EncodingBox::Block, Value::Object(ptr)) => *ptr as usize as u64,
+            (EncodingBox::Class, Value::Class(ptr)) => *ptr as usize as u64,
+            (EncodingBox::Sel, Value::Sel(sel)) => {
+                sel.map_or(0, |sel| sel.as_ptr() as usize as u64)
+            }
+            (EncodingBox::Pointer(_), Value::Pointer(ptr)) => *ptr as usize as u64,
+            (EncodingBox::Float, Value::Float(v)) => {
+                self.push_float(*v);
+                return Ok(());
+            }
+            (EncodingBox::Double, Value::Double(v)) => {
+                self.floats.push(*v);
+                return Ok(());
+            }
+            (EncodingBox::Struct(_, _), Value::Struct(bytes)) => {
+                let layout =
+                    Layout::new(enc).ok_or_else(|| Inner::UnsupportedArgument(i, enc.clone()))?;
+                if layout.size != bytes.len() {
+                    return Err(Inner::MismatchedStructSize(i, layout.size, bytes.len()));
+                }
+                return self.push_struct(abi, &layout, bytes).map_err(|err| {
+                    err.unwrap_or_else(|| Inner::UnsupportedArgument(i, enc.clone()))
+                });
+            }
+            (_, value) => {
+                return Err(match Layout::new(enc) {
+                    Some(_) => Inner::MismatchedArgument(i, enc.clone(), value.kind()),
+                    None => Inner::UnsupportedArgument(i, enc.clone()),
+                })
+            }
+        };
+        self.ints.push(int);
+        Ok(())
+    }
+
+    fn into_args(self, abi: Abi, indirect_return: bool) -> Result<(Args, Vec<Vec<u64>>), Inner> {
+        if self.ints.len() > abi.int_registers(indirect_return) || self.floats.len() > FLOAT_ARGS {
+            return Err(Inner::TooManyArguments);
+        }
+        let mut i = [0; INT_ARGS];
+        i[..self.ints.len()].copy_from_slice(&self.ints);
+        let mut f = [0.0; FLOAT_ARGS];
+        f[..self.floats.len()].copy_from_slice(&self.floats);
+        let args = (
+            i[0], i[1], i[2], i[3], i[4], i[5], f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+        );
+        Ok((args, self.buffers))
+    }
+}
+
+/// A struct with the same layout and return convention as `T`.
+///
+/// The encoding is only used by the backends to pick between `stret` and
+/// `fpret`, which is done based on size and not on the members.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Aggregate<T>(T);
+
+unsafe impl<T> Encode for Aggregate<T> {
+    const ENCODING: Encoding = Encoding::Struct("?", &[]);
+}
+
+/// A struct of two eightbytes of possibly different classes.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Pair<A, B>(A, B);
+
+unsafe impl<A, B> Encode for Pair<A, B> {
+    const ENCODING: Encoding = Encoding::Struct("?", &[]);
+}
+
+unsafe fn call_bytes<R: Encode + Copy>(
+    receiver: *mut Object,
+    sel: Sel,
+    args: Args,
+    size: usize,
+) -> Vec<u8> {
+    let ret: R = unsafe { send_unverified(receiver, sel, args) };
+    let ptr: *const R = &ret;
+    // SAFETY: The return types are plain-old-data without padding.
+    let bytes = unsafe { slice::from_raw_parts(ptr.cast::<u8>(), mem::size_of::<R>()) };
+    bytes[..size].to_vec()
+}
+
+unsafe fn call_struct(
+    receiver: *mut Object,
+    sel: Sel,
+    args: Args,
+    abi: Abi,
+    layout: &Layout,
+) -> Option<Vec<u8>> {
+    let size = layout.size;
+    let pieces = layout.pieces(abi);
+    let bytes = unsafe {
+        if layout.is_indirect() {
+            match layout.words() {
+                3 => call_bytes::<Aggregate<[u64; 3]>>(receiver, sel, args, size),
+                4 => call_bytes::<Aggregate<[u64; 4]>>(receiver, sel, args, size),
+                5 => call_bytes::<Aggregate<[u64; 5]>>(receiver, sel, args, size),
+                6 => call_bytes::<Aggregate<[u64; 6]>>(receiver, sel, args, size),
+                7 => call_bytes::<Aggregate<[u64; 7]>>(receiver, sel, args, size),
+                8 => call_bytes::<Aggregate<[u64; 8]>>(receiver, sel, args, size),
+                9 => call_bytes::<Aggregate<[u64; 9]>>(receiver, sel, args, size),
+                10 => call_bytes::<Aggregate<[u64; 10]>>(receiver, sel, args, size),
+                11 => call_bytes::<Aggregate<[u64; 11]>>(receiver, sel, args, size),
+                12 => call_bytes::<Aggregate<[u64; 12]>>(receiver, sel, args, size),
+                13 => call_bytes::<Aggregate<[u64; 13]>>(receiver, sel, args, size),
+                14 => call_bytes::<Aggregate<[u64; 14]>>(receiver, sel, args, size),
+                15 => call_bytes::<Aggregate<[u64; 15]>>(receiver, sel, args, size),
+                16 => call_bytes::<Aggregate<[u64; 16]>>(receiver, sel, args, size),
+                _ => return None,
+            }
+        } else {
+            use Scalar::{Double as D, Float as F, Int as I};
+            let scalars: Vec<Scalar> = pieces.iter().map(|(_, scalar)| *scalar).collect();
+            match &*scalars {
+                [] => {
+                    send_unverified::<Args, ()>(receiver, sel, args);
+                    Vec::new()
+                }
+                [I] => call_bytes::<Aggregate<[u64; 1]>>(receiver, sel, args, size),
+                [I, I] => call_bytes::<Aggregate<[u64; 2]>>(receiver, sel, args, size),
+                [I, D] => call_bytes::<Pair<u64, f64>>(receiver, sel, args, size),
+                [D, I] => call_bytes::<Pair<f64, u64>>(receiver, sel, args, size),
+                [D] => call_bytes::<Aggregate<[f64; 1]>>(receiver, sel, args, size),
+                [D, D] => call_bytes::<Aggregate<[f64; 2]>>(receiver, sel, args, size),
+                [D, D, D] => call_bytes::<Aggregate<[f64; 3]>>(receiver, sel, args, size),
+                [D, D, D, D] => call_bytes::<Aggregate<[f64; 4]>>(receiver, sel, args, size),
+                [F] => call_bytes::<Aggregate<[f32; 1]>>(receiver, sel, args, size),
+                [F, F] => call_bytes::<Aggregate<[f32; 2]>>(receiver, sel, args, size),
+                [F, F, F] => call_bytes::<Aggregate<[f32; 3]>>(receiver, sel, args, size),
+                [F, F, F, F] => call_bytes::<Aggregate<[f32; 4]>>(receiver, sel, args, size),
+                _ => return None,
+            }
+        }
+    };
+    Some(bytes)
+}
+
+/// Send a message with dynamically typed arguments.
+///
+/// The arguments are packed into the canonical [`Args`] signature, and the
+/// method is called through that regardless of its actual signature. This
+/// punning is only sound when every argument is passed in a register, since
+/// the callee then simply ignores the registers it doesn't read.
+///
+/// The 32-bit ABIs (i386 and ARMv7) pass most arguments on the stack, where
+/// the layout of the fixed signature does not match what the callee reads,
+/// and they also have their own rules for when `stret` / `fpret` is used.
+/// They are deliberately not supported, and [`Abi::CURRENT`] is `None` there.
+pub(crate) unsafe fn send_message_dyn(
+    receiver: *mut Object,
+    sel: Sel,
+    args: &[Value],
+) -> Result<Value, DynMessageError> {
+    // SAFETY: Caller ensures only valid or NULL pointers.
+    let obj = unsafe { receiver.as_ref() }.ok_or(Inner::NullReceiver)?;
+    let method = obj
+        .class()
+        .instance_method(sel)
+        .ok_or(Inner::MethodNotFound)?;
+    let abi = Abi::CURRENT.ok_or(Inner::UnsupportedArchitecture)?;
+
+    let signature = parse_signature(method.types())?;
+    let count = signature.arguments.len();
+    if count != args.len() {
+        return Err(Inner::MismatchedArgumentsCount(count, args.len()).into());
+    }
+    let ret = signature.return_type.encoding;
+
+    let ret_layout = match ret {
+        EncodingBox::Void => None,
+        _ => Some(Layout::new(&ret).ok_or_else(|| Inner::UnsupportedReturn(ret.clone()))?),
+    };
+
+    let mut registers = Registers::default();
+    for (i, (argument, value)) in signature.arguments.iter().zip(args).enumerate() {
+        registers.push(abi, i, &argument.encoding, value)?;
+    }
+
+    let indirect_return = matches!(&ret_layout, Some(layout) if layout.is_indirect());
+    // The buffers are kept alive until after the call.
+    let (args, _buffers) = registers.into_args(abi, indirect_return)?;
+
+    // SAFETY: The method's type encoding was used to marshal the arguments,
+    // and the caller upholds the rest of the safety requirements.
+    unsafe {
+        Ok(match &ret {
+            EncodingBox::Void => {
+                send_unverified::<Args, ()>(receiver, sel, args);
+                Value::Void
+            }
+            EncodingBox::Float => Value::Float(send_unverified(receiver, sel, args)),
+            EncodingBox::Double => Value::Double(send_unverified(receiver, sel, args)),
+            EncodingBox::Struct(_, _) => {
+                let layout = ret_layout.as_ref().unwrap();
+                let bytes = call_struct(receiver, sel, args, abi, layout)
+                    .ok_or_else(|| Inner::UnsupportedReturn(ret.clone()))?;
+                Value::Struct(bytes)
+            }
+            _ => {
+                // Integers and pointers are returned in the same register,
+                // only the low bits are defined for smaller types.
+                let v: u64 = send_unverified(receiver, sel, args);
+                match &ret {
+                    EncodingBox::Char => Value::Char(v as i8),
+                    EncodingBox::Short => Value::Short(v as i16),
+                    EncodingBox::Int => Value::Int(v as i32),
+                    EncodingBox::Long => Value::Long(v as i32),
+                    EncodingBox::LongLong => Value::LongLong(v as i64),
+                    EncodingBox::UChar => Value::UChar(v as u8),
+                    EncodingBox::UShort => Value::UShort(v as u16),
+                    EncodingBox::UInt => Value::UInt(v as u32),
+                    EncodingBox::ULong => Value::ULong(v as u32),
+                    EncodingBox::ULongLong => Value::ULongLong(v),
+                    EncodingBox::Bool => Value::Bool(v as u8 != 0),
+                    EncodingBox::String => Value::String(v as usize as *mut c_char),
+                    EncodingBox::Object | EncodingBox::Block => {
+                        Value::Object(v as usize as *mut Object)
+                    }
+                    EncodingBox::Class => Value::Class(v as usize as *const Class),
+                    EncodingBox::Sel => Value::Sel(Sel::from_ptr(v as usize as *const _)),
+                    EncodingBox::Pointer(_) => Value::Pointer(v as usize as *mut c_void),
+                    _ => return Err(Inner::UnsupportedReturn(ret.clone()).into()),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+// The tests rely on `test_utils`, which uses ivars and protocols
+#[cfg(not(feature = "unstable-objfw"))]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    use crate::test_utils;
+    use crate::{sel, MessageReceiver};
+
+    #[test]
+    fn test_layout() {
+        let enc: EncodingBox = "{A=cd{B=fi}[2s]}".parse().unwrap();
+        let layout = Layout::new(&enc).unwrap();
+        assert_eq!(layout.size, 32);
+        assert_eq!(layout.align, 8);
+        assert_eq!(
+            layout.scalars,
+            [
+                (0, Scalar::Int, 1),
+                (8, Scalar::Double, 8),
+                (16, Scalar::Float, 4),
+                (20, Scalar::Int, 4),
+                (24, Scalar::Int, 2),
+                (26, Scalar::Int, 2),
+            ]
+        );
+
+        let enc: EncodingBox = "{CGPoint=dd}".parse().unwrap();
+        let layout = Layout::new(&enc).unwrap();
+        assert_eq!(
+            layout.pieces(Abi::X86_64),
+            [(0, Scalar::Double), (8, Scalar::Double)]
+        );
+        assert_eq!(
+            layout.pieces(Abi::Aarch64),
+            [(0, Scalar::Double), (8, Scalar::Double)]
+        );
+
+        let enc: EncodingBox = "{A=ffi}".parse().unwrap();
+        let layout = Layout::new(&enc).unwrap();
+        assert_eq!(
+            layout.pieces(Abi::X86_64),
+            [(0, Scalar::Double), (8, Scalar::Int)]
+        );
+        assert_eq!(
+            layout.pieces(Abi::Aarch64),
+            [(0, Scalar::Int), (8, Scalar::Int)]
+        );
+
+        let enc: EncodingBox = "{A=fff}".parse().unwrap();
+        let layout = Layout::new(&enc).unwrap();
+        assert_eq!(
+            layout.pieces(Abi::Aarch64),
+            [(0, Scalar::Float), (4, Scalar::Float), (8, Scalar::Float)]
+        );
+
+        let enc: EncodingBox = "{A}".parse().unwrap();
+        assert_eq!(Layout::new(&enc), None);
+    }
+
+    #[test]
+    fn test_send_message_dyn() {
+        let obj = test_utils::custom_object();
+        let obj: &Object = &obj;
+
+        let res = unsafe { obj.send_message_dyn(sel!(setFoo:), &[Value::UInt(42)]) };
+        assert_eq!(res, Ok(Value::Void));
+        let res = unsafe { obj.send_message_dyn(sel!(foo), &[]) };
+        assert_eq!(res, Ok(Value::UInt(42)));
+
+        let res = unsafe { obj.send_message_dyn(sel!(customStruct), &[]) };
+        let expected: Vec<u8> = [1u64, 2, 3, 4]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect();
+        assert_eq!(res, Ok(Value::Struct(expected)));
+
+        let cls = test_utils::custom_class();
+        let res = unsafe {
+            cls.send_message_dyn(sel!(addNumber:toNumber:), &[Value::Int(3), Value::Int(-5)])
+        };
+        assert_eq!(res, Ok(Value::Int(-2)));
+    }
+
+    #[test]
+    fn test_send_message_dyn_errors() {
+        let obj = test_utils::custom_object();
+        let obj: &Object = &obj;
+
+        let res = unsafe { obj.send_message_dyn(sel!(unknown), &[]) };
+        assert_eq!(res, Err(Inner::MethodNotFound.into()));
+
+        let res = unsafe { obj.send_message_dyn(sel!(foo), &[Value::UInt(1)]) };
+        assert_eq!(res, Err(Inner::MismatchedArgumentsCount(0, 1).into()));
+        // The count is checked before the types of the arguments
+        let res = unsafe { obj.send_message_dyn(sel!(setFoo:), &[Value::Int(1), Value::Int(2)]) };
+        assert_eq!(res, Err(Inner::MismatchedArgumentsCount(1, 2).into()));
+
+        let res = unsafe { obj.send_message_dyn(sel!(setFoo:), &[Value::Int(1)]) };
+        let err = res.unwrap_err();
+        assert_eq!(
+            err,
+            Inner::MismatchedArgument(0, EncodingBox::UInt, "int").into()
+        );
+        assert_eq!(
+            err.to_string(),
+            "expected argument at index 0 to have type `u32`, but found int value"
+        );
+
+        let res = unsafe { (core::ptr::null_mut::<Object>()).send_message_dyn(sel!(foo), &[]) };
+        assert_eq!(res, Err(Inner::NullReceiver.into()));
+    }
+}
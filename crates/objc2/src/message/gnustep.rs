@@ -38,7 +38,7 @@ where
 
     let msg_send_fn = unsafe { ffi::objc_msg_lookup(receiver.cast(), sel.as_ptr()) };
     let msg_send_fn = unwrap_msg_send_fn(msg_send_fn);
-    unsafe { conditional_try!(|| A::__invoke(msg_send_fn, receiver, sel, args)) }
+    unsafe { A::__invoke(msg_send_fn, receiver, sel, args) }
 }
 
 #[track_caller]
@@ -64,5 +64,5 @@ where
     };
     let msg_send_fn = unsafe { ffi::objc_msg_lookup_super(&sup, sel.as_ptr()) };
     let msg_send_fn = unwrap_msg_send_fn(msg_send_fn);
-    unsafe { conditional_try!(|| A::__invoke(msg_send_fn, receiver, sel, args)) }
+    unsafe { A::__invoke(msg_send_fn, receiver, sel, args) }
 }
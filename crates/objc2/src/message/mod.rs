@@ -91,8 +91,39 @@ mod platform;
 #[cfg(feature = "gnustep-1-7")]
 #[path = "gnustep.rs"]
 mod platform;
+#[cfg(feature = "unstable-objfw")]
+#[path = "objfw.rs"]
+mod platform;
+
+#[inline]
+#[track_caller]
+unsafe fn send_unverified<A, R>(receiver: *mut Object, sel: Sel, args: A) -> R
+where
+    A: MessageArguments,
+    R: EncodeReturn,
+{
+    unsafe { conditional_try!(|| platform::send_unverified(receiver, sel, args)) }
+}
+
+#[inline]
+#[track_caller]
+unsafe fn send_super_unverified<A, R>(
+    receiver: *mut Object,
+    superclass: &Class,
+    sel: Sel,
+    args: A,
+) -> R
+where
+    A: MessageArguments,
+    R: EncodeReturn,
+{
+    unsafe { conditional_try!(|| platform::send_super_unverified(receiver, superclass, sel, args)) }
+}
+
+pub(crate) mod dynamic;
+mod variadic;
 
-use self::platform::{send_super_unverified, send_unverified};
+pub use self::variadic::{Variadic, VariadicArgument, VariadicArguments};
 
 /// Types that can be sent Objective-C messages.
 ///
@@ -276,6 +307,68 @@ pub unsafe trait MessageReceiver: private::Sealed + Sized {
         }
     }
 
+    /// Sends a message to self with the given selector and dynamically typed
+    /// arguments.
+    ///
+    /// This is useful when the selector and the arguments are only known at
+    /// runtime, for example when bridging to a scripting language. The method
+    /// is looked up on the class of the receiver, and its type encoding is
+    /// used to convert the arguments and the return value, choosing between
+    /// the `stret` and `fpret` variants of `objc_msgSend` as required.
+    ///
+    /// Only the x86-64 (except on Windows) and AArch64 calling conventions
+    /// are supported, and all arguments must fit in registers. Unions,
+    /// bitfields and `long double` are not supported.
+    ///
+    /// Internally, every message is sent with the same fixed signature of six
+    /// 64-bit integer and eight `f64` arguments, into which the actual
+    /// arguments are packed according to the calling convention. This relies
+    /// on all of those being passed in registers, and on the callee ignoring
+    /// the registers it doesn't use. That does not hold for 32-bit targets
+    /// (e.g. i386 and ARMv7), which pass arguments on the stack, so this
+    /// always returns an error there.
+    ///
+    /// Prefer [`send_message`][Self::send_message] or the [`msg_send!`] macro
+    /// when the types are known at compile-time.
+    ///
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the receiver is NULL, if the method is not found,
+    /// if the arguments do not match the method's type encoding, if the
+    /// types cannot be passed dynamically, or if the current architecture is
+    /// not supported.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// This shares the same safety requirements as [`msg_send!`], except for
+    /// the requirement that the types match, which is checked instead.
+    ///
+    /// Additionally, the method's type encoding must be correct.
+    ///
+    /// [`msg_send!`]: crate::msg_send
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use objc2::runtime::{NSObject, Value};
+    /// use objc2::{sel, MessageReceiver};
+    ///
+    /// let obj = NSObject::new();
+    /// let ptr: *const NSObject = &*obj;
+    /// let res = unsafe { (&*obj).send_message_dyn(sel!(self), &[]) };
+    /// assert_eq!(res, Ok(Value::Object(ptr as *mut _)));
+    /// ```
+    unsafe fn send_message_dyn(
+        self,
+        sel: Sel,
+        args: &[dynamic::Value],
+    ) -> Result<dynamic::Value, dynamic::DynMessageError> {
+        unsafe { dynamic::send_message_dyn(self.__as_raw_receiver(), sel, args) }
+    }
+
     #[inline]
     #[track_caller]
     #[doc(hidden)]
@@ -361,6 +454,143 @@ pub unsafe trait MessageReceiver: private::Sealed + Sized {
             Err(unsafe { encountered_error(err) })
         }
     }
+
+    // Non-catching functions below, used by `msg_send![try ...]`, which
+    // catches the exception itself.
+    //
+    // These are the same as the functions above, except that the message
+    // send is not wrapped in another `@try` when the `"catch-all"` feature is
+    // enabled. Message sends made by the called method are still handled by
+    // `"catch-all"` as usual, so an exception is never unwound through Rust
+    // frames there.
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    unsafe fn __send_message_no_catch<A, R>(self, sel: Sel, args: A) -> R
+    where
+        A: MessageArguments,
+        R: EncodeConvertReturn,
+    {
+        let this = self.__as_raw_receiver();
+        #[cfg(debug_assertions)]
+        {
+            // SAFETY: Caller ensures only valid or NULL pointers.
+            let obj = unsafe { this.as_ref() };
+            msg_send_check(obj, sel, A::ENCODINGS, &R::__Inner::ENCODING_RETURN);
+        }
+        unsafe { EncodeConvertReturn::__from_return(platform::send_unverified(this, sel, args)) }
+    }
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    unsafe fn __send_super_message_no_catch<A, R>(self, superclass: &Class, sel: Sel, args: A) -> R
+    where
+        A: MessageArguments,
+        R: EncodeConvertReturn,
+    {
+        let this = self.__as_raw_receiver();
+        #[cfg(debug_assertions)]
+        {
+            if this.is_null() {
+                panic_null(sel);
+            }
+            if let Err(err) = superclass.verify_sel::<A, R>(sel) {
+                panic_verify(superclass, sel, err);
+            }
+        }
+        unsafe {
+            EncodeConvertReturn::__from_return(platform::send_super_unverified(
+                this, superclass, sel, args,
+            ))
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    unsafe fn __send_super_message_static_no_catch<A, R>(self, sel: Sel, args: A) -> R
+    where
+        Self::__Inner: ClassType,
+        <Self::__Inner as ClassType>::Super: ClassType,
+        A: MessageArguments,
+        R: EncodeConvertReturn,
+    {
+        let superclass = <Self::__Inner as ClassType>::Super::class();
+        unsafe { self.__send_super_message_no_catch(superclass, sel, args) }
+    }
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    unsafe fn __send_message_error_no_catch<A, E>(self, sel: Sel, args: A) -> Result<(), Id<E>>
+    where
+        *mut *mut E: Encode,
+        A: __TupleExtender<*mut *mut E>,
+        <A as __TupleExtender<*mut *mut E>>::PlusOneArgument: MessageArguments,
+        E: Message,
+    {
+        let mut err: *mut E = ptr::null_mut();
+        let args = args.add_argument(&mut err);
+        let res: bool = unsafe { self.__send_message_no_catch(sel, args) };
+        if res {
+            Ok(())
+        } else {
+            Err(unsafe { encountered_error(err) })
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    unsafe fn __send_super_message_error_no_catch<A, E>(
+        self,
+        superclass: &Class,
+        sel: Sel,
+        args: A,
+    ) -> Result<(), Id<E>>
+    where
+        *mut *mut E: Encode,
+        A: __TupleExtender<*mut *mut E>,
+        <A as __TupleExtender<*mut *mut E>>::PlusOneArgument: MessageArguments,
+        E: Message,
+    {
+        let mut err: *mut E = ptr::null_mut();
+        let args = args.add_argument(&mut err);
+        let res: bool = unsafe { self.__send_super_message_no_catch(superclass, sel, args) };
+        if res {
+            Ok(())
+        } else {
+            Err(unsafe { encountered_error(err) })
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    #[doc(hidden)]
+    unsafe fn __send_super_message_static_error_no_catch<A, E>(
+        self,
+        sel: Sel,
+        args: A,
+    ) -> Result<(), Id<E>>
+    where
+        Self::__Inner: ClassType,
+        <Self::__Inner as ClassType>::Super: ClassType,
+        *mut *mut E: Encode,
+        A: __TupleExtender<*mut *mut E>,
+        <A as __TupleExtender<*mut *mut E>>::PlusOneArgument: MessageArguments,
+        E: Message,
+    {
+        let mut err: *mut E = ptr::null_mut();
+        let args = args.add_argument(&mut err);
+        let res: bool = unsafe { self.__send_super_message_static_no_catch(sel, args) };
+        if res {
+            Ok(())
+        } else {
+            Err(unsafe { encountered_error(err) })
+        }
+    }
 }
 
 #[cold]
@@ -486,6 +716,57 @@ pub unsafe trait MessageArguments: EncodeArguments {
     unsafe fn __invoke<R: EncodeReturn>(imp: Imp, obj: *mut Object, sel: Sel, args: Self) -> R;
 }
 
+/// Helper for invoking variadic methods, see [`VariadicArguments`].
+///
+/// The variadic arguments are always padded to 16 arguments.
+#[doc(hidden)]
+pub unsafe trait __InvokeVariadic: MessageArguments {
+    #[doc(hidden)]
+    #[allow(clippy::type_complexity)]
+    unsafe fn __invoke_variadic<
+        V0,
+        V1,
+        V2,
+        V3,
+        V4,
+        V5,
+        V6,
+        V7,
+        V8,
+        V9,
+        V10,
+        V11,
+        V12,
+        V13,
+        V14,
+        V15,
+        R: EncodeReturn,
+    >(
+        imp: Imp,
+        obj: *mut Object,
+        sel: Sel,
+        args: Self,
+        varargs: (
+            V0,
+            V1,
+            V2,
+            V3,
+            V4,
+            V5,
+            V6,
+            V7,
+            V8,
+            V9,
+            V10,
+            V11,
+            V12,
+            V13,
+            V14,
+            V15,
+        ),
+    ) -> R;
+}
+
 pub trait __TupleExtender<T> {
     #[doc(hidden)]
     type PlusOneArgument;
@@ -530,6 +811,59 @@ macro_rules! message_args_impl {
             }
         }
 
+        unsafe impl<$($t: EncodeConvertArgument),*> __InvokeVariadic for ($($t,)*) {
+            #[inline]
+            unsafe fn __invoke_variadic<
+                V0,
+                V1,
+                V2,
+                V3,
+                V4,
+                V5,
+                V6,
+                V7,
+                V8,
+                V9,
+                V10,
+                V11,
+                V12,
+                V13,
+                V14,
+                V15,
+                R: EncodeReturn,
+            >(
+                imp: Imp,
+                obj: *mut Object,
+                sel: Sel,
+                ($($a,)*): Self,
+                (v0, v1, v2, v3, v4, v5, v6, v7, v8, v9, v10, v11, v12, v13, v14, v15): (V0, V1, V2, V3, V4, V5, V6, V7, V8, V9, V10, V11, V12, V13, V14, V15),
+            ) -> R {
+                $(let $a = EncodeConvertArgument::__into_argument($a);)*
+
+                // Same as above, except that the function pointer must be
+                // variadic, since the calling convention may differ for
+                // variadic functions (e.g. on Apple's ARM64 platforms, all
+                // variadic arguments are passed on the stack).
+                #[cfg(not(feature = "unstable-c-unwind"))]
+                let imp: unsafe extern "C" fn(*mut Object, Sel $(, $t::__Inner)*, ...) -> R = unsafe {
+                    mem::transmute(imp)
+                };
+                #[cfg(feature = "unstable-c-unwind")]
+                let imp: unsafe extern "C-unwind" fn(*mut Object, Sel $(, $t::__Inner)*, ...) -> R = unsafe {
+                    mem::transmute(imp)
+                };
+                let result = unsafe {
+                    imp(obj, sel $(, $a.0)*, v0, v1, v2, v3, v4, v5, v6, v7, v8, v9, v10, v11, v12, v13, v14, v15)
+                };
+
+                $(
+                    // SAFETY: Same as above.
+                    unsafe { <$t as EncodeConvertArgument>::__process_after_message_send($a.1) };
+                )*
+                result
+            }
+        }
+
         impl<$($t,)* T> __TupleExtender<T> for ($($t,)*) {
             type PlusOneArgument = ($($t,)* T,);
 
@@ -647,11 +981,14 @@ message_args_impl!(
 );
 
 #[cfg(test)]
+// The tests rely on `test_utils`, which uses ivars and protocols
+#[cfg(not(feature = "unstable-objfw"))]
 mod tests {
     use super::*;
     use crate::rc::{Id, Owned};
+    use crate::runtime::NSObject;
     use crate::test_utils;
-    use crate::{msg_send, msg_send_id};
+    use crate::{extern_methods, msg_send, msg_send_id};
 
     #[allow(unused)]
     fn test_different_receivers(mut obj: Id<Object, Owned>) {
@@ -671,6 +1008,28 @@ mod tests {
         }
     }
 
+    #[allow(unused)]
+    fn test_variadic(obj: &Object, cls: &Class) {
+        unsafe {
+            let _: () = msg_send![obj, a: 1i32, ...(2u8, 3.0f32, obj)];
+            let _: () = msg_send![cls, a: 1i32, ...()];
+            let args = (true, ptr::null::<Object>());
+            let _: () = msg_send![super(obj, cls), a: 1i32, b: 2i32, ...args];
+            let _: Id<Object> = msg_send_id![cls, a: 1i32, ...(obj, Some(obj), None::<&Object>)];
+        }
+    }
+
+    extern_methods!(
+        #[allow(dead_code)]
+        unsafe impl NSObject {
+            #[method(variadic:)]
+            fn variadic_class(arg: i32, args: ...) -> i32;
+
+            #[method_id(variadic:and:)]
+            unsafe fn variadic_instance(&self, arg: i32, arg2: &Object, args: ...) -> Id<Self>;
+        }
+    );
+
     #[test]
     fn test_send_message() {
         let mut obj = test_utils::custom_object();
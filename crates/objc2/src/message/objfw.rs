@@ -0,0 +1,125 @@
+use core::hint;
+use core::mem;
+
+use crate::encode::Encoding;
+use crate::encode::__unstable::EncodeReturn;
+use crate::ffi;
+use crate::runtime::{Class, Imp, Object, Sel};
+use crate::MessageArguments;
+
+/// ObjFW has separate `_stret` lookup functions for methods whose return
+/// value is passed in memory through a hidden first argument.
+///
+/// The implementation returned for an existing method is the same for both
+/// lookup functions, but if the method is not found, the forwarding handler
+/// must know where the arguments are, so we must use the right one.
+trait MsgLookupFn: EncodeReturn {
+    const STRET: bool;
+}
+
+impl<T: EncodeReturn> MsgLookupFn for T {
+    const STRET: bool = is_stret(&T::ENCODING_RETURN, mem::size_of::<T>());
+}
+
+/// If the size of an object is larger than two eightbytes, it has class
+/// MEMORY. If the type has class MEMORY, then the caller provides space for
+/// the return value and passes the address of this storage.
+///
+/// <https://www.uclibc.org/docs/psABI-x86_64.pdf>
+#[cfg(target_arch = "x86_64")]
+const fn is_stret(_encoding: &Encoding, size: usize) -> bool {
+    size > 16
+}
+
+/// Structures and unions are always returned in memory on the System V i386
+/// ABI (unlike on Apple's platforms).
+///
+/// <https://www.uclibc.org/docs/psABI-i386.pdf>
+#[cfg(target_arch = "x86")]
+const fn is_stret(encoding: &Encoding, _size: usize) -> bool {
+    matches!(encoding, Encoding::Struct(_, _) | Encoding::Union(_, _))
+}
+
+/// Any composite type larger than 4 bytes is returned in memory.
+///
+/// <https://developer.arm.com/documentation/ihi0042/latest>
+#[cfg(target_arch = "arm")]
+const fn is_stret(encoding: &Encoding, size: usize) -> bool {
+    matches!(encoding, Encoding::Struct(_, _) | Encoding::Union(_, _)) && size > 4
+}
+
+/// Other architectures (e.g. aarch64) pass the address of the return value
+/// in a separate register, so the arguments are unaffected.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "arm")))]
+const fn is_stret(_encoding: &Encoding, _size: usize) -> bool {
+    false
+}
+
+#[inline]
+fn unwrap_msg_send_fn(msg_send_fn: Option<Imp>) -> Imp {
+    match msg_send_fn {
+        Some(msg_send_fn) => msg_send_fn,
+        None => {
+            // SAFETY: This will never be NULL, even if the selector is not
+            // found a callable function pointer will still be returned!
+            //
+            // `clang` doesn't insert a NULL check here either.
+            unsafe { hint::unreachable_unchecked() }
+        }
+    }
+}
+
+#[track_caller]
+pub(crate) unsafe fn send_unverified<A, R>(receiver: *mut Object, sel: Sel, args: A) -> R
+where
+    A: MessageArguments,
+    R: EncodeReturn,
+{
+    // If `receiver` is NULL, objc_msg_lookup will return a standard C-method
+    // taking two arguments, the receiver and the selector. Transmuting and
+    // calling such a function with multiple parameters is UB, so instead we
+    // just return NULL directly.
+    if receiver.is_null() {
+        // SAFETY: Caller guarantees that messages to NULL-receivers only
+        // return pointers, and a mem::zeroed pointer is just a NULL-pointer.
+        return unsafe { mem::zeroed() };
+    }
+
+    let msg_send_fn = if R::STRET {
+        unsafe { ffi::objc_msg_lookup_stret(receiver.cast(), sel.as_ptr()) }
+    } else {
+        unsafe { ffi::objc_msg_lookup(receiver.cast(), sel.as_ptr()) }
+    };
+    let msg_send_fn = unwrap_msg_send_fn(msg_send_fn);
+    unsafe { A::__invoke(msg_send_fn, receiver, sel, args) }
+}
+
+#[track_caller]
+pub(crate) unsafe fn send_super_unverified<A, R>(
+    receiver: *mut Object,
+    superclass: &Class,
+    sel: Sel,
+    args: A,
+) -> R
+where
+    A: MessageArguments,
+    R: EncodeReturn,
+{
+    if receiver.is_null() {
+        // SAFETY: Same as in `send_unverified`.
+        return unsafe { mem::zeroed() };
+    }
+
+    let superclass: *const Class = superclass;
+    let sup = ffi::objc_super {
+        receiver: receiver.cast(),
+        super_class: superclass.cast(),
+    };
+    let msg_send_fn = if R::STRET {
+        unsafe { ffi::objc_msg_lookup_super_stret(&sup, sel.as_ptr()) }
+    } else {
+        unsafe { ffi::objc_msg_lookup_super(&sup, sel.as_ptr()) }
+    };
+    let msg_send_fn = unwrap_msg_send_fn(msg_send_fn);
+    unsafe { A::__invoke(msg_send_fn, receiver, sel, args) }
+}
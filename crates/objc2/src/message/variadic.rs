@@ -0,0 +1,274 @@
+//! Support for sending messages to variadic methods.
+use core::ffi::c_void;
+use core::ptr::{self, NonNull};
+use std::os::raw::c_int;
+
+use crate::encode::__unstable::EncodeReturn;
+use crate::encode::{Encode, RefEncode};
+use crate::runtime::{Bool, Imp, Object, Sel};
+
+use super::{__InvokeVariadic, MessageArguments};
+
+/// Types that may be passed as a variadic argument to an Objective-C method.
+///
+/// Arguments passed through the `...` part of a C function signature undergo
+/// the [default argument promotions], which this trait performs; for example,
+/// `i8`, `u16` and `bool` are passed as `c_int`, and `f32` is passed as
+/// `f64`.
+///
+/// This is implemented for the primitive integer and floating-point types,
+/// [`Bool`], [`Sel`], and for pointers and references to types that
+/// implement [`Encode`] or [`RefEncode`].
+///
+/// [default argument promotions]: https://en.cppreference.com/w/c/language/conversion#Default_argument_promotions
+///
+///
+/// # Safety
+///
+/// This is a sealed trait, and should not need to be implemented. Open an
+/// issue if you know a use-case where this restrition should be lifted!
+pub unsafe trait VariadicArgument {
+    /// The type that the argument is promoted to.
+    #[doc(hidden)]
+    type __Promoted;
+
+    #[doc(hidden)]
+    fn __promote(self) -> Self::__Promoted;
+}
+
+macro_rules! promote_impl {
+    ($($t:ty => $promoted:ty),* $(,)?) => {$(
+        unsafe impl VariadicArgument for $t {
+            type __Promoted = $promoted;
+
+            #[inline]
+            fn __promote(self) -> $promoted {
+                self as $promoted
+            }
+        }
+    )*};
+}
+
+promote_impl!(
+    i8 => c_int,
+    u8 => c_int,
+    i16 => c_int,
+    u16 => c_int,
+    bool => c_int,
+    f32 => f64,
+    i32 => i32,
+    u32 => u32,
+    i64 => i64,
+    u64 => u64,
+    isize => isize,
+    usize => usize,
+    f64 => f64,
+);
+
+unsafe impl VariadicArgument for Bool {
+    type __Promoted = c_int;
+
+    #[inline]
+    fn __promote(self) -> c_int {
+        self.as_bool() as c_int
+    }
+}
+
+unsafe impl VariadicArgument for Sel {
+    type __Promoted = *const c_void;
+
+    #[inline]
+    fn __promote(self) -> *const c_void {
+        self.as_ptr().cast()
+    }
+}
+
+unsafe impl<T: ?Sized> VariadicArgument for *const T
+where
+    *const T: Encode,
+{
+    type __Promoted = Self;
+
+    #[inline]
+    fn __promote(self) -> Self {
+        self
+    }
+}
+
+unsafe impl<T: ?Sized> VariadicArgument for *mut T
+where
+    *mut T: Encode,
+{
+    type __Promoted = Self;
+
+    #[inline]
+    fn __promote(self) -> Self {
+        self
+    }
+}
+
+unsafe impl<T: RefEncode + ?Sized> VariadicArgument for NonNull<T> {
+    type __Promoted = *mut T;
+
+    #[inline]
+    fn __promote(self) -> *mut T {
+        self.as_ptr()
+    }
+}
+
+unsafe impl<T: RefEncode + ?Sized> VariadicArgument for &T {
+    type __Promoted = *const T;
+
+    #[inline]
+    fn __promote(self) -> *const T {
+        self
+    }
+}
+
+unsafe impl<T: RefEncode + ?Sized> VariadicArgument for &mut T {
+    type __Promoted = *mut T;
+
+    #[inline]
+    fn __promote(self) -> *mut T {
+        self
+    }
+}
+
+unsafe impl<T: RefEncode> VariadicArgument for Option<&T> {
+    type __Promoted = *const T;
+
+    #[inline]
+    fn __promote(self) -> *const T {
+        self.map_or(ptr::null(), |r| r)
+    }
+}
+
+unsafe impl<T: RefEncode> VariadicArgument for Option<&mut T> {
+    type __Promoted = *mut T;
+
+    #[inline]
+    fn __promote(self) -> *mut T {
+        self.map_or(ptr::null_mut(), |r| r)
+    }
+}
+
+unsafe impl<T: RefEncode> VariadicArgument for Option<NonNull<T>> {
+    type __Promoted = *mut T;
+
+    #[inline]
+    fn __promote(self) -> *mut T {
+        self.map_or(ptr::null_mut(), NonNull::as_ptr)
+    }
+}
+
+/// Types that may be used as the variadic arguments of an Objective-C
+/// message.
+///
+/// This is implemented for tuples of up to 16 arguments, where each argument
+/// implements [`VariadicArgument`].
+///
+///
+/// # Safety
+///
+/// This is a sealed trait, and should not need to be implemented. Open an
+/// issue if you know a use-case where this restrition should be lifted!
+pub unsafe trait VariadicArguments {
+    /// Invoke an [`Imp`] with the given object, selector, arguments and
+    /// variadic arguments.
+    #[doc(hidden)]
+    unsafe fn __invoke<T, R>(imp: Imp, obj: *mut Object, sel: Sel, args: T, varargs: Self) -> R
+    where
+        T: __InvokeVariadic,
+        R: EncodeReturn;
+}
+
+// To avoid having to implement a trait for every combination of normal and
+// variadic arguments, the variadic arguments are always padded to 16 with
+// zeroes. This is fine, since the caller is responsible for cleaning up the
+// arguments of a variadic function, and the method only reads the arguments
+// that it was told about (e.g. through a format string or a terminating
+// `nil`).
+macro_rules! variadic_args_impl {
+    (($($v:ident: $V:ident),*) ($($pad:literal),*)) => {
+        unsafe impl<$($V: VariadicArgument),*> VariadicArguments for ($($V,)*) {
+            #[inline]
+            unsafe fn __invoke<T, R>(imp: Imp, obj: *mut Object, sel: Sel, args: T, ($($v,)*): Self) -> R
+            where
+                T: __InvokeVariadic,
+                R: EncodeReturn,
+            {
+                unsafe {
+                    T::__invoke_variadic(imp, obj, sel, args, ($($v.__promote(),)* $($pad,)*))
+                }
+            }
+        }
+    };
+}
+
+/// Implement `VariadicArguments` for the given arguments, and for every
+/// suffix of them (adding a padding argument for each one removed).
+macro_rules! variadic_args_impls {
+    (() ($($pad:literal),*)) => {
+        variadic_args_impl!(() ($($pad),*));
+    };
+    (($v:ident: $V:ident $(, $rest_v:ident: $rest_V:ident)*) ($($pad:literal),*)) => {
+        variadic_args_impl!(($v: $V $(, $rest_v: $rest_V)*) ($($pad),*));
+        variadic_args_impls!(($($rest_v: $rest_V),*) (0usize $(, $pad)*));
+    };
+}
+
+variadic_args_impls!(
+    (
+        a: A,
+        b: B,
+        c: C,
+        d: D,
+        e: E,
+        f: F,
+        g: G,
+        h: H,
+        i: I,
+        j: J,
+        k: K,
+        l: L,
+        m: M,
+        n: N,
+        o: O,
+        p: P
+    )
+    ()
+);
+
+/// The arguments to a variadic method, used by `msg_send!` and
+/// `msg_send_id!`.
+///
+/// Only the normal arguments are part of the method's type encoding, so only
+/// those are verified.
+#[doc(hidden)]
+pub struct Variadic<A, V>(pub A, pub V);
+
+unsafe impl<A, V> MessageArguments for Variadic<A, V>
+where
+    A: __InvokeVariadic,
+    V: VariadicArguments,
+{
+    #[inline]
+    unsafe fn __invoke<R: EncodeReturn>(imp: Imp, obj: *mut Object, sel: Sel, args: Self) -> R {
+        unsafe { V::__invoke(imp, obj, sel, args.0, args.1) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promote() {
+        assert_eq!((-1i8).__promote(), -1 as c_int);
+        assert_eq!(u16::MAX.__promote(), u16::MAX as c_int);
+        assert_eq!(true.__promote(), 1 as c_int);
+        assert_eq!(Bool::NO.__promote(), 0 as c_int);
+        assert_eq!(1.5f32.__promote(), 1.5f64);
+        assert_eq!(None::<&Object>.__promote(), ptr::null());
+    }
+}
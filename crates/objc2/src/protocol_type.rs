@@ -52,7 +52,8 @@ pub unsafe trait ProtocolType {
     ///
     /// Note that some protocols [are not registered with the runtime][p-obj],
     /// depending on various factors. In those cases, this function may return
-    /// `None`.
+    /// `None`. This is always the case on ObjFW, which doesn't keep a
+    /// registry of protocols.
     ///
     /// [p-obj]: https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/ObjectiveC/Chapters/ocProtocols.html#//apple_ref/doc/uid/TP30001163-CH15-TPXREF149
     ///
@@ -63,7 +64,15 @@ pub unsafe trait ProtocolType {
     /// protocol, e.g. if the program is not properly linked to the framework
     /// that defines the protocol.
     fn protocol() -> Option<&'static Protocol> {
-        Protocol::get(Self::NAME)
+        #[cfg(not(feature = "unstable-objfw"))]
+        {
+            Protocol::get(Self::NAME)
+        }
+        // ObjFW doesn't keep a registry of protocols.
+        #[cfg(feature = "unstable-objfw")]
+        {
+            None
+        }
     }
 
     #[doc(hidden)]
@@ -271,6 +271,18 @@ declare_class!(
                 *param = ptr::null_mut();
             }
         }
+
+        #[method(outParamWriteback:)]
+        fn out_param_writeback(param: &mut Id<__RcTestObject, Owned>) {
+            *param = __RcTestObject::new();
+        }
+
+        #[method(optionalOutParamWriteback:)]
+        fn optional_out_param_writeback(param: Option<&mut Option<Id<__RcTestObject, Owned>>>) {
+            if let Some(param) = param {
+                *param = Some(__RcTestObject::new());
+            }
+        }
     }
 );
 
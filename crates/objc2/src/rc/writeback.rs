@@ -7,13 +7,65 @@
 //! address we then work on; instead, we just directly reuse the pointer that
 //! the user provides (since, if it's a mutable pointer, we know that it's not
 //! shared elsewhere in the program, and hence it is safe to modify directly).
+//!
+//! The same types can be used as parameters in `declare_class!`, in which
+//! case the roles are reversed: The caller passes a pointer to a value with
+//! +0 retain count, and expects that any value written to it is autoreleased
+//! (also +0).
 use core::mem::ManuallyDrop;
 use core::ptr::NonNull;
 
 use crate::encode::__unstable::EncodeConvertArgument;
-use crate::rc::{Id, Ownership};
+use crate::rc::{Id, Ownership, Shared};
 use crate::Message;
 
+/// Autoreleases the value stored in an out parameter of a `declare_class!`
+/// method when dropped.
+///
+/// Before the method body is run, the value is retained, such that the
+/// method can treat it as an owned `Id` (and e.g. overwrite it, which will
+/// release the old value). Afterwards, we autorelease whatever value is
+/// stored, so that the caller again gets a value with +0 retain count.
+#[doc(hidden)]
+pub struct __DeclaredOutParamGuard<T: Message> {
+    ptr: Option<NonNull<*mut T>>,
+}
+
+impl<T: Message> __DeclaredOutParamGuard<T> {
+    /// # Safety
+    ///
+    /// The pointer must be valid for reads and writes, and point to either
+    /// NULL or a valid object, for the duration of the method body.
+    #[inline]
+    unsafe fn new(ptr: Option<NonNull<*mut T>>) -> Self {
+        if let Some(ptr) = ptr {
+            // SAFETY: Upheld by caller.
+            let old: Option<Id<T, Shared>> = unsafe { Id::retain(*ptr.as_ptr()) };
+            // The value is now +1, and ownership is transferred to the slot.
+            let _ = ManuallyDrop::new(old);
+        }
+        Self { ptr }
+    }
+}
+
+impl<T: Message> Drop for __DeclaredOutParamGuard<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(ptr) = self.ptr {
+            // SAFETY: The slot is either NULL, or contains a value with +1
+            // retain count that is owned by the slot, since it was retained
+            // in `new`, or written by the method body (which gave up
+            // ownership of it when writing it).
+            let new: Option<Id<T, Shared>> = unsafe { Id::new(*ptr.as_ptr()) };
+            // `objc_autorelease` returns the same pointer, so there's no need
+            // to write the value back into the slot.
+            if let Some(new) = new {
+                let _ = Id::autorelease_inner(new);
+            }
+        }
+    }
+}
+
 // Note the `'static` bound here - this may not be necessary, but I'm unsure
 // of the exact requirements, so we better just keep it for now.
 impl<T: Message + 'static, O: Ownership> EncodeConvertArgument for &mut Id<T, O> {
@@ -33,9 +85,27 @@ impl<T: Message + 'static, O: Ownership> EncodeConvertArgument for &mut Id<T, O>
         NonNull<T>,
     );
 
+    type __DeclaredParamGuard = __DeclaredOutParamGuard<T>;
+
+    #[inline]
+    unsafe fn __declared_param_guard(inner: &Self::__Inner) -> Self::__DeclaredParamGuard {
+        // SAFETY: Upheld by caller.
+        #[cfg(debug_assertions)]
+        if unsafe { *inner.as_ptr() }.is_null() {
+            panic!("found that NULL was passed in `&mut Id<_, _>`, which is UB! You should handle this with `&mut Option<Id<_, _>>` instead");
+        }
+        // SAFETY: Upheld by caller.
+        unsafe { __DeclaredOutParamGuard::new(Some(*inner)) }
+    }
+
     #[inline]
-    fn __from_declared_param(_inner: Self::__Inner) -> Self {
-        todo!("`&mut Id<_, _>` is not supported in `declare_class!` yet")
+    fn __from_declared_param(inner: Self::__Inner) -> Self {
+        // `Id` is `#[repr(transparent)]` over `NonNull`, which has the same
+        // layout as `*mut T`.
+        let ptr: NonNull<Id<T, O>> = inner.cast();
+        // SAFETY: The pointer came from Objective-C, and the guard from
+        // `__declared_param_guard` has made the value owned by us.
+        unsafe { &mut *ptr.as_ptr() }
     }
 
     #[inline]
@@ -114,9 +184,20 @@ impl<T: Message + 'static, O: Ownership> EncodeConvertArgument for &mut Option<I
 
     type __StoredBeforeMessage = (Self::__Inner, *mut T);
 
+    type __DeclaredParamGuard = __DeclaredOutParamGuard<T>;
+
     #[inline]
-    fn __from_declared_param(_inner: Self::__Inner) -> Self {
-        todo!("`&mut Option<Id<_, _>>` is not supported in `declare_class!` yet")
+    unsafe fn __declared_param_guard(inner: &Self::__Inner) -> Self::__DeclaredParamGuard {
+        // SAFETY: Upheld by caller.
+        unsafe { __DeclaredOutParamGuard::new(Some(*inner)) }
+    }
+
+    #[inline]
+    fn __from_declared_param(inner: Self::__Inner) -> Self {
+        // `Option<Id<T, _>>` has the same memory layout as `*mut T`.
+        let ptr: NonNull<Option<Id<T, O>>> = inner.cast();
+        // SAFETY: Same as for `&mut Id`.
+        unsafe { &mut *ptr.as_ptr() }
     }
 
     #[inline]
@@ -161,9 +242,22 @@ impl<T: Message + 'static, O: Ownership> EncodeConvertArgument for Option<&mut I
 
     type __StoredBeforeMessage = Option<(NonNull<*mut T>, NonNull<T>)>;
 
+    type __DeclaredParamGuard = __DeclaredOutParamGuard<T>;
+
+    #[inline]
+    unsafe fn __declared_param_guard(inner: &Self::__Inner) -> Self::__DeclaredParamGuard {
+        if let Some(inner) = inner {
+            // SAFETY: Checked by caller
+            unsafe { <&mut Id<T, O>>::__declared_param_guard(inner) }
+        } else {
+            // SAFETY: No pointer is given.
+            unsafe { __DeclaredOutParamGuard::new(None) }
+        }
+    }
+
     #[inline]
-    fn __from_declared_param(_inner: Self::__Inner) -> Self {
-        todo!("`Option<&mut Id<_, _>>` is not supported in `declare_class!` yet")
+    fn __from_declared_param(inner: Self::__Inner) -> Self {
+        inner.map(<&mut Id<T, O>>::__from_declared_param)
     }
 
     #[inline]
@@ -190,9 +284,17 @@ impl<T: Message + 'static, O: Ownership> EncodeConvertArgument for Option<&mut O
 
     type __StoredBeforeMessage = Option<(NonNull<*mut T>, *mut T)>;
 
+    type __DeclaredParamGuard = __DeclaredOutParamGuard<T>;
+
+    #[inline]
+    unsafe fn __declared_param_guard(inner: &Self::__Inner) -> Self::__DeclaredParamGuard {
+        // SAFETY: Upheld by caller.
+        unsafe { __DeclaredOutParamGuard::new(*inner) }
+    }
+
     #[inline]
-    fn __from_declared_param(_inner: Self::__Inner) -> Self {
-        todo!("`Option<&mut Option<Id<_, _>>>` is not supported in `declare_class!` yet")
+    fn __from_declared_param(inner: Self::__Inner) -> Self {
+        inner.map(<&mut Option<Id<T, O>>>::__from_declared_param)
     }
 
     #[inline]
@@ -298,6 +400,72 @@ mod tests {
         let _: () = unsafe { msg_send![cls, outParamNull: &mut param] };
     }
 
+    #[test]
+    fn test_declared_out_param() {
+        let mut expected = __ThreadTestData::current();
+        let cls = __RcTestObject::class();
+
+        let mut param: Id<__RcTestObject, Owned> = __RcTestObject::new();
+        expected.alloc += 1;
+        expected.init += 1;
+        expected.assert_current();
+
+        autoreleasepool(|_| {
+            let _: () = unsafe { msg_send![cls, outParamWriteback: &mut param] };
+            // The old value is retained by the method, and then released
+            // both when it is overwritten, and by the caller afterwards.
+            expected.retain += 1;
+            expected.release += 2;
+            expected.dealloc += 1;
+            // The new value is autoreleased by the method, and retained by
+            // the caller.
+            expected.alloc += 1;
+            expected.init += 1;
+            expected.autorelease += 1;
+            expected.retain += 1;
+            expected.assert_current();
+        });
+        expected.release += 1;
+        expected.assert_current();
+
+        drop(param);
+        expected.release += 1;
+        expected.dealloc += 1;
+        expected.assert_current();
+    }
+
+    #[test]
+    fn test_declared_optional_out_param() {
+        let mut expected = __ThreadTestData::current();
+        let cls = __RcTestObject::class();
+
+        let _: () = unsafe {
+            msg_send![
+                cls,
+                optionalOutParamWriteback: None::<&mut Option<Id<__RcTestObject, Owned>>>
+            ]
+        };
+        expected.assert_current();
+
+        let mut param: Option<Id<__RcTestObject, Owned>> = None;
+        autoreleasepool(|_| {
+            let _: () = unsafe { msg_send![cls, optionalOutParamWriteback: Some(&mut param)] };
+            expected.alloc += 1;
+            expected.init += 1;
+            expected.autorelease += 1;
+            expected.retain += 1;
+            expected.assert_current();
+        });
+        expected.release += 1;
+        expected.assert_current();
+
+        assert!(param.is_some());
+        drop(param);
+        expected.release += 1;
+        expected.dealloc += 1;
+        expected.assert_current();
+    }
+
     // TODO: Fix this in release mode with Apple's runtime
     const AUTORELEASE_SKIPPED: bool = cfg!(feature = "gnustep-1-7");
 
@@ -1,16 +1,27 @@
+#[cfg(feature = "panic-to-exception")]
+use core::ffi::c_void;
 use core::slice;
 use core::str;
 use std::os::raw::c_char;
 
 use crate::ffi::NSUInteger;
 use crate::msg_send;
+#[cfg(feature = "panic-to-exception")]
+use crate::msg_send_id;
 use crate::rc::AutoreleasePool;
+#[cfg(feature = "panic-to-exception")]
+use crate::rc::{Id, Shared};
+#[cfg(feature = "panic-to-exception")]
+use crate::runtime::Class;
 use crate::runtime::NSObject;
 
 #[cfg(feature = "apple")]
 pub const UTF8_ENCODING: usize = 4;
 #[cfg(feature = "gnustep-1-7")]
 pub const UTF8_ENCODING: i32 = 4;
+// `OFStringEncodingUTF8`
+#[cfg(feature = "unstable-objfw")]
+pub const UTF8_ENCODING: i32 = 0;
 
 /// The number of UTF-8 code units in the given string.
 ///
@@ -67,3 +78,20 @@ pub unsafe fn nsstring_to_str<'r, 's: 'r, 'p: 'r>(
     // TODO: Always UTF-8, so should we use `from_utf8_unchecked`?
     str::from_utf8(bytes).unwrap()
 }
+
+/// Create a new `NSString` with the contents of the given string.
+///
+/// Returns [`None`] if the `NSString` class is not available.
+#[cfg(feature = "panic-to-exception")]
+pub fn nsstring_new(s: &str) -> Option<Id<NSObject, Shared>> {
+    let cls = Class::get("NSString")?;
+    let bytes: *const c_void = s.as_ptr().cast();
+    unsafe {
+        msg_send_id![
+            msg_send_id![cls, alloc],
+            initWithBytes: bytes,
+            length: s.len(),
+            encoding: UTF8_ENCODING,
+        ]
+    }
+}
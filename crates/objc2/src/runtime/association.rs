@@ -0,0 +1,468 @@
+//! Associated objects, see [`Object::associated`] and
+//! [`Object::set_associated`].
+#[cfg(not(feature = "unstable-objfw"))]
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr;
+
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::declare::IvarDrop;
+use crate::ffi;
+use crate::rc::{Id, Shared};
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::rc::{Owned, WeakId};
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::runtime::NSObject;
+use crate::runtime::Object;
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::{declare_class, msg_send_id, ClassType};
+use crate::{Encode, Message};
+
+/// A key that identifies a value associated with an object.
+///
+/// Associated objects allow attaching extra state to objects whose class you
+/// don't control, and they are released together with the object they are
+/// associated with. See [`Object::associated`] and
+/// [`Object::set_associated`] for how to use the key.
+///
+/// The type parameter `T` is the type of the associated value, and must
+/// implement [`AssociatedValue`]. Since the runtime identifies keys by their
+/// address, these are almost always declared in a `static`.
+///
+/// See also [Apple's documentation on associative references][assoc].
+///
+/// [assoc]: https://developer.apple.com/documentation/objectivec/1418509-objc_setassociatedobject?language=objc
+///
+///
+/// # Example
+///
+/// Attach a tag and a weak reference to an object that we didn't create.
+///
+#[cfg_attr(feature = "apple", doc = "```")]
+#[cfg_attr(not(feature = "apple"), doc = "```no_run")]
+/// use objc2::rc::{Id, WeakId};
+/// use objc2::runtime::{AssociatedKey, NSObject};
+///
+/// static TAG: AssociatedKey<usize> = AssociatedKey::new();
+/// static PARENT: AssociatedKey<WeakId<NSObject>> = AssociatedKey::new();
+///
+/// let obj = Id::into_shared(NSObject::new());
+/// let parent = Id::into_shared(NSObject::new());
+///
+/// obj.set_associated(&TAG, Some(42));
+/// obj.set_associated(&PARENT, Some(WeakId::new(&parent)));
+///
+/// assert_eq!(obj.associated(&TAG), Some(42));
+/// let weak = obj.associated(&PARENT).unwrap();
+/// assert_eq!(weak.load(), Some(parent.clone()));
+///
+/// drop(parent);
+/// assert_eq!(obj.associated(&PARENT).unwrap().load(), None);
+/// ```
+pub struct AssociatedKey<T: ?Sized> {
+    // Note: This is also what ensures that each static has a unique address,
+    // since that is not guaranteed for zero-sized types.
+    policy: ffi::objc_AssociationPolicy,
+    p: PhantomData<fn() -> T>,
+}
+
+impl<T: ?Sized> AssociatedKey<T> {
+    /// Create a new key.
+    ///
+    /// Objects associated with keys created with this are retained
+    /// atomically.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            policy: ffi::OBJC_ASSOCIATION_RETAIN,
+            p: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn as_ptr(&'static self) -> *const c_void {
+        let ptr: *const Self = self;
+        ptr.cast()
+    }
+}
+
+impl<T: ?Sized> AssociatedKey<Id<T, Shared>> {
+    /// Create a new key whose objects are retained non-atomically.
+    ///
+    /// This is slightly faster than [`AssociatedKey::new`], but is not safe
+    /// to use if the value is changed from one thread while being read on
+    /// another, so it requires that the object it is associated with is not
+    /// `Sync`.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The associated value must not be set and read concurrently from
+    /// different threads.
+    #[inline]
+    pub const unsafe fn new_nonatomic() -> Self {
+        Self {
+            policy: ffi::OBJC_ASSOCIATION_RETAIN_NONATOMIC,
+            p: PhantomData,
+        }
+    }
+
+    /// Create a new key whose objects are copied atomically when set.
+    ///
+    /// This is the equivalent of a `copy` property.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// Sending `copy` to an object of type `T` must return another object of
+    /// type `T`.
+    #[inline]
+    pub const unsafe fn new_copy() -> Self {
+        Self {
+            policy: ffi::OBJC_ASSOCIATION_COPY,
+            p: PhantomData,
+        }
+    }
+
+    /// Create a new key whose objects are copied non-atomically when set.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The requirements of both [`new_nonatomic`](Self::new_nonatomic) and
+    /// [`new_copy`](Self::new_copy) must be upheld.
+    #[inline]
+    pub const unsafe fn new_copy_nonatomic() -> Self {
+        Self {
+            policy: ffi::OBJC_ASSOCIATION_COPY_NONATOMIC,
+            p: PhantomData,
+        }
+    }
+}
+
+// SAFETY: The key is just an address, so it is safe to share between threads
+// (the requirements for thread-safety of the value itself is handled by the
+// `Object` methods).
+unsafe impl<T: ?Sized> Sync for AssociatedKey<T> {}
+unsafe impl<T: ?Sized> Send for AssociatedKey<T> {}
+
+impl<T: ?Sized> fmt::Debug for AssociatedKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssociatedKey")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) mod private {
+    pub trait Sealed {}
+}
+
+/// Types that can be associated with an object using [`AssociatedKey`].
+///
+/// This is a sealed trait, implemented for:
+/// - [`Id<T, Shared>`], which is retained (or copied, depending on the key)
+///   by the object it is associated with.
+/// - [`WeakId<T>`], which does not keep the target object alive. This is
+///   stored in an internal helper object, that is retained instead. This is
+///   not available on ObjFW, since the helper object needs an ivar.
+/// - `'static` [`Encode`] values that are `Copy`, and no larger than a
+///   pointer, such as integers, `Sel` or `&'static Class`. These are stored
+///   directly (`OBJC_ASSOCIATION_ASSIGN`), and since the runtime can't tell
+///   such values apart from a missing value, a value with all bits set to
+///   zero will be read back as [`None`].
+///
+/// Using a value that is larger than a pointer fails to compile:
+///
+/// ```compile_fail
+/// use objc2::runtime::{AssociatedKey, NSObject};
+///
+/// static KEY: AssociatedKey<[usize; 2]> = AssociatedKey::new();
+///
+/// let obj = NSObject::new();
+/// obj.set_associated(&KEY, Some([1, 2]));
+/// ```
+///
+/// [`WeakId<T>`]: crate::rc::WeakId
+pub trait AssociatedValue: private::Sealed + Sized {
+    #[doc(hidden)]
+    fn __set(obj: &Object, key: &'static AssociatedKey<Self>, value: Option<Self>);
+
+    #[doc(hidden)]
+    fn __get(obj: &Object, key: &'static AssociatedKey<Self>) -> Option<Self>;
+}
+
+/// # Safety
+///
+/// The value must be valid for the given policy.
+unsafe fn set_raw<T: ?Sized>(
+    obj: &Object,
+    key: &'static AssociatedKey<T>,
+    value: *mut ffi::objc_object,
+    policy: ffi::objc_AssociationPolicy,
+) {
+    // SAFETY: The object and key pointers are valid, and the caller upholds
+    // that the value is valid for the policy.
+    unsafe { ffi::objc_setAssociatedObject(obj.as_ptr() as *mut _, key.as_ptr(), value, policy) }
+}
+
+fn get_raw<T: ?Sized>(obj: &Object, key: &'static AssociatedKey<T>) -> *mut ffi::objc_object {
+    // SAFETY: The object and key pointers are valid.
+    let ptr = unsafe { ffi::objc_getAssociatedObject(obj.as_ptr(), key.as_ptr()) };
+    ptr as *mut ffi::objc_object
+}
+
+impl<T: Message> private::Sealed for Id<T, Shared> {}
+
+impl<T: Message> AssociatedValue for Id<T, Shared> {
+    fn __set(obj: &Object, key: &'static AssociatedKey<Self>, value: Option<Self>) {
+        let ptr = value.as_ref().map_or(ptr::null(), Id::as_ptr);
+        // SAFETY: The object is kept alive by `value` until the runtime has
+        // retained (or copied) it.
+        unsafe { set_raw(obj, key, ptr as *mut _, key.policy) };
+    }
+
+    fn __get(obj: &Object, key: &'static AssociatedKey<Self>) -> Option<Self> {
+        let ptr = get_raw(obj, key);
+        // SAFETY: The key is typed, so the object was stored as a `T`, and
+        // `objc_getAssociatedObject` returns the object at +0.
+        unsafe { Id::retain(ptr.cast()) }
+    }
+}
+
+#[cfg(not(feature = "unstable-objfw"))]
+declare_class!(
+    /// Helper object for storing weak references as associated objects,
+    /// since the runtime doesn't provide a policy for that.
+    struct WeakAssociation {
+        weak: IvarDrop<Option<Box<WeakId<Object>>>, "_weak">,
+    }
+
+    mod weak_association_ivars;
+
+    unsafe impl ClassType for WeakAssociation {
+        type Super = NSObject;
+        const NAME: &'static str = "__ObjC2WeakAssociation";
+    }
+);
+
+#[cfg(not(feature = "unstable-objfw"))]
+impl<T: Message> private::Sealed for WeakId<T> {}
+
+#[cfg(not(feature = "unstable-objfw"))]
+impl<T: Message> AssociatedValue for WeakId<T> {
+    fn __set(obj: &Object, key: &'static AssociatedKey<Self>, value: Option<Self>) {
+        let holder = value.map(|value| {
+            let mut holder: Id<WeakAssociation, Owned> =
+                unsafe { msg_send_id![WeakAssociation::class(), new] };
+            let weak = match value.load() {
+                // SAFETY: Any object can be referenced as `Object`.
+                Some(obj) => WeakId::new(&unsafe { Id::cast::<Object>(obj) }),
+                None => WeakId::default(),
+            };
+            *holder.weak = Some(Box::new(weak));
+            holder
+        });
+        let ptr = holder.as_ref().map_or(ptr::null(), Id::as_ptr);
+        // SAFETY: The holder is kept alive until the runtime has retained it.
+        unsafe { set_raw(obj, key, ptr as *mut _, ffi::OBJC_ASSOCIATION_RETAIN) };
+    }
+
+    fn __get(obj: &Object, key: &'static AssociatedKey<Self>) -> Option<Self> {
+        let ptr = get_raw(obj, key);
+        // SAFETY: The key is typed, so the object is a `WeakAssociation`.
+        let holder: Id<WeakAssociation, Shared> = unsafe { Id::retain(ptr.cast()) }?;
+        let weak = (*holder.weak)
+            .as_ref()
+            .expect("weak association initialized");
+        Some(match weak.load() {
+            // SAFETY: The object was a `T` when the reference was stored.
+            Some(obj) => WeakId::new(&unsafe { Id::cast::<T>(obj) }),
+            None => WeakId::default(),
+        })
+    }
+}
+
+/// Checks at compile-time that a value can be stored directly in the
+/// associated object pointer.
+struct AssertFitsInPointer<T>(PhantomData<T>);
+
+impl<T> AssertFitsInPointer<T> {
+    const ASSERT: () = assert!(
+        mem::size_of::<T>() <= mem::size_of::<*mut ffi::objc_object>(),
+        "associated value must not be larger than a pointer",
+    );
+}
+
+impl<T: Encode + Copy + 'static> private::Sealed for T {}
+
+impl<T: Encode + Copy + 'static> AssociatedValue for T {
+    fn __set(obj: &Object, key: &'static AssociatedKey<Self>, value: Option<Self>) {
+        #[allow(clippy::let_unit_value)]
+        let _ = AssertFitsInPointer::<T>::ASSERT;
+        let mut raw: *mut ffi::objc_object = ptr::null_mut();
+        if let Some(value) = value {
+            let dst: *mut *mut ffi::objc_object = &mut raw;
+            // SAFETY: The value fits inside the pointer, and is `Copy` so it
+            // doesn't have a destructor that we need to worry about.
+            unsafe { ptr::write_unaligned(dst.cast::<T>(), value) };
+        }
+        // SAFETY: The value is not an object, but it is not retained either.
+        unsafe { set_raw(obj, key, raw, ffi::OBJC_ASSOCIATION_ASSIGN) };
+    }
+
+    fn __get(obj: &Object, key: &'static AssociatedKey<Self>) -> Option<Self> {
+        let raw = get_raw(obj, key);
+        if raw.is_null() {
+            return None;
+        }
+        let src: *const *mut ffi::objc_object = &raw;
+        // SAFETY: The key is typed, so the value was written by `__set`
+        // above, and it fits, see `AssertFitsInPointer`.
+        Some(unsafe { ptr::read_unaligned(src.cast::<T>()) })
+    }
+}
+
+impl Object {
+    /// Get the value associated with this object for the given key, or
+    /// [`None`] if no value has been set.
+    ///
+    /// See [`AssociatedKey`] for details.
+    #[doc(alias = "objc_getAssociatedObject")]
+    pub fn associated<T: AssociatedValue>(&self, key: &'static AssociatedKey<T>) -> Option<T> {
+        T::__get(self, key)
+    }
+
+    /// Associate a value with this object for the given key, or remove the
+    /// current association by passing [`None`].
+    ///
+    /// Any previously associated value for the key is released.
+    ///
+    /// See [`AssociatedKey`] for details.
+    #[doc(alias = "objc_setAssociatedObject")]
+    pub fn set_associated<T: AssociatedValue>(
+        &self,
+        key: &'static AssociatedKey<T>,
+        value: Option<T>,
+    ) {
+        T::__set(self, key, value)
+    }
+
+    /// Remove all associations from this object.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// This also removes associations that other code may have set up, and
+    /// may rely on to be present; Apple's documentation recommends only using
+    /// this to restore an object to "pristine condition".
+    #[doc(alias = "objc_removeAssociatedObjects")]
+    pub unsafe fn remove_associated_objects(&self) {
+        // SAFETY: The object pointer is valid, rest is upheld by the caller.
+        unsafe { ffi::objc_removeAssociatedObjects(self.as_ptr() as *mut _) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rc::{__RcTestObject, __ThreadTestData, autoreleasepool};
+    use crate::runtime::{Class, NSObject, Sel};
+    use crate::{sel, ClassType};
+
+    #[test]
+    fn test_retain() {
+        static KEY: AssociatedKey<Id<__RcTestObject>> = AssociatedKey::new();
+
+        let obj = NSObject::new();
+        assert_eq!(obj.associated(&KEY), None);
+
+        let deallocs = __ThreadTestData::current().dealloc;
+        autoreleasepool(|_| {
+            let value = Id::into_shared(__RcTestObject::new());
+            obj.set_associated(&KEY, Some(value.clone()));
+            drop(value);
+            // Kept alive by the association
+            assert!(obj.associated(&KEY).is_some());
+        });
+        assert_eq!(__ThreadTestData::current().dealloc, deallocs);
+
+        // Released together with the object
+        drop(obj);
+        assert_eq!(__ThreadTestData::current().dealloc, deallocs + 1);
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        static KEY: AssociatedKey<Id<NSObject>> = AssociatedKey::new();
+
+        let obj = NSObject::new();
+        let first = Id::into_shared(NSObject::new());
+        let second = Id::into_shared(NSObject::new());
+
+        obj.set_associated(&KEY, Some(first.clone()));
+        obj.set_associated(&KEY, Some(second.clone()));
+        assert_eq!(obj.associated(&KEY), Some(second.clone()));
+
+        obj.set_associated(&KEY, None);
+        assert_eq!(obj.associated(&KEY), None);
+
+        obj.set_associated(&KEY, Some(first.clone()));
+        unsafe { obj.remove_associated_objects() };
+        assert_eq!(obj.associated(&KEY), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_weak() {
+        static KEY: AssociatedKey<WeakId<NSObject>> = AssociatedKey::new();
+
+        let obj = NSObject::new();
+        let target = Id::into_shared(NSObject::new());
+
+        obj.set_associated(&KEY, Some(WeakId::new(&target)));
+        let weak = obj.associated(&KEY).unwrap();
+        assert_eq!(weak.load(), Some(target.clone()));
+
+        drop(weak);
+        drop(target);
+        assert_eq!(obj.associated(&KEY).unwrap().load(), None);
+    }
+
+    #[test]
+    fn test_encode_values() {
+        static INT: AssociatedKey<u32> = AssociatedKey::new();
+        static SEL: AssociatedKey<Sel> = AssociatedKey::new();
+        static CLS: AssociatedKey<&'static Class> = AssociatedKey::new();
+
+        let obj = NSObject::new();
+        assert_eq!(obj.associated(&INT), None);
+
+        obj.set_associated(&INT, Some(42));
+        obj.set_associated(&SEL, Some(sel!(description)));
+        obj.set_associated(&CLS, Some(NSObject::class()));
+
+        assert_eq!(obj.associated(&INT), Some(42));
+        assert_eq!(obj.associated(&SEL), Some(sel!(description)));
+        assert_eq!(obj.associated(&CLS), Some(NSObject::class()));
+
+        // Zero cannot be distinguished from no value
+        obj.set_associated(&INT, Some(0));
+        assert_eq!(obj.associated(&INT), None);
+    }
+
+    #[test]
+    fn test_keys_are_distinct() {
+        static KEY1: AssociatedKey<usize> = AssociatedKey::new();
+        static KEY2: AssociatedKey<usize> = AssociatedKey::new();
+
+        let obj = NSObject::new();
+        obj.set_associated(&KEY1, Some(1));
+        obj.set_associated(&KEY2, Some(2));
+        assert_eq!(obj.associated(&KEY1), Some(1));
+        assert_eq!(obj.associated(&KEY2), Some(2));
+    }
+}
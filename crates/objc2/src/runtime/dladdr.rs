@@ -0,0 +1,57 @@
+//! Bindings to `dladdr`, for finding the image and the symbol that an
+//! address belongs to.
+//!
+//! Defined in `dlfcn.h`.
+use core::ffi::c_void;
+use core::ptr;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct Dl_info {
+    dli_fname: *const c_char,
+    dli_fbase: *mut c_void,
+    dli_sname: *const c_char,
+    dli_saddr: *mut c_void,
+}
+
+extern "C" {
+    fn dladdr(addr: *const c_void, info: *mut Dl_info) -> c_int;
+}
+
+/// Information about the image containing an address.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AddressInfo<'a> {
+    /// The path of the image.
+    pub(crate) image: Option<&'a CStr>,
+    /// The nearest symbol below the address, along with its address.
+    #[cfg_attr(not(feature = "exception"), allow(dead_code))]
+    pub(crate) symbol: Option<(&'a CStr, usize)>,
+}
+
+/// Look up the image and the nearest symbol of the given address, or
+/// [`None`] if it is not contained in a loaded image.
+///
+///
+/// # Safety
+///
+/// The returned strings are owned by the image, so it must not be unloaded
+/// for the lifetime `'a`.
+pub(crate) unsafe fn lookup<'a>(address: *const c_void) -> Option<AddressInfo<'a>> {
+    let mut info = Dl_info {
+        dli_fname: ptr::null(),
+        dli_fbase: ptr::null_mut(),
+        dli_sname: ptr::null(),
+        dli_saddr: ptr::null_mut(),
+    };
+    if unsafe { dladdr(address, &mut info) } == 0 {
+        return None;
+    }
+    // SAFETY: The strings are either NULL or valid C strings while the image
+    // is loaded, which the caller ensures.
+    let image = unsafe { info.dli_fname.as_ref() }.map(|s| unsafe { CStr::from_ptr(s) });
+    let symbol = unsafe { info.dli_sname.as_ref() }
+        .map(|s| (unsafe { CStr::from_ptr(s) }, info.dli_saddr as usize));
+    Some(AddressInfo { image, symbol })
+}
@@ -0,0 +1,88 @@
+//! Utility for parsing and verifying an Objective-C method type encoding.
+use core::fmt;
+use std::error::Error;
+
+use crate::encode::{Encoding, EncodingBox, MethodEncoding, ParseError};
+
+/// Parse a method type encoding, and verify that the implicit receiver and
+/// selector arguments have the expected types.
+pub(crate) fn parse_signature(types: &str) -> Result<MethodEncoding, EncodingParseError> {
+    // TODO: Verify stack layout
+    let signature: MethodEncoding = types.parse()?;
+    if !Encoding::Object.equivalent_to_box(&signature.receiver.encoding) {
+        return Err(EncodingParseError::InvalidReceiver(
+            signature.receiver.encoding,
+        ));
+    }
+    if !Encoding::Sel.equivalent_to_box(&signature.sel.encoding) {
+        return Err(EncodingParseError::InvalidSel(signature.sel.encoding));
+    }
+    Ok(signature)
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) enum EncodingParseError {
+    ParseError(ParseError),
+    InvalidReceiver(EncodingBox),
+    InvalidSel(EncodingBox),
+}
+
+impl From<ParseError> for EncodingParseError {
+    fn from(e: ParseError) -> Self {
+        Self::ParseError(e)
+    }
+}
+
+impl fmt::Display for EncodingParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !matches!(self, Self::ParseError(_)) {
+            write!(f, "failed parsing encoding: ")?
+        }
+
+        match self {
+            Self::ParseError(e) => write!(f, "{e}")?,
+            Self::InvalidReceiver(enc) => {
+                write!(f, "receiver encoding must be '@', but it was '{enc}'")?
+            }
+            Self::InvalidSel(enc) => {
+                write!(f, "selector encoding must be ':', but it was '{enc}'")?
+            }
+        }
+        write!(f, ". This is likely a bug, please report it!")
+    }
+}
+
+impl Error for EncodingParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_valid() {
+        let signature = parse_signature("v24@0:8i16").unwrap();
+        assert_eq!(signature.return_type.encoding, EncodingBox::Void);
+        assert_eq!(signature.arguments.len(), 1);
+        assert_eq!(signature.arguments[0].encoding, EncodingBox::Int);
+        assert_eq!(signature.arguments[0].stack_offset, Some(16));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(
+            parse_signature("v@@").unwrap_err(),
+            EncodingParseError::InvalidSel(EncodingBox::Object),
+        );
+        assert_eq!(
+            parse_signature("v::").unwrap_err(),
+            EncodingParseError::InvalidReceiver(EncodingBox::Sel),
+        );
+        let err = parse_signature("v@").unwrap_err();
+        assert!(matches!(err, EncodingParseError::ParseError(_)));
+        assert_eq!(
+            err.to_string(),
+            "failed parsing encoding: unexpected end at byte-index 2 in \"v@\". This is likely a bug, please report it!",
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! For more information on foreign functions, see Apple's documentation:
 //! <https://developer.apple.com/library/mac/documentation/Cocoa/Reference/ObjCRuntimeRef/index.html>
 
+#[cfg(not(feature = "unstable-objfw"))]
+use alloc::format;
 #[cfg(feature = "malloc")]
 use alloc::vec::Vec;
 #[cfg(doc)]
@@ -21,27 +23,43 @@ use std::os::raw::c_uint;
 
 #[doc(hidden)]
 pub mod __nsstring;
+mod association;
 mod bool;
-mod method_encoding_iter;
+#[cfg(all(unix, any(feature = "exception", feature = "gnustep-2-0")))]
+pub(crate) mod dladdr;
+mod method_encoding;
 mod nsobject;
+// ObjFW doesn't have an equivalent of `NSProxy`.
+#[cfg(not(feature = "unstable-objfw"))]
 mod nsproxy;
 mod nszone;
+mod property;
 mod protocol_object;
 
-pub(crate) use self::method_encoding_iter::{EncodingParseError, MethodEncodingIter};
+pub(crate) use self::method_encoding::{parse_signature, EncodingParseError};
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::declare::{ClassBuilder, MethodClosure, MethodImplementation};
 use crate::encode::__unstable::{EncodeArguments, EncodeConvertReturn, EncodeReturn};
-use crate::encode::{Encode, Encoding, OptionEncode, RefEncode};
+use crate::encode::{
+    Encode, Encoding, EncodingBox, MethodEncoding, OptionEncode, ParseError, RefEncode,
+};
 use crate::ffi;
 use crate::verify::{verify_method_signature, Inner};
+#[cfg(not(feature = "unstable-objfw"))]
+use crate::{msg_send, sel};
 
+pub use self::association::{AssociatedKey, AssociatedValue};
 pub use self::bool::Bool;
 pub use self::nsobject::{NSObject, NSObjectProtocol};
 // Note: While this is not public, it is still a breaking change to remove,
 // since `icrate` relies on it.
 #[doc(hidden)]
+#[cfg(not(feature = "unstable-objfw"))]
 pub use self::nsproxy::NSProxy as __NSProxy;
 pub use self::nszone::NSZone;
+pub use self::property::{Property, PropertyAttributes};
 pub use self::protocol_object::{ImplementedBy, ProtocolObject};
+pub use crate::message::dynamic::{DynMessageError, Value};
 pub use crate::verify::VerificationError;
 
 /// Use [`Bool`] or [`ffi::BOOL`] instead.
@@ -116,7 +134,7 @@ macro_rules! standard_pointer_impls {
 
 // Implement PartialEq, Eq and Hash using pointer semantics; there's not
 // really a better way to do it.
-standard_pointer_impls!(Ivar, Method, Class);
+standard_pointer_impls!(Ivar, Method, Class, Property);
 
 #[cfg(not(feature = "unstable-c-unwind"))]
 type InnerImp = unsafe extern "C" fn();
@@ -303,15 +321,18 @@ unsafe impl Send for Ivar {}
 impl UnwindSafe for Ivar {}
 impl RefUnwindSafe for Ivar {}
 
-#[cfg_attr(not(feature = "malloc"), allow(dead_code))]
-#[derive(Debug, PartialEq, Eq, Hash)]
-pub(crate) struct MethodDescription {
+/// A description of a method declared by a [`Protocol`].
+///
+/// See [`Protocol::method_description`] and
+/// [`Protocol::method_descriptions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MethodDescription {
     pub(crate) sel: Sel,
     pub(crate) types: &'static str,
 }
 
 impl MethodDescription {
-    #[cfg_attr(not(feature = "malloc"), allow(dead_code))]
+    #[cfg_attr(feature = "unstable-objfw", allow(dead_code))]
     pub(crate) unsafe fn from_raw(raw: ffi::objc_method_description) -> Option<Self> {
         // SAFETY: Sel::from_ptr checks for NULL, rest is checked by caller.
         let sel = unsafe { Sel::from_ptr(raw.name) }?;
@@ -323,6 +344,40 @@ impl MethodDescription {
         let types = unsafe { CStr::from_ptr(raw.types) }.to_str().unwrap();
         Some(Self { sel, types })
     }
+
+    /// The selector of the method.
+    pub fn sel(&self) -> Sel {
+        self.sel
+    }
+
+    /// The raw type encoding of the method, e.g. `v@:i` or `v24@0:8i16`.
+    pub fn types(&self) -> &'static str {
+        self.types
+    }
+
+    /// Parses the full type encoding of the method, including qualifiers
+    /// and stack offsets.
+    pub fn signature(&self) -> Result<MethodEncoding, ParseError> {
+        self.types.parse()
+    }
+
+    /// Parses the encoding of the return type of the method.
+    pub fn return_type(&self) -> Result<EncodingBox, VerificationError> {
+        Ok(parse_signature(self.types)?.return_type.encoding)
+    }
+
+    /// Parses the encodings of the arguments of the method.
+    ///
+    /// This does not include the implicit receiver and selector arguments.
+    #[cfg(feature = "malloc")]
+    pub fn argument_types(&self) -> Result<Vec<EncodingBox>, VerificationError> {
+        let signature = parse_signature(self.types)?;
+        Ok(signature
+            .arguments
+            .into_iter()
+            .map(|argument| argument.encoding)
+            .collect())
+    }
 }
 
 impl Method {
@@ -337,7 +392,7 @@ impl Method {
     }
 
     /// Returns the `Encoding` of self's return type.
-    #[cfg(feature = "malloc")]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
     pub fn return_type(&self) -> Malloc<str> {
         unsafe {
             let encoding = ffi::method_copyReturnType(self.as_ptr());
@@ -347,7 +402,7 @@ impl Method {
 
     /// Returns the `Encoding` of a single parameter type of self, or
     /// [`None`] if self has no parameter at the given index.
-    #[cfg(feature = "malloc")]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
     pub fn argument_type(&self, index: usize) -> Option<Malloc<str>> {
         unsafe {
             let encoding = ffi::method_copyArgumentType(self.as_ptr(), index as c_uint);
@@ -355,20 +410,9 @@ impl Method {
         }
     }
 
-    /// An iterator over the method's types.
-    ///
-    /// It is approximately equivalent to:
-    ///
-    /// ```ignore
-    /// let types = method.types();
-    /// assert_eq!(types.next()?, method.return_type());
-    /// for i in 0..method.arguments_count() {
-    ///    assert_eq!(types.next()?, method.argument_type(i)?);
-    /// }
-    /// assert!(types.next().is_none());
-    /// ```
+    /// The raw type encoding of the method.
     #[doc(alias = "method_getTypeEncoding")]
-    pub(crate) fn types(&self) -> MethodEncodingIter<'_> {
+    pub(crate) fn types(&self) -> &str {
         // SAFETY: The method pointer is valid and non-null
         let cstr = unsafe { ffi::method_getTypeEncoding(self.as_ptr()) };
         if cstr.is_null() {
@@ -377,22 +421,93 @@ impl Method {
         // SAFETY: `method_getTypeEncoding` returns a C-string, and we just
         // checked that it is non-null.
         let encoding = unsafe { CStr::from_ptr(cstr) };
-        let s = str::from_utf8(encoding.to_bytes()).expect("method type encoding to be UTF-8");
-        MethodEncodingIter::new(s)
+        str::from_utf8(encoding.to_bytes()).expect("method type encoding to be UTF-8")
+    }
+
+    /// Parses the full type encoding of the method, including the implicit
+    /// receiver and selector arguments, qualifiers and stack offsets.
+    ///
+    /// This is more detailed than `return_type` and `argument_type`, which
+    /// only give the encodings of the individual types.
+    #[doc(alias = "method_getTypeEncoding")]
+    pub fn signature(&self) -> Result<MethodEncoding, ParseError> {
+        self.types().parse()
     }
 
     /// Returns the number of arguments accepted by self.
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn arguments_count(&self) -> usize {
         unsafe { ffi::method_getNumberOfArguments(self.as_ptr()) as usize }
     }
 
     /// Returns the implementation of self.
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn implementation(&self) -> Imp {
         unsafe { ffi::method_getImplementation(self.as_ptr()).expect("Null IMP") }
     }
 
-    // unsafe fn set_implementation(&mut self, imp: Imp) -> Imp;
-    // unsafe fn exchange_implementation(&mut self, other: &mut Method);
+    /// Sets the implementation of self, and returns the previous
+    /// implementation.
+    ///
+    /// Prefer [`Class::replace_method`], which verifies the signature of the
+    /// new implementation.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The implementation must have the same signature as self, and must be
+    /// safe to call whenever the method is invoked (including from other
+    /// threads that may be using the method concurrently).
+    #[doc(alias = "method_setImplementation")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn set_implementation(&self, imp: Imp) -> Imp {
+        unsafe { ffi::method_setImplementation(self.as_ptr(), Some(imp)) }.expect("Null IMP")
+    }
+
+    /// Exchanges the implementations of self and the other method.
+    ///
+    /// This is the classic "method swizzling" operation, and is done
+    /// atomically by the runtime.
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type encodings of the two methods are not equivalent.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// Both implementations must be safe to call whenever the other method is
+    /// invoked. In particular, if the methods belong to different classes,
+    /// the implementations must be able to handle receivers of either class.
+    #[doc(alias = "method_exchangeImplementations")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn exchange(&self, other: &Method) {
+        // Compare the encodings, ignoring qualifiers and the stack layout.
+        let equivalent = match (self.signature(), other.signature()) {
+            (Ok(a), Ok(b)) => {
+                a.return_type.encoding == b.return_type.encoding
+                    && a.receiver.encoding == b.receiver.encoding
+                    && a.sel.encoding == b.sel.encoding
+                    && a.arguments.len() == b.arguments.len()
+                    && a.arguments
+                        .iter()
+                        .zip(&b.arguments)
+                        .all(|(a, b)| a.encoding == b.encoding)
+            }
+            _ => false,
+        };
+        if !equivalent {
+            panic!(
+                "failed exchanging implementations of {:?} and {:?}: differing type encodings",
+                self.name(),
+                other.name(),
+            );
+        }
+        unsafe {
+            ffi::method_exchangeImplementations(self.as_ptr() as *mut _, other.as_ptr() as *mut _)
+        }
+    }
 }
 
 // SAFETY: Method is immutable (and can be retrieved from Class anyhow).
@@ -433,6 +548,86 @@ impl Class {
         unsafe { ffi::objc_getClassList(ptr::null_mut(), 0) as usize }
     }
 
+    /// Returns the paths of all the loaded Objective-C frameworks and
+    /// dynamic libraries.
+    ///
+    /// The paths are not necessarily valid UTF-8, so they are returned as C
+    /// strings.
+    ///
+    /// See [`image_name`][Self::image_name] for the image a class was loaded
+    /// from, and for which runtimes this is available on.
+    #[doc(alias = "objc_copyImageNames")]
+    #[cfg(all(
+        feature = "malloc",
+        any(feature = "apple", all(feature = "gnustep-2-0", unix))
+    ))]
+    pub fn image_names() -> Vec<&'static CStr> {
+        #[cfg(feature = "apple")]
+        {
+            let mut count: c_uint = 0;
+            let names =
+                unsafe { Malloc::from_array(ffi::objc_copyImageNames(&mut count), count as usize) };
+            names
+                .iter()
+                // SAFETY: The image names are valid for the lifetime of the
+                // process.
+                .map(|&name| unsafe { CStr::from_ptr(name) })
+                .collect()
+        }
+        // GNUstep doesn't keep a list of images, so collect them from the
+        // loaded classes instead.
+        #[cfg(not(feature = "apple"))]
+        {
+            let mut names: Vec<_> = Self::classes()
+                .iter()
+                .copied()
+                .filter_map(|cls| cls.image_name())
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+        }
+    }
+
+    /// Returns the classes defined in the image (framework or dynamic
+    /// library) with the given path.
+    ///
+    /// This can be used together with [`image_names`][Self::image_names] to
+    /// group all the loaded classes by the image they come from.
+    #[doc(alias = "objc_copyClassNamesForImage")]
+    #[cfg(all(
+        feature = "malloc",
+        any(feature = "apple", all(feature = "gnustep-2-0", unix))
+    ))]
+    pub fn classes_in_image(image: &CStr) -> Vec<&'static Self> {
+        #[cfg(feature = "apple")]
+        {
+            let mut count: c_uint = 0;
+            let names = unsafe {
+                let names = ffi::objc_copyClassNamesForImage(image.as_ptr(), &mut count);
+                if names.is_null() {
+                    return Vec::new();
+                }
+                Malloc::from_array(names, count as usize)
+            };
+            names
+                .iter()
+                .filter_map(|&name| {
+                    let cls = unsafe { ffi::objc_getClass(name) };
+                    unsafe { cls.cast::<Self>().as_ref() }
+                })
+                .collect()
+        }
+        #[cfg(not(feature = "apple"))]
+        {
+            Self::classes()
+                .iter()
+                .copied()
+                .filter(|cls| cls.image_name() == Some(image))
+                .collect()
+        }
+    }
+
     /// # Safety
     ///
     /// 1. The class pointer must be valid.
@@ -475,6 +670,42 @@ impl Class {
         unsafe { Self::superclass_raw(self.as_ptr()) }
     }
 
+    /// Returns the classes whose superclass is self.
+    ///
+    /// This has to look at every registered class, so it is fairly
+    /// expensive.
+    #[cfg(feature = "malloc")]
+    pub fn subclasses(&self) -> Vec<&'static Self> {
+        Self::classes()
+            .iter()
+            .copied()
+            .filter(|cls| cls.superclass() == Some(self))
+            .collect()
+    }
+
+    /// Returns all the classes that inherit from self, both directly and
+    /// indirectly.
+    ///
+    /// This has to look at every registered class, so it is fairly
+    /// expensive.
+    #[cfg(feature = "malloc")]
+    pub fn all_subclasses(&self) -> Vec<&'static Self> {
+        Self::classes()
+            .iter()
+            .copied()
+            .filter(|cls| {
+                let mut superclass = cls.superclass();
+                while let Some(cls) = superclass {
+                    if cls == self {
+                        return true;
+                    }
+                    superclass = cls.superclass();
+                }
+                false
+            })
+            .collect()
+    }
+
     /// Returns the metaclass of self.
     pub fn metaclass(&self) -> &Self {
         let ptr: *const Self = unsafe { ffi::object_getClass(self.as_ptr().cast()) }.cast();
@@ -488,6 +719,42 @@ impl Class {
         unsafe { Bool::from_raw(ffi::class_isMetaClass(self.as_ptr())).as_bool() }
     }
 
+    /// Returns the path of the framework or dynamic library that self was
+    /// loaded from, or [`None`] if the class was created at runtime.
+    ///
+    /// The path is not necessarily valid UTF-8, so it is returned as a C
+    /// string.
+    ///
+    /// GNUstep doesn't keep track of which image a class comes from, so there
+    /// this is instead found by looking up the address of the class structure
+    /// with `dladdr`. This requires the structures emitted by the compiler to
+    /// be used directly, which is only the case since the 2.0 ABI, and is
+    /// therefore only available with the `gnustep-2-0` feature on Unix.
+    ///
+    /// This is not available on ObjFW.
+    #[doc(alias = "class_getImageName")]
+    #[cfg(any(feature = "apple", all(feature = "gnustep-2-0", unix)))]
+    pub fn image_name(&self) -> Option<&CStr> {
+        #[cfg(feature = "apple")]
+        {
+            let name = unsafe { ffi::class_getImageName(self.as_ptr()) };
+            if name.is_null() {
+                return None;
+            }
+            // SAFETY: The image name is valid for the lifetime of the process.
+            Some(unsafe { CStr::from_ptr(name) })
+        }
+        // Classes created at runtime are allocated on the heap, which is not
+        // part of any image.
+        #[cfg(not(feature = "apple"))]
+        {
+            // SAFETY: The class structure is part of the image, so the
+            // image stays loaded for as long as the class is borrowed.
+            let info = unsafe { self::dladdr::lookup(self.as_ptr().cast()) }?;
+            info.image
+        }
+    }
+
     /// Returns the size of instances of self.
     pub fn instance_size(&self) -> usize {
         unsafe { ffi::class_getInstanceSize(self.as_ptr()) }
@@ -509,6 +776,12 @@ impl Class {
     ///
     /// Same as `cls.metaclass().class_method()`.
     pub fn class_method(&self, sel: Sel) -> Option<&Method> {
+        // ObjFW doesn't have `class_getClassMethod`
+        #[cfg(feature = "unstable-objfw")]
+        {
+            self.metaclass().instance_method(sel)
+        }
+        #[cfg(not(feature = "unstable-objfw"))]
         unsafe {
             let method = ffi::class_getClassMethod(self.as_ptr(), sel.as_ptr());
             method.cast::<Method>().as_ref()
@@ -517,6 +790,7 @@ impl Class {
 
     /// Returns the ivar for a specified instance variable of self, or
     /// [`None`] if self has no ivar with the given name.
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn instance_variable(&self, name: &str) -> Option<&Ivar> {
         let name = CString::new(name).unwrap();
         unsafe {
@@ -525,6 +799,38 @@ impl Class {
         }
     }
 
+    /// Returns the ivar for a specified instance variable of self, or
+    /// [`None`] if self has no ivar with the given name.
+    #[cfg(feature = "unstable-objfw")]
+    pub fn instance_variable(&self, name: &str) -> Option<&Ivar> {
+        extern "C" {
+            fn free(ptr: *mut core::ffi::c_void);
+        }
+
+        // ObjFW doesn't have `class_getInstanceVariable`, so we search the
+        // ivar lists of the class and its superclasses instead.
+        let mut cls = Some(self);
+        while let Some(current) = cls {
+            let mut count: std::os::raw::c_uint = 0;
+            let ivars = unsafe { ffi::class_copyIvarList(current.as_ptr(), &mut count) };
+            if !ivars.is_null() {
+                // SAFETY: The runtime returns a valid array of `count` ivars.
+                let found = unsafe { core::slice::from_raw_parts(ivars, count as usize) }
+                    .iter()
+                    .map(|&ivar| unsafe { &*ivar.cast::<Ivar>() })
+                    .find(|ivar| ivar.name() == name);
+                // SAFETY: The list was allocated by the runtime with `malloc`.
+                unsafe { free(ivars.cast()) };
+                if found.is_some() {
+                    return found;
+                }
+            }
+            cls = current.superclass();
+        }
+        None
+    }
+
+    #[cfg(not(feature = "unstable-objfw"))]
     #[allow(unused)]
     fn instance_variable_layout(&self) -> Option<&[u8]> {
         let layout: *const c_char = unsafe { ffi::class_getIvarLayout(self.as_ptr()).cast() };
@@ -535,6 +841,7 @@ impl Class {
         }
     }
 
+    #[cfg(not(feature = "unstable-objfw"))]
     #[allow(unused)]
     fn class_variable(&self, name: &str) -> Option<&Ivar> {
         let name = CString::new(name).unwrap();
@@ -553,6 +860,15 @@ impl Class {
         }
     }
 
+    /// Describes the class methods implemented by self.
+    ///
+    /// This is the same as the instance methods of the
+    /// [metaclass][Self::metaclass].
+    #[cfg(feature = "malloc")]
+    pub fn class_methods(&self) -> Malloc<[&Method]> {
+        self.metaclass().instance_methods()
+    }
+
     /// Checks whether this class conforms to the specified protocol.
     pub fn conforms_to(&self, proto: &Protocol) -> bool {
         unsafe {
@@ -561,7 +877,7 @@ impl Class {
     }
 
     /// Get a list of the protocols to which this class conforms.
-    #[cfg(feature = "malloc")]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
     pub fn adopted_protocols(&self) -> Malloc<[&Protocol]> {
         unsafe {
             let mut count: c_uint = 0;
@@ -581,6 +897,32 @@ impl Class {
         }
     }
 
+    /// Returns the property with the given name declared by self or its
+    /// superclasses, or [`None`] if there is no such property.
+    #[doc(alias = "class_getProperty")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            let property = ffi::class_getProperty(self.as_ptr(), name.as_ptr());
+            property.cast::<Property>().as_ref()
+        }
+    }
+
+    /// Describes the properties declared by self.
+    ///
+    /// This does not include properties declared by superclasses.
+    #[doc(alias = "class_copyPropertyList")]
+    #[cfg(feature = "malloc")]
+    pub fn properties(&self) -> Malloc<[&Property]> {
+        unsafe {
+            let mut count: c_uint = 0;
+            let properties: *mut &Property =
+                ffi::class_copyPropertyList(self.as_ptr(), &mut count).cast();
+            Malloc::from_array(properties, count as usize)
+        }
+    }
+
     /// Check whether instances of this class respond to the given selector.
     ///
     /// This doesn't call `respondsToSelector:`, but works entirely within the
@@ -599,9 +941,121 @@ impl Class {
         Bool::from_raw(res).as_bool()
     }
 
-    // fn property(&self, name: &str) -> Option<&Property>;
-    // fn properties(&self) -> Malloc<[&Property]>;
-    // unsafe fn replace_method(&self, name: Sel, imp: Imp, types: &str) -> Imp;
+    /// Replaces the implementation of the instance method with the given
+    /// selector, and returns the previous implementation.
+    ///
+    /// The signature of `func` is verified against the existing method, and
+    /// a [`VerificationError`] is returned if it differs, or if the class
+    /// does not have such a method.
+    ///
+    /// If the method is inherited from a superclass, the new implementation
+    /// is added to self (leaving the superclass untouched), and the
+    /// superclass' implementation is returned. This makes it possible to
+    /// call the original implementation from the new one.
+    ///
+    /// To replace class methods, use this on the [metaclass][Self::metaclass].
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The new implementation must be safe to call whenever the method is
+    /// invoked, including from other threads that may be using the method
+    /// concurrently.
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use objc2::{class, msg_send, sel};
+    /// use objc2::runtime::{Object, Sel};
+    ///
+    /// extern "C" fn hash(_obj: &Object, _sel: Sel) -> usize {
+    ///     42
+    /// }
+    ///
+    /// let cls = class!(NSObject);
+    /// let f: extern "C" fn(_, _) -> _ = hash;
+    /// let original = unsafe { cls.replace_method(sel!(hash), f) }.unwrap();
+    ///
+    /// let obj: *mut Object = unsafe { msg_send![cls, new] };
+    /// let obj = unsafe { &*obj };
+    /// let hash: usize = unsafe { msg_send![obj, hash] };
+    /// assert_eq!(hash, 42);
+    /// // The original implementation can still be called directly
+    /// let _original_hash = original(obj, sel!(hash));
+    /// ```
+    #[doc(alias = "class_replaceMethod")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn replace_method<F>(&self, sel: Sel, func: F) -> Result<F, VerificationError>
+    where
+        F: MethodImplementation,
+    {
+        let method = self.instance_method(sel).ok_or(Inner::MethodNotFound)?;
+        verify_method_signature(method, F::Args::ENCODINGS, &F::Ret::ENCODING_RETURN)?;
+
+        // The previous implementation, in case the method is inherited.
+        let inherited = method.implementation();
+        // SAFETY: The encoding is verified to be equivalent to the existing
+        // method, so re-using that is fine.
+        let types = unsafe { ffi::method_getTypeEncoding(method.as_ptr()) };
+        let previous = unsafe {
+            ffi::class_replaceMethod(
+                self.as_ptr() as *mut _,
+                sel.as_ptr(),
+                Some(func.__imp()),
+                types,
+            )
+        };
+        // SAFETY: The previous implementation has the same signature as
+        // `func`, as verified above.
+        Ok(unsafe { F::__from_imp(previous.unwrap_or(inherited)) })
+    }
+
+    /// Adds an instance method with the given selector to self, implemented
+    /// by a closure.
+    ///
+    /// Unlike [`ClassBuilder::add_method_with_closure`], this can be used on
+    /// classes that have already been registered. The closure is kept alive
+    /// for as long as the class exists.
+    ///
+    /// To add class methods, use this on the [metaclass][Self::metaclass].
+    ///
+    /// [`ClassBuilder::add_method_with_closure`]: crate::declare::ClassBuilder::add_method_with_closure
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics if the method wasn't sucessfully added (e.g. a method with
+    /// that name already exists on self).
+    ///
+    /// If `debug_assertions` are enabled and the method is overriding a
+    /// method on a superclass, their encodings are verified to be equal.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the types match those that are expected
+    /// when the method is invoked from Objective-C, and that the closure is
+    /// safe to call from any thread that may invoke the method.
+    #[doc(alias = "imp_implementationWithBlock")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn add_method_with_closure<T, A, F>(&self, sel: Sel, closure: F)
+    where
+        T: ?Sized,
+        A: EncodeArguments,
+        F: MethodClosure<T, A>,
+    {
+        unsafe {
+            crate::declare::add_method_inner(
+                self.as_ptr() as *mut _,
+                sel,
+                A::ENCODINGS,
+                &F::Ret::ENCODING_RETURN,
+                || closure.__imp(),
+            )
+        }
+    }
+
     // unsafe fn replace_property(&self, name: &str, attributes: &[ffi::objc_property_attribute_t]);
     // unsafe fn set_ivar_layout(&mut self, layout: &[u8]);
     // fn method_imp(&self, name: Sel) -> Imp; // + _stret
@@ -666,6 +1120,10 @@ impl Protocol {
 
     /// Returns the protocol definition of a specified protocol, or [`None`]
     /// if the protocol is not registered with the Objective-C runtime.
+    ///
+    /// This is not available on ObjFW, since it doesn't keep a registry of
+    /// protocols.
+    #[cfg(not(feature = "unstable-objfw"))]
     pub fn get(name: &str) -> Option<&'static Protocol> {
         let name = CString::new(name).unwrap();
         unsafe {
@@ -675,7 +1133,7 @@ impl Protocol {
     }
 
     /// Obtains the list of registered protocol definitions.
-    #[cfg(feature = "malloc")]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
     pub fn protocols() -> Malloc<[&'static Protocol]> {
         unsafe {
             let mut count: c_uint = 0;
@@ -685,7 +1143,7 @@ impl Protocol {
     }
 
     /// Get a list of the protocols to which this protocol conforms.
-    #[cfg(feature = "malloc")]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
     pub fn adopted_protocols(&self) -> Malloc<[&Protocol]> {
         unsafe {
             let mut count: c_uint = 0;
@@ -712,7 +1170,46 @@ impl Protocol {
         str::from_utf8(name.to_bytes()).unwrap()
     }
 
-    #[cfg(feature = "malloc")]
+    /// Returns the property with the given name declared by self, or
+    /// [`None`] if there is no such property.
+    #[doc(alias = "protocol_getProperty")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub fn property(&self, name: &str, required: bool, instance: bool) -> Option<&Property> {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            let property = ffi::protocol_getProperty(
+                self.as_ptr(),
+                name.as_ptr(),
+                Bool::new(required).as_raw(),
+                Bool::new(instance).as_raw(),
+            );
+            property.cast::<Property>().as_ref()
+        }
+    }
+
+    /// Describes the required instance properties declared by self.
+    #[doc(alias = "protocol_copyPropertyList")]
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
+    pub fn properties(&self) -> Malloc<[&Property]> {
+        unsafe {
+            let mut count: c_uint = 0;
+            let properties: *mut &Property =
+                ffi::protocol_copyPropertyList(self.as_ptr(), &mut count).cast();
+            Malloc::from_array(properties, count as usize)
+        }
+    }
+
+    // ObjFW doesn't support introspecting protocols.
+    #[cfg(all(feature = "malloc", feature = "unstable-objfw"))]
+    fn method_descriptions_inner(
+        &self,
+        _required: bool,
+        _instance: bool,
+    ) -> Vec<MethodDescription> {
+        Vec::new()
+    }
+
+    #[cfg(all(feature = "malloc", not(feature = "unstable-objfw")))]
     fn method_descriptions_inner(&self, required: bool, instance: bool) -> Vec<MethodDescription> {
         let mut count: c_uint = 0;
         let descriptions = unsafe {
@@ -735,16 +1232,42 @@ impl Protocol {
             .collect()
     }
 
-    #[cfg(feature = "malloc")]
-    #[allow(dead_code)]
-    pub(crate) fn method_descriptions(&self, required: bool) -> Vec<MethodDescription> {
-        self.method_descriptions_inner(required, true)
+    /// Returns the description of the method with the given selector
+    /// declared by self, or [`None`] if there is no such method.
+    ///
+    /// `required` and `instance` specify whether to look for a required or
+    /// optional method, and an instance or class method, respectively.
+    #[doc(alias = "protocol_getMethodDescription")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub fn method_description(
+        &self,
+        sel: Sel,
+        required: bool,
+        instance: bool,
+    ) -> Option<MethodDescription> {
+        let desc = unsafe {
+            ffi::protocol_getMethodDescription(
+                self.as_ptr(),
+                sel.as_ptr(),
+                Bool::new(required).as_raw(),
+                Bool::new(instance).as_raw(),
+            )
+        };
+        // SAFETY: The method description is valid if non-NULL.
+        unsafe { MethodDescription::from_raw(desc) }
     }
 
+    /// Describes the methods declared by self.
+    ///
+    /// `required` and `instance` specify whether to list the required or
+    /// optional methods, and the instance or class methods, respectively.
+    ///
+    /// Note that this does not include methods declared by the protocols
+    /// that self adopts.
+    #[doc(alias = "protocol_copyMethodDescriptionList")]
     #[cfg(feature = "malloc")]
-    #[allow(dead_code)]
-    pub(crate) fn class_method_descriptions(&self, required: bool) -> Vec<MethodDescription> {
-        self.method_descriptions_inner(required, false)
+    pub fn method_descriptions(&self, required: bool, instance: bool) -> Vec<MethodDescription> {
+        self.method_descriptions_inner(required, instance)
     }
 }
 
@@ -830,6 +1353,191 @@ impl Object {
         unsafe { ptr.as_ref().unwrap_unchecked() }
     }
 
+    /// Changes the class of this object, and returns the previous class.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The new class must be compatible with the object's current class;
+    /// usually this means that it must be a subclass of it that doesn't add
+    /// any instance variables.
+    #[doc(alias = "object_setClass")]
+    pub unsafe fn set_class(&self, cls: &Class) -> &'static Class {
+        let ptr = self.as_ptr() as *mut ffi::objc_object;
+        let ptr: *const Class = unsafe { ffi::object_setClass(ptr, cls.as_ptr()) }.cast();
+        unsafe { ptr.as_ref().unwrap_unchecked() }
+    }
+
+    /// Overrides methods on this specific object, by changing its class to a
+    /// hidden subclass of its current class (also known as "isa-swizzling").
+    ///
+    /// The subclass is named `{prefix}_{name of current class}`, and is
+    /// created the first time it is needed, in which case `declare` is
+    /// called to add the overriding methods to it, along with the current
+    /// class (which the overriding methods can use to call the original
+    /// implementations with [`msg_send!(super(...), ...)`]). Later calls with
+    /// the same prefix reuse the subclass, and calling this on an object that
+    /// already has the subclass does nothing.
+    ///
+    /// The subclass overrides `-class` to return what the object reported
+    /// before, so that the change is hidden from other code, like
+    /// Key-Value Observing does.
+    ///
+    /// Returns the subclass.
+    ///
+    /// This is only available on Apple and GNUstep, since the `-class`
+    /// override is implemented with a closure.
+    ///
+    /// [`msg_send!(super(...), ...)`]: crate::msg_send
+    ///
+    ///
+    /// # Panics
+    ///
+    /// Panics if `declare` does, or if a class with the subclass' name
+    /// already exists, but was not created by this function.
+    ///
+    ///
+    /// # Safety
+    ///
+    /// The methods added in `declare` must not add instance variables, and
+    /// must have the correct signatures, see [`ClassBuilder::add_method`].
+    ///
+    /// The object's class must not be changed concurrently.
+    ///
+    /// [`ClassBuilder::add_method`]: crate::declare::ClassBuilder::add_method
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use objc2::runtime::{Class, NSObject, Object};
+    /// use objc2::{msg_send, sel, ClassType};
+    ///
+    /// let obj = NSObject::new();
+    /// let hash: usize = unsafe { msg_send![&obj, hash] };
+    ///
+    /// unsafe {
+    ///     obj.swizzle_class("Doubled", |builder, original| {
+    ///         builder.add_method_with_closure(sel!(hash), move |this: &Object| -> usize {
+    ///             let hash: usize = msg_send![super(this, original), hash];
+    ///             hash * 2
+    ///         });
+    ///     });
+    /// }
+    ///
+    /// let new_hash: usize = unsafe { msg_send![&obj, hash] };
+    /// assert_eq!(new_hash, hash * 2);
+    /// // The object still reports its original class
+    /// let cls: &Class = unsafe { msg_send![&obj, class] };
+    /// assert_eq!(cls, NSObject::class());
+    /// ```
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub unsafe fn swizzle_class<F>(&self, prefix: &str, declare: F) -> &'static Class
+    where
+        F: FnOnce(&mut ClassBuilder, &'static Class),
+    {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+        use core::sync::atomic::{AtomicPtr, Ordering};
+        use std::sync::{Condvar, Mutex, Once};
+
+        /// The class that a subclass created by `swizzle_class` was created
+        /// from, used to recognize such subclasses.
+        static SWIZZLED_FROM: AssociatedKey<&'static Class> = AssociatedKey::new();
+
+        fn as_object(cls: &Class) -> &Object {
+            // SAFETY: Classes are objects.
+            unsafe { &*(cls as *const Class).cast() }
+        }
+
+        /// The names of the subclasses that are currently being created, used
+        /// to make other threads wait until the subclass has been marked.
+        struct Creating {
+            names: Mutex<Vec<String>>,
+            done: Condvar,
+        }
+
+        fn creating() -> &'static Creating {
+            // TODO: Use `Mutex::new` in the static once our MSRV allows it
+            static CREATING: AtomicPtr<Creating> = AtomicPtr::new(ptr::null_mut());
+            static INIT: Once = Once::new();
+
+            INIT.call_once(|| {
+                let creating = Box::new(Creating {
+                    names: Mutex::new(Vec::new()),
+                    done: Condvar::new(),
+                });
+                CREATING.store(Box::into_raw(creating), Ordering::Release);
+            });
+
+            // SAFETY: Initialized above, and never deallocated.
+            unsafe { &*CREATING.load(Ordering::Acquire) }
+        }
+
+        /// Removes the name again once the subclass has been created (or
+        /// `declare` panicked), and wakes up the waiting threads.
+        struct CreatingGuard<'a>(&'a str);
+
+        impl Drop for CreatingGuard<'_> {
+            fn drop(&mut self) {
+                let creating = creating();
+                let mut names = creating.names.lock().unwrap_or_else(|err| err.into_inner());
+                names.retain(|name| name != self.0);
+                creating.done.notify_all();
+            }
+        }
+
+        // SAFETY: Classes live for the rest of the program, except for
+        // classes registered with `ClassBuilder::register_disposable`, whose
+        // caller must ensure that the class is not used (e.g. subclassed like
+        // here) after it has been disposed.
+        let current: &'static Class = unsafe { &*(self.class() as *const Class) };
+        if let Some(original) = as_object(current).associated(&SWIZZLED_FROM) {
+            if current.name() == format!("{prefix}_{}", original.name()) {
+                return current;
+            }
+        }
+        let name = format!("{prefix}_{}", current.name());
+
+        let creating = creating();
+        // The list is always in a consistent state, so poisoning is fine.
+        let mut names = creating.names.lock().unwrap_or_else(|err| err.into_inner());
+        // Wait for any other thread that is creating the subclass
+        while names.contains(&name) {
+            names = creating
+                .done
+                .wait(names)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+
+        let subclass = if let Some(subclass) = Class::get(&name) {
+            drop(names);
+            if as_object(subclass).associated(&SWIZZLED_FROM) != Some(current) {
+                panic!("class {name} already exists, but was not created from {current:?}");
+            }
+            subclass
+        } else {
+            names.push(name.clone());
+            drop(names);
+            let _guard = CreatingGuard(&name);
+
+            let mut builder = ClassBuilder::new(&name, current)
+                .unwrap_or_else(|| panic!("could not create class {name}"));
+            let reported: &'static Class = unsafe { msg_send![self, class] };
+            unsafe {
+                builder.add_method_with_closure(sel!(class), move |_this: &Self| reported);
+            }
+            declare(&mut builder, current);
+            let subclass = builder.register();
+            as_object(subclass).set_associated(&SWIZZLED_FROM, Some(current));
+            subclass
+        };
+
+        unsafe { self.set_class(subclass) };
+        subclass
+    }
+
     /// Offset an object pointer to get a pointer to an ivar.
     ///
     ///
@@ -981,10 +1689,6 @@ impl Object {
         // SAFETY: Invariants upheld by caller
         unsafe { *self.ivar_mut::<T>(name) = value };
     }
-
-    // objc_setAssociatedObject
-    // objc_getAssociatedObject
-    // objc_removeAssociatedObjects
 }
 
 impl fmt::Debug for Object {
@@ -995,13 +1699,19 @@ impl fmt::Debug for Object {
 
 #[cfg(test)]
 mod tests {
+    // Most tests use ivars or protocols, and are disabled on ObjFW
+    #![cfg_attr(feature = "unstable-objfw", allow(unused_imports))]
+
     use alloc::format;
     use alloc::string::ToString;
 
     use super::*;
+    use crate::declare::ClassBuilder;
+    use crate::rc::Id;
+    #[cfg(not(feature = "unstable-objfw"))]
     use crate::test_utils;
     use crate::MessageReceiver;
-    use crate::{msg_send, sel};
+    use crate::{class, msg_send, msg_send_id, sel};
 
     #[test]
     fn test_selector() {
@@ -1044,6 +1754,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_ivar() {
         let cls = test_utils::custom_class();
         let ivar = cls.instance_variable("_foo").unwrap();
@@ -1056,12 +1767,20 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_instance_method() {
         let cls = test_utils::custom_class();
         let sel = Sel::register("foo");
         let method = cls.instance_method(sel).unwrap();
         assert_eq!(method.name().name(), "foo");
         assert_eq!(method.arguments_count(), 2);
+
+        let signature = method.signature().unwrap();
+        assert!(<u32>::ENCODING.equivalent_to_box(&signature.return_type.encoding));
+        assert_eq!(signature.receiver.encoding, EncodingBox::Object);
+        assert_eq!(signature.sel.encoding, EncodingBox::Sel);
+        assert_eq!(signature.arguments, []);
+        assert_eq!(signature.to_string(), method.types());
         #[cfg(feature = "malloc")]
         {
             assert!(<u32>::ENCODING.equivalent_to_str(&method.return_type()));
@@ -1072,6 +1791,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_class_method() {
         let cls = test_utils::custom_class();
         let method = cls.class_method(sel!(classFoo)).unwrap();
@@ -1091,6 +1811,182 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_replace_method() {
+        extern "C" fn foo(_cls: &Class, _cmd: Sel) -> u32 {
+            1
+        }
+        extern "C" fn bar(_cls: &Class, _cmd: Sel) -> u32 {
+            2
+        }
+        extern "C" fn replaced(_cls: &Class, _cmd: Sel) -> u32 {
+            3
+        }
+        extern "C" fn invalid(_cls: &Class, _cmd: Sel) -> i8 {
+            0
+        }
+
+        let mut builder = ClassBuilder::new("TestReplaceMethod", class!(NSObject)).unwrap();
+        unsafe {
+            builder.add_class_method(sel!(foo), foo as extern "C" fn(_, _) -> _);
+            builder.add_class_method(sel!(bar), bar as extern "C" fn(_, _) -> _);
+        }
+        let cls = builder.register();
+        let metaclass = cls.metaclass();
+        let call_foo = || -> u32 { unsafe { msg_send![cls, foo] } };
+        let call_bar = || -> u32 { unsafe { msg_send![cls, bar] } };
+
+        let f: extern "C" fn(_, _) -> _ = replaced;
+        let original = unsafe { metaclass.replace_method(sel!(foo), f) }.unwrap();
+        assert_eq!(original(cls, sel!(foo)), 1);
+        assert_eq!(call_foo(), 3);
+
+        let f: extern "C" fn(_, _) -> _ = invalid;
+        assert!(unsafe { metaclass.replace_method(sel!(foo), f) }.is_err());
+        assert!(unsafe { metaclass.replace_method(sel!(unknown), f) }.is_err());
+
+        let foo_method = metaclass.instance_method(sel!(foo)).unwrap();
+        let bar_method = metaclass.instance_method(sel!(bar)).unwrap();
+        unsafe { foo_method.exchange(bar_method) };
+        assert_eq!(call_foo(), 2);
+        assert_eq!(call_bar(), 3);
+
+        let previous = unsafe { foo_method.set_implementation(original.__imp()) };
+        assert_eq!(call_foo(), 1);
+        let bar: extern "C" fn(_, _) -> _ = bar;
+        assert_eq!(previous as usize, bar as usize);
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_replace_inherited_method() {
+        extern "C" fn foo(_this: &Object, _cmd: Sel) -> u32 {
+            10
+        }
+
+        let cls = test_utils::custom_class();
+        let builder = ClassBuilder::new("TestReplaceInheritedMethod", cls).unwrap();
+        let subclass = builder.register();
+
+        let f: extern "C" fn(_, _) -> _ = foo;
+        let original = unsafe { subclass.replace_method(sel!(foo), f) }.unwrap();
+        assert_eq!(
+            original as usize,
+            cls.instance_method(sel!(foo)).unwrap().implementation() as usize
+        );
+        assert!(subclass.instance_method(sel!(foo)) != cls.instance_method(sel!(foo)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_add_method_with_closure() {
+        let builder = ClassBuilder::new("TestAddMethodWithClosure", class!(NSObject)).unwrap();
+        let cls = builder.register();
+
+        let offset = 3;
+        unsafe {
+            cls.add_method_with_closure(sel!(addOffset:), move |_this: &Object, a: u32| a + offset);
+            cls.metaclass()
+                .add_method_with_closure(sel!(double:), |_cls: &Class, a: i32| a * 2);
+        }
+
+        let obj: Id<Object> = unsafe { msg_send_id![cls, new] };
+        let res: u32 = unsafe { msg_send![&obj, addOffset: 4u32] };
+        assert_eq!(res, 7);
+
+        let res: i32 = unsafe { msg_send![cls, double: 21i32] };
+        assert_eq!(res, 42);
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_swizzle_class() {
+        let nsobject = class!(NSObject);
+        let obj1: Id<Object> = unsafe { msg_send_id![nsobject, new] };
+        let obj2: Id<Object> = unsafe { msg_send_id![nsobject, new] };
+
+        let declare = |builder: &mut ClassBuilder, original: &'static Class| {
+            assert_eq!(original, nsobject);
+            unsafe {
+                builder.add_method_with_closure(sel!(hash), move |this: &Object| -> usize {
+                    let hash: usize = msg_send![super(this, original), hash];
+                    hash.wrapping_add(1)
+                });
+            }
+        };
+
+        let hash: usize = unsafe { msg_send![&obj1, hash] };
+        let subclass = unsafe { obj1.swizzle_class("TestSwizzleClass", declare) };
+        assert_eq!(subclass.name(), "TestSwizzleClass_NSObject");
+        assert_eq!(subclass.superclass(), Some(nsobject));
+        assert_eq!(obj1.class(), subclass);
+        let new_hash: usize = unsafe { msg_send![&obj1, hash] };
+        assert_eq!(new_hash, hash.wrapping_add(1));
+        let cls: &Class = unsafe { msg_send![&obj1, class] };
+        assert_eq!(cls, nsobject);
+
+        // Swizzling again does nothing
+        let res = unsafe { obj1.swizzle_class("TestSwizzleClass", |_, _| unreachable!()) };
+        assert_eq!(res, subclass);
+
+        // The subclass is reused for other objects
+        let res = unsafe { obj2.swizzle_class("TestSwizzleClass", |_, _| unreachable!()) };
+        assert_eq!(res, subclass);
+        assert_eq!(obj2.class(), subclass);
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    fn test_swizzle_class_concurrently() {
+        use alloc::vec::Vec;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use std::thread;
+        use std::time::Duration;
+
+        static DECLARED: AtomicUsize = AtomicUsize::new(0);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    let obj: Id<Object> = unsafe { msg_send_id![class!(NSObject), new] };
+                    let subclass = unsafe {
+                        obj.swizzle_class("TestSwizzleClassConcurrently", |_, _| {
+                            // Give the other threads time to find the
+                            // subclass before it has been marked
+                            thread::sleep(Duration::from_millis(50));
+                            DECLARED.fetch_add(1, Ordering::Relaxed);
+                        })
+                    };
+                    assert_eq!(obj.class(), subclass);
+                    subclass.name().to_string()
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let name = handle.join().unwrap();
+            assert_eq!(name, "TestSwizzleClassConcurrently_NSObject");
+        }
+        assert_eq!(DECLARED.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[should_panic = "class TestSwizzleClassUnrelated_NSObject already exists, but was not created from NSObject"]
+    fn test_swizzle_class_unrelated() {
+        let builder = ClassBuilder::new(
+            "TestSwizzleClassUnrelated_NSObject",
+            test_utils::custom_class(),
+        )
+        .unwrap();
+        let _cls = builder.register();
+
+        let obj: Id<Object> = unsafe { msg_send_id![class!(NSObject), new] };
+        unsafe { obj.swizzle_class("TestSwizzleClassUnrelated", |_, _| unreachable!()) };
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_class() {
         let cls = test_utils::custom_class();
         assert_eq!(cls.name(), "CustomObject");
@@ -1117,6 +2013,51 @@ mod tests {
         assert_eq!(subclass.superclass().unwrap(), cls);
     }
 
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[cfg(feature = "malloc")]
+    fn test_class_methods() {
+        let cls = test_utils::custom_class();
+        let methods = cls.class_methods();
+        assert!(methods.iter().any(|m| m.name() == sel!(classFoo)));
+        assert!(!methods.iter().any(|m| m.name() == sel!(foo)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[cfg(feature = "malloc")]
+    fn test_subclasses() {
+        let cls = test_utils::custom_class();
+        let subclass = test_utils::custom_subclass();
+        assert!(cls.subclasses().contains(&subclass));
+        assert!(!subclass.subclasses().contains(&cls));
+
+        let root = class!(NSObject);
+        assert!(!root.subclasses().contains(&subclass));
+        let all_subclasses = root.all_subclasses();
+        assert!(all_subclasses.contains(&cls));
+        assert!(all_subclasses.contains(&subclass));
+        assert!(!all_subclasses.contains(&root));
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[cfg(any(feature = "apple", all(feature = "gnustep-2-0", unix)))]
+    fn test_image_name() {
+        let cls = class!(NSObject);
+        let image = cls.image_name().unwrap();
+        assert!(image.to_str().unwrap().contains("libobjc"));
+        assert_eq!(test_utils::custom_class().image_name(), None);
+
+        #[cfg(feature = "malloc")]
+        {
+            assert!(Class::image_names().contains(&image));
+            assert!(Class::classes_in_image(image).contains(&cls));
+            let unknown = CStr::from_bytes_with_nul(b"unknown image\0").unwrap();
+            assert!(Class::classes_in_image(unknown).is_empty());
+        }
+    }
+
     #[test]
     fn test_classes_count() {
         assert!(Class::classes_count() > 0);
@@ -1130,6 +2071,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_protocol() {
         let proto = test_utils::custom_protocol();
         assert_eq!(proto.name(), "CustomProtocol");
@@ -1144,25 +2086,41 @@ mod tests {
                     sel: sel!(setBar:),
                     types: "v@:i",
                 };
-                assert_eq!(&proto.method_descriptions(true), &[desc]);
+                assert_eq!(&proto.method_descriptions(true, true), &[desc]);
                 let desc = MethodDescription {
                     sel: sel!(getName),
                     types: "*@:",
                 };
-                assert_eq!(&proto.method_descriptions(false), &[desc]);
+                assert_eq!(&proto.method_descriptions(false, true), &[desc]);
                 let desc = MethodDescription {
                     sel: sel!(addNumber:toNumber:),
                     types: "i@:ii",
                 };
-                assert_eq!(&proto.class_method_descriptions(true), &[desc]);
+                assert_eq!(&proto.method_descriptions(true, false), &[desc]);
+
+                let desc = proto
+                    .method_description(sel!(addNumber:toNumber:), true, false)
+                    .unwrap();
+                assert_eq!(desc.sel(), sel!(addNumber:toNumber:));
+                assert_eq!(desc.return_type().unwrap(), EncodingBox::Int);
+                let signature = desc.signature().unwrap();
+                assert_eq!(signature.arguments.len(), 2);
+                assert_eq!(signature.to_string(), desc.types());
+                assert_eq!(
+                    desc.argument_types().unwrap(),
+                    [EncodingBox::Int, EncodingBox::Int]
+                );
+                assert_eq!(proto.method_description(sel!(setBar:), false, true), None);
+                assert_eq!(proto.method_description(sel!(unknown), true, true), None);
             }
-            assert_eq!(&proto.class_method_descriptions(false), &[]);
+            assert_eq!(&proto.method_descriptions(false, false), &[]);
 
             assert!(class.adopted_protocols().iter().any(|p| *p == proto));
         }
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_protocol_method() {
         let class = test_utils::custom_class();
         let result: i32 = unsafe { msg_send![class, addNumber: 1, toNumber: 2] };
@@ -1170,6 +2128,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_subprotocols() {
         let sub_proto = test_utils::custom_subprotocol();
         let super_proto = test_utils::custom_protocol();
@@ -1179,6 +2138,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_protocols() {
         // Ensure that a protocol has been registered on linux
         let _ = test_utils::custom_protocol();
@@ -1188,6 +2148,36 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
+    #[cfg_attr(
+        not(feature = "apple"),
+        ignore = "only Apple's NSObject protocol declares properties"
+    )]
+    fn test_property() {
+        let proto = Protocol::get("NSObject").unwrap();
+        let property = proto.property("hash", true, true).unwrap();
+        assert_eq!(property.name(), "hash");
+
+        let attrs = property.attributes().unwrap();
+        assert_eq!(attrs.encoding(), &<ffi::NSUInteger>::ENCODING);
+        assert!(attrs.is_readonly());
+        assert_eq!(attrs.getter(), sel!(hash));
+        assert_eq!(attrs.setter(), None);
+
+        let property = proto.property("description", true, true).unwrap();
+        let attrs = property.attributes().unwrap();
+        assert_eq!(attrs.class_name(), Some("NSString"));
+        assert!(attrs.is_copy());
+
+        assert!(proto.property("hash", false, true).is_none());
+        assert!(proto.property("doesNotExist", true, true).is_none());
+
+        #[cfg(feature = "malloc")]
+        assert!(proto.properties().iter().any(|p| p.name() == "hash"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_object() {
         let mut obj = test_utils::custom_object();
         assert_eq!(obj.class(), test_utils::custom_class());
@@ -1200,6 +2190,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[should_panic = "Ivar unknown not found on class CustomObject"]
     fn test_object_ivar_unknown() {
         let obj = test_utils::custom_object();
@@ -1207,6 +2198,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     #[should_panic = "wrong encoding. Tried to retrieve ivar with encoding I, but the encoding of the given type was C"]
     fn test_object_ivar_wrong_type() {
         let obj = test_utils::custom_object();
@@ -1240,6 +2232,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_debug() {
         assert_eq!(format!("{:?}", sel!(abc:)), "abc:");
         let cls = test_utils::custom_class();
@@ -1255,6 +2248,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "unstable-objfw"))]
     fn test_multiple_colon() {
         let class = test_utils::custom_class();
         let res: i32 = unsafe {
@@ -18,6 +18,8 @@ crate::__emit_struct! {
         /// This is exported under `icrate::Foundation::NSObject`, you probably
         /// want to use that path instead.
         ///
+        /// On ObjFW, this represents the `OFObject` root class instead.
+        ///
         /// [cls]: https://developer.apple.com/documentation/objectivec/nsobject?language=objc
     )
     (pub)
@@ -35,7 +37,12 @@ crate::__extern_class_impl_traits! {
 
 unsafe impl ClassType for NSObject {
     type Super = Object;
-    const NAME: &'static str = "NSObject";
+    // ObjFW doesn't have `NSObject`, its root class is `OFObject`.
+    const NAME: &'static str = if cfg!(feature = "unstable-objfw") {
+        "OFObject"
+    } else {
+        "NSObject"
+    };
 
     #[inline]
     fn class() -> &'static Class {
@@ -43,6 +50,10 @@ unsafe impl ClassType for NSObject {
         {
             crate::class!(NSObject)
         }
+        #[cfg(feature = "unstable-objfw")]
+        {
+            crate::class!(OFObject)
+        }
         #[cfg(feature = "gnustep-1-7")]
         {
             extern "C" {
@@ -157,7 +168,18 @@ unsafe impl ProtocolType for NSObject {
     const NAME: &'static str = "NSObject";
 
     fn protocol() -> Option<&'static Protocol> {
-        Some(Protocol::get(<Self as ProtocolType>::NAME).expect("could not find NSObject protocol"))
+        #[cfg(not(feature = "unstable-objfw"))]
+        {
+            Some(
+                Protocol::get(<Self as ProtocolType>::NAME)
+                    .expect("could not find NSObject protocol"),
+            )
+        }
+        // ObjFW doesn't have the `NSObject` protocol.
+        #[cfg(feature = "unstable-objfw")]
+        {
+            None
+        }
     }
 
     const __INNER: () = ();
@@ -0,0 +1,299 @@
+//! Declared properties, see [`Class::properties`] and
+//! [`Protocol::properties`].
+//!
+//! [`Class::properties`]: crate::runtime::Class::properties
+//! [`Protocol::properties`]: crate::runtime::Protocol::properties
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::str;
+use std::ffi::CStr;
+
+use crate::encode::{EncodingBox, ParseError};
+use crate::ffi;
+use crate::runtime::Sel;
+
+/// A type that represents a declared property.
+///
+/// See [Apple's documentation on declared properties][props] for details on
+/// the attribute string.
+///
+/// [props]: https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/ObjCRuntimeGuide/Articles/ocrtPropertyIntrospection.html
+#[repr(C)]
+pub struct Property(ffi::objc_property);
+
+impl Property {
+    pub(crate) fn as_ptr(&self) -> *const ffi::objc_property {
+        let ptr: *const Self = self;
+        ptr.cast()
+    }
+
+    /// Returns the name of self.
+    #[doc(alias = "property_getName")]
+    pub fn name(&self) -> &str {
+        let name = unsafe { CStr::from_ptr(ffi::property_getName(self.as_ptr())) };
+        str::from_utf8(name.to_bytes()).unwrap()
+    }
+
+    /// Returns the raw attribute string of self, e.g.
+    /// `T@"NSString",C,N,V_name`.
+    #[doc(alias = "property_getAttributes")]
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub fn attributes_str(&self) -> &str {
+        let attributes = unsafe { CStr::from_ptr(ffi::property_getAttributes(self.as_ptr())) };
+        str::from_utf8(attributes.to_bytes()).unwrap()
+    }
+
+    /// Parses the attributes of self.
+    ///
+    /// Returns an error if the type encoding of the property could not be
+    /// parsed.
+    #[cfg(not(feature = "unstable-objfw"))]
+    pub fn attributes(&self) -> Result<PropertyAttributes, ParseError> {
+        PropertyAttributes::parse(self.name(), self.attributes_str())
+    }
+}
+
+// SAFETY: Property is immutable (and can be retrieved from Class anyhow).
+unsafe impl Sync for Property {}
+unsafe impl Send for Property {}
+impl UnwindSafe for Property {}
+impl RefUnwindSafe for Property {}
+
+/// The parsed attributes of a [`Property`].
+///
+/// Attributes that are not recognized (such as the legacy `P` garbage
+/// collection attribute) are ignored.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PropertyAttributes {
+    encoding: EncodingBox,
+    class_name: Option<String>,
+    readonly: bool,
+    copy: bool,
+    retain: bool,
+    weak: bool,
+    nonatomic: bool,
+    dynamic: bool,
+    getter: Sel,
+    setter: Option<Sel>,
+    ivar: Option<String>,
+}
+
+impl PropertyAttributes {
+    /// Parse the attribute string of the property with the given name.
+    #[cfg_attr(feature = "unstable-objfw", allow(dead_code))]
+    pub(crate) fn parse(name: &str, mut s: &str) -> Result<Self, ParseError> {
+        let mut encoding = EncodingBox::Unknown;
+        let mut class_name = None;
+        let mut readonly = false;
+        let mut copy = false;
+        let mut retain = false;
+        let mut weak = false;
+        let mut nonatomic = false;
+        let mut dynamic = false;
+        let mut getter = None;
+        let mut setter = None;
+        let mut ivar = None;
+
+        while let Some(attribute) = s.chars().next() {
+            s = &s[attribute.len_utf8()..];
+
+            if attribute == 'T' {
+                // The type may contain commas (e.g. in struct names), so we
+                // parse it before splitting the rest of the attributes.
+                if let Some(rest) = s.strip_prefix("@\"") {
+                    // Objects may contain the class and protocol names,
+                    // which `EncodingBox` does not support.
+                    let end = rest.find('"').unwrap_or(rest.len());
+                    let name = rest[..end].split('<').next().unwrap_or("");
+                    if !name.is_empty() {
+                        class_name = Some(name.to_string());
+                    }
+                    encoding = EncodingBox::Object;
+                    s = rest.get(end + 1..).unwrap_or("");
+                } else {
+                    encoding = EncodingBox::from_start_of_str(&mut s)?;
+                }
+            }
+
+            let (value, rest) = s.split_once(',').unwrap_or((s, ""));
+            s = rest;
+
+            match attribute {
+                'R' => readonly = true,
+                'C' => copy = true,
+                '&' => retain = true,
+                'W' => weak = true,
+                'N' => nonatomic = true,
+                'D' => dynamic = true,
+                'G' => getter = Some(Sel::register(value)),
+                'S' => setter = Some(Sel::register(value)),
+                'V' => ivar = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        let getter = getter.unwrap_or_else(|| Sel::register(name));
+        let setter = if readonly {
+            None
+        } else {
+            Some(setter.unwrap_or_else(|| {
+                let mut chars = name.chars();
+                let first = chars.next().map(|c| c.to_ascii_uppercase());
+                Sel::register(&format!(
+                    "set{}{}:",
+                    first.map(String::from).unwrap_or_default(),
+                    chars.as_str()
+                ))
+            }))
+        };
+
+        Ok(Self {
+            encoding,
+            class_name,
+            readonly,
+            copy,
+            retain,
+            weak,
+            nonatomic,
+            dynamic,
+            getter,
+            setter,
+            ivar,
+        })
+    }
+
+    /// The type of the property (`T`).
+    ///
+    /// Objects are always [`EncodingBox::Object`], see
+    /// [`class_name`][Self::class_name] for the class of the object.
+    pub fn encoding(&self) -> &EncodingBox {
+        &self.encoding
+    }
+
+    /// The name of the class of the property, if the property is an object
+    /// with a known class, e.g. `NSString` for `@property NSString *name`.
+    pub fn class_name(&self) -> Option<&str> {
+        self.class_name.as_deref()
+    }
+
+    /// Whether the property is read-only (`R`).
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// Whether the property is a copy of the value assigned (`C`).
+    pub fn is_copy(&self) -> bool {
+        self.copy
+    }
+
+    /// Whether the property is a reference to the value assigned (`&`).
+    pub fn is_retain(&self) -> bool {
+        self.retain
+    }
+
+    /// Whether the property is a weak reference (`W`).
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Whether the property is non-atomic (`N`).
+    pub fn is_nonatomic(&self) -> bool {
+        self.nonatomic
+    }
+
+    /// Whether the property is dynamic, i.e. `@dynamic` (`D`).
+    pub fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+
+    /// The selector of the getter method.
+    ///
+    /// This is the custom getter (`G`) if specified, and otherwise the name
+    /// of the property.
+    pub fn getter(&self) -> Sel {
+        self.getter
+    }
+
+    /// The selector of the setter method, or [`None`] if the property is
+    /// read-only.
+    ///
+    /// This is the custom setter (`S`) if specified, and otherwise
+    /// `setName:` for a property named `name`.
+    pub fn setter(&self) -> Option<Sel> {
+        self.setter
+    }
+
+    /// The name of the instance variable backing the property (`V`), if
+    /// any.
+    pub fn ivar_name(&self) -> Option<&str> {
+        self.ivar.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sel;
+
+    #[test]
+    fn test_parse_object() {
+        let attrs = PropertyAttributes::parse("name", r#"T@"NSString",C,N,V_name"#).unwrap();
+        assert_eq!(attrs.encoding(), &EncodingBox::Object);
+        assert_eq!(attrs.class_name(), Some("NSString"));
+        assert!(attrs.is_copy());
+        assert!(attrs.is_nonatomic());
+        assert!(!attrs.is_readonly());
+        assert!(!attrs.is_retain());
+        assert!(!attrs.is_weak());
+        assert!(!attrs.is_dynamic());
+        assert_eq!(attrs.getter(), sel!(name));
+        assert_eq!(attrs.setter(), Some(sel!(setName:)));
+        assert_eq!(attrs.ivar_name(), Some("_name"));
+    }
+
+    #[test]
+    fn test_parse_protocol_object() {
+        let attrs = PropertyAttributes::parse("delegate", r#"T@"<Foo><Bar>",W,N"#).unwrap();
+        assert_eq!(attrs.encoding(), &EncodingBox::Object);
+        assert_eq!(attrs.class_name(), None);
+        assert!(attrs.is_weak());
+
+        let attrs = PropertyAttributes::parse("items", r#"T@"NSArray<Foo>",&,D"#).unwrap();
+        assert_eq!(attrs.class_name(), Some("NSArray"));
+        assert!(attrs.is_retain());
+        assert!(attrs.is_dynamic());
+        assert_eq!(attrs.ivar_name(), None);
+    }
+
+    #[test]
+    fn test_parse_custom_accessors() {
+        let attrs =
+            PropertyAttributes::parse("enabled", "Tc,GisEnabled,SsetIsEnabled:,V_enabled").unwrap();
+        assert_eq!(attrs.encoding(), &EncodingBox::Char);
+        assert_eq!(attrs.getter(), sel!(isEnabled));
+        assert_eq!(attrs.setter(), Some(sel!(setIsEnabled:)));
+    }
+
+    #[test]
+    fn test_parse_readonly_struct() {
+        let attrs =
+            PropertyAttributes::parse("frame", "T{CGRect={CGPoint=dd}{CGSize=dd}},R,N").unwrap();
+        assert!(matches!(attrs.encoding(), EncodingBox::Struct(name, _) if name == "CGRect"));
+        assert!(attrs.is_readonly());
+        assert_eq!(attrs.getter(), sel!(frame));
+        assert_eq!(attrs.setter(), None);
+    }
+
+    #[test]
+    fn test_parse_unknown_attributes() {
+        let attrs = PropertyAttributes::parse("x", "Tq,P,?,N").unwrap();
+        assert_eq!(attrs.encoding(), &EncodingBox::LongLong);
+        assert!(attrs.is_nonatomic());
+    }
+
+    #[test]
+    fn test_parse_invalid_type() {
+        assert!(PropertyAttributes::parse("x", "T{abc,N").is_err());
+    }
+}
@@ -1,29 +1,51 @@
+//! Verifying method declarations against the Objective-C runtime.
+//!
+//! When the `"verify"` feature is enabled and debug assertions are on,
+//! [`msg_send!`] verifies the types of each message the first time it is
+//! sent. [`verify_all`] can instead be used to verify every method declared
+//! with [`extern_methods!`] at once, which is useful for catching mistakes
+//! in bindings without having to call every method.
+//!
+//! [`msg_send!`]: crate::msg_send
+//! [`extern_methods!`]: crate::extern_methods
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::fmt;
 use core::hash::Hash;
+#[cfg(all(feature = "verify", unix))]
+use core::{mem, ptr, slice};
 use std::error::Error;
 
 use crate::encode::{Encoding, EncodingBox};
-use crate::runtime::{EncodingParseError, Method};
+#[cfg(feature = "verify")]
+use crate::runtime::Class;
+#[cfg(all(debug_assertions, feature = "verify"))]
+use crate::runtime::MethodDescription;
+use crate::runtime::{parse_signature, EncodingParseError, Method, Sel};
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub(crate) enum Inner {
+    #[cfg_attr(not(feature = "verify"), allow(dead_code))]
+    ClassNotFound,
     MethodNotFound,
     EncodingParseError(EncodingParseError),
     MismatchedReturn(EncodingBox, Encoding),
     MismatchedArgumentsCount(usize, usize),
     MismatchedArgument(usize, EncodingBox, Encoding),
+    /// The name of the protocol, and the selectors of the missing methods
+    /// along with whether they're instance methods.
+    #[cfg_attr(not(all(debug_assertions, feature = "verify")), allow(dead_code))]
+    MissingProtocolMethods(String, Vec<(Sel, bool)>),
 }
 
 impl fmt::Display for Inner {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::ClassNotFound => write!(f, "class not found"),
             Self::MethodNotFound => write!(f, "method not found"),
             Self::EncodingParseError(e) => write!(f, "{e}"),
             Self::MismatchedReturn(expected, actual) => {
-                write!(
-                    f,
-                    "expected return to have type code '{expected}', but found '{actual}'",
-                )
+                write!(f, "expected return to have {}", Mismatch(expected, actual))
             }
             Self::MismatchedArgumentsCount(expected, actual) => {
                 write!(f, "expected {expected} arguments, but {actual} were given",)
@@ -31,9 +53,46 @@ impl fmt::Display for Inner {
             Self::MismatchedArgument(i, expected, actual) => {
                 write!(
                     f,
-                    "expected argument at index {i} to have type code '{expected}', but found '{actual}'",
+                    "expected argument at index {i} to have {}",
+                    Mismatch(expected, actual)
                 )
             }
+            Self::MissingProtocolMethods(protocol, methods) => {
+                if methods.len() == 1 {
+                    write!(f, "missing required protocol method")?;
+                } else {
+                    write!(f, "missing required protocol methods")?;
+                }
+                for (i, (sel, is_instance)) in methods.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    let kind = if *is_instance { "-" } else { "+" };
+                    write!(f, "{sep}{kind}[{protocol} {sel:?}]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Displays a mismatch between two types as Rust types, e.g.
+/// "type `CGRect`, but found `f64`".
+///
+/// Falls back to also showing the type codes if the Rust types look the
+/// same, e.g. if two structs have the same name but different fields.
+struct Mismatch<'a>(&'a EncodingBox, &'a Encoding);
+
+impl fmt::Display for Mismatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = self.0.rust_type().to_string();
+        let actual = self.1.rust_type().to_string();
+        if expected == actual {
+            write!(
+                f,
+                "type `{expected}` ('{}'), but found `{actual}` ('{}')",
+                self.0, self.1,
+            )
+        } else {
+            write!(f, "type `{expected}`, but found `{actual}`")
         }
     }
 }
@@ -71,28 +130,256 @@ impl fmt::Display for VerificationError {
 
 impl Error for VerificationError {}
 
+/// A method declared with [`extern_methods!`], as registered for
+/// [`verify_all`].
+///
+/// [`extern_methods!`]: crate::extern_methods
+#[doc(hidden)]
+#[cfg(feature = "verify")]
+#[repr(C)]
+pub struct __MethodDeclaration {
+    /// The name of the class, or `None` if the type is not a `ClassType`,
+    /// in which case the method is not verified.
+    pub class_name: fn() -> Option<&'static str>,
+    pub sel: fn() -> Sel,
+    pub is_instance_method: bool,
+    pub args: &'static [Encoding],
+    pub ret: &'static Encoding,
+    pub file: &'static str,
+    pub line: u32,
+}
+
+// Ensure that the section exists, even if no methods are declared.
+#[cfg(all(feature = "verify", unix))]
+#[used]
+#[cfg_attr(
+    target_vendor = "apple",
+    link_section = "__DATA,__objc2_methods,regular,no_dead_strip"
+)]
+#[cfg_attr(not(target_vendor = "apple"), link_section = "objc2_methods")]
+static __EMPTY_METHOD_DECLARATIONS: [__MethodDeclaration; 0] = [];
+
+/// All the method declarations in the binary, which the linker has
+/// collected into a single section.
+#[cfg(all(feature = "verify", unix))]
+fn method_declarations() -> &'static [__MethodDeclaration] {
+    extern "C" {
+        #[cfg_attr(
+            target_vendor = "apple",
+            link_name = "\x01section$start$__DATA$__objc2_methods"
+        )]
+        #[cfg_attr(not(target_vendor = "apple"), link_name = "__start_objc2_methods")]
+        static START: u8;
+        #[cfg_attr(
+            target_vendor = "apple",
+            link_name = "\x01section$end$__DATA$__objc2_methods"
+        )]
+        #[cfg_attr(not(target_vendor = "apple"), link_name = "__stop_objc2_methods")]
+        static STOP: u8;
+    }
+
+    // Older compilers require `unsafe` for this
+    #[allow(unused_unsafe)]
+    let start = unsafe { ptr::addr_of!(START) };
+    #[allow(unused_unsafe)]
+    let stop = unsafe { ptr::addr_of!(STOP) };
+    let len = (stop as usize - start as usize) / mem::size_of::<__MethodDeclaration>();
+    // SAFETY: The section only contains `__MethodDeclaration`s, which are
+    // laid out contiguously since they all have the same size and alignment.
+    unsafe { slice::from_raw_parts(start.cast(), len) }
+}
+
+#[cfg(all(feature = "verify", not(unix)))]
+fn method_declarations() -> &'static [__MethodDeclaration] {
+    &[]
+}
+
+/// A method declared with [`extern_methods!`] whose declaration did not
+/// match the runtime.
+///
+/// This is returned by [`verify_all`].
+///
+/// [`extern_methods!`]: crate::extern_methods
+#[cfg(feature = "verify")]
+#[derive(Debug)]
+pub struct DeclarationError {
+    class_name: &'static str,
+    sel: Sel,
+    is_instance_method: bool,
+    file: &'static str,
+    line: u32,
+    error: VerificationError,
+}
+
+#[cfg(feature = "verify")]
+impl DeclarationError {
+    /// The name of the class that the method was declared on.
+    pub fn class_name(&self) -> &'static str {
+        self.class_name
+    }
+
+    /// The class that the method was declared on, or [`None`] if it could
+    /// not be found.
+    pub fn class(&self) -> Option<&'static Class> {
+        Class::get(self.class_name)
+    }
+
+    /// The selector of the method.
+    pub fn sel(&self) -> Sel {
+        self.sel
+    }
+
+    /// Whether the method was declared as an instance method (as opposed to
+    /// a class method).
+    pub fn is_instance_method(&self) -> bool {
+        self.is_instance_method
+    }
+
+    /// The file that the method was declared in.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// The line that the method was declared on.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The reason that verification failed.
+    pub fn error(&self) -> &VerificationError {
+        &self.error
+    }
+}
+
+#[cfg(feature = "verify")]
+impl fmt::Display for DeclarationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}[{} {:?}]: {}",
+            self.file,
+            self.line,
+            if self.is_instance_method { "-" } else { "+" },
+            self.class_name,
+            self.sel,
+            self.error,
+        )
+    }
+}
+
+#[cfg(feature = "verify")]
+impl Error for DeclarationError {}
+
+/// Verify every method declared with [`extern_methods!`] in the binary
+/// against the runtime, and return the declarations that didn't match.
+///
+/// This checks that each method exists on the class, and that the argument
+/// and return types match the type encoding of the method, in the same way
+/// that [`msg_send!`] does with debug assertions enabled.
+///
+/// Methods declared in generic `impl`s or on types that don't implement
+/// [`ClassType`] are not registered, and neither are methods on platforms
+/// whose linker is not supported (currently only Apple platforms and
+/// ELF-based Unix platforms are supported).
+///
+/// [`extern_methods!`]: crate::extern_methods
+/// [`msg_send!`]: crate::msg_send
+/// [`ClassType`]: crate::ClassType
+///
+///
+/// # Example
+///
+/// Verify all bindings in a test.
+///
+/// ```no_run
+/// #[test]
+/// fn verify_bindings() {
+///     if let Err(errors) = objc2::verify::verify_all() {
+///         for error in &errors {
+///             eprintln!("{error}");
+///         }
+///         panic!("{} method declarations were invalid", errors.len());
+///     }
+/// }
+/// ```
+#[cfg(feature = "verify")]
+pub fn verify_all() -> Result<(), Vec<DeclarationError>> {
+    let errors: Vec<_> = method_declarations()
+        .iter()
+        .filter_map(|decl| {
+            let class_name = (decl.class_name)()?;
+            let sel = (decl.sel)();
+            let res = match Class::get(class_name) {
+                Some(class) => {
+                    let method = if decl.is_instance_method {
+                        class.instance_method(sel)
+                    } else {
+                        class.class_method(sel)
+                    };
+                    match method {
+                        Some(method) => verify_method_signature(method, decl.args, decl.ret),
+                        None => Err(Inner::MethodNotFound.into()),
+                    }
+                }
+                None => Err(Inner::ClassNotFound.into()),
+            };
+            res.err().map(|error| DeclarationError {
+                class_name,
+                sel,
+                is_instance_method: decl.is_instance_method,
+                file: decl.file,
+                line: decl.line,
+                error,
+            })
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 pub(crate) fn verify_method_signature(
     method: &Method,
     args: &[Encoding],
     ret: &Encoding,
 ) -> Result<(), VerificationError> {
-    let mut iter = method.types();
+    verify_signature(method.types(), method.name(), args, ret)
+}
+
+/// Verify a method implementation against the method description in a
+/// protocol.
+#[cfg(all(debug_assertions, feature = "verify"))]
+pub(crate) fn verify_method_description(
+    desc: &MethodDescription,
+    args: &[Encoding],
+    ret: &Encoding,
+) -> Result<(), VerificationError> {
+    verify_signature(desc.types(), desc.sel(), args, ret)
+}
 
+fn verify_signature(
+    types: &str,
+    sel: Sel,
+    args: &[Encoding],
+    ret: &Encoding,
+) -> Result<(), VerificationError> {
     // TODO: Verify stack layout
-    let (expected, _stack_layout) = iter.extract_return()?;
+    let signature = parse_signature(types)?;
+
+    let expected = signature.return_type.encoding;
     if !ret.equivalent_to_box(&expected) {
         return Err(Inner::MismatchedReturn(expected, ret.clone()).into());
     }
 
-    iter.verify_receiver()?;
-    iter.verify_sel()?;
-
     let actual_count = args.len();
+    let mut iter = signature.arguments.into_iter();
 
     for (i, actual) in args.iter().enumerate() {
-        if let Some(res) = iter.next() {
-            // TODO: Verify stack layout
-            let (expected, _stack_layout) = res?;
+        if let Some(expected) = iter.next() {
+            let expected = expected.encoding;
             if !actual.equivalent_to_box(&expected) {
                 return Err(Inner::MismatchedArgument(i, expected, actual.clone()).into());
             }
@@ -106,7 +393,7 @@ pub(crate) fn verify_method_signature(
         return Err(Inner::MismatchedArgumentsCount(actual_count + remaining, actual_count).into());
     }
 
-    let expected_count = method.name().number_of_arguments();
+    let expected_count = sel.number_of_arguments();
     if expected_count != actual_count {
         return Err(Inner::MismatchedArgumentsCount(expected_count, actual_count).into());
     }
@@ -115,12 +402,16 @@ pub(crate) fn verify_method_signature(
 }
 
 #[cfg(test)]
+// The tests rely on `test_utils`, which uses ivars and protocols
+#[cfg(not(feature = "unstable-objfw"))]
 mod tests {
     use super::*;
     use crate::runtime::Sel;
     use crate::sel;
     use crate::test_utils;
+    use alloc::boxed::Box;
     use alloc::string::ToString;
+    use alloc::vec;
     use core::panic::{RefUnwindSafe, UnwindSafe};
 
     #[test]
@@ -148,7 +439,7 @@ mod tests {
         let err = cls.verify_sel::<(u32,), u64>(sel!(setFoo:)).unwrap_err();
         assert_eq!(
             err.to_string(),
-            "expected return to have type code 'v', but found 'Q'"
+            "expected return to have type `()`, but found `u64`"
         );
 
         // Too many arguments
@@ -163,7 +454,7 @@ mod tests {
         let err = cls.verify_sel::<(Sel,), ()>(sel!(setFoo:)).unwrap_err();
         assert_eq!(
             err.to_string(),
-            "expected argument at index 0 to have type code 'I', but found ':'"
+            "expected argument at index 0 to have type `u32`, but found `Sel`"
         );
 
         // Metaclass
@@ -174,9 +465,32 @@ mod tests {
         assert_eq!(err.to_string(), "expected 2 arguments, but 3 were given");
     }
 
+    #[test]
+    fn test_mismatch_message() {
+        let err = VerificationError::from(Inner::MismatchedReturn(
+            EncodingBox::Pointer(Box::new(EncodingBox::Void)),
+            Encoding::Double,
+        ));
+        assert_eq!(
+            err.to_string(),
+            "expected return to have type `*mut c_void`, but found `f64`"
+        );
+
+        // Falls back to type codes when the Rust types look the same
+        let err = VerificationError::from(Inner::MismatchedArgument(
+            1,
+            EncodingBox::Struct("Foo".into(), Some(vec![EncodingBox::Int])),
+            Encoding::Struct("Foo", &[Encoding::Char]),
+        ));
+        assert_eq!(
+            err.to_string(),
+            "expected argument at index 1 to have type `Foo` ('{Foo=i}'), but found `Foo` ('{Foo=c}')"
+        );
+    }
+
     #[test]
     #[cfg(debug_assertions)]
-    #[should_panic = "invalid message send to -[CustomObject foo]: expected return to have type code 'I', but found 'i'"]
+    #[should_panic = "invalid message send to -[CustomObject foo]: expected return to have type `u32`, but found `i32`"]
     fn test_send_message_verified() {
         let obj = test_utils::custom_object();
         let _: i32 = unsafe { crate::msg_send![&obj, foo] };
@@ -190,6 +504,72 @@ mod tests {
         let _: i32 = unsafe { crate::msg_send![cls, abcDef] };
     }
 
+    #[test]
+    #[cfg(all(feature = "verify", unix))]
+    fn test_verify_all() {
+        use crate::runtime::NSObject;
+        use crate::{declare_class, extern_class, extern_methods, ClassType};
+
+        declare_class!(
+            struct VerifyAllObject;
+
+            unsafe impl ClassType for VerifyAllObject {
+                type Super = NSObject;
+                const NAME: &'static str = "VerifyAllObject";
+            }
+
+            unsafe impl VerifyAllObject {
+                #[method(verifyAllNumber)]
+                fn _number(&self) -> u32 {
+                    42
+                }
+            }
+        );
+
+        extern_methods!(
+            #[allow(dead_code)]
+            unsafe impl VerifyAllObject {
+                #[method(verifyAllNumber)]
+                fn number(&self) -> u32;
+
+                #[method(verifyAllNumber)]
+                fn wrong_return(&self) -> i8;
+
+                #[method(verifyAllMissing)]
+                fn missing();
+            }
+        );
+
+        extern_class!(
+            struct VerifyAllMissingClass;
+
+            unsafe impl ClassType for VerifyAllMissingClass {
+                type Super = NSObject;
+            }
+        );
+
+        extern_methods!(
+            #[allow(dead_code)]
+            unsafe impl VerifyAllMissingClass {
+                #[method(foo)]
+                fn foo(&self);
+            }
+        );
+
+        let mut errors = verify_all().unwrap_err();
+        errors.retain(|error| error.class_name().starts_with("VerifyAll"));
+        errors.sort_by_key(|error| error.line());
+        assert_eq!(errors[0].class(), Some(VerifyAllObject::class()));
+        assert_eq!(errors[2].class(), None);
+        let errors: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors[0].ends_with(
+            ": -[VerifyAllObject verifyAllNumber]: expected return to have type `u32`, but found `i8`"
+        ));
+        assert!(errors[1].ends_with(": +[VerifyAllObject verifyAllMissing]: method not found"));
+        assert!(errors[2].ends_with(": -[VerifyAllMissingClass foo]: class not found"));
+    }
+
     #[test]
     fn test_marker_traits() {
         fn assert_marker_traits<T: Send + Sync + UnwindSafe + RefUnwindSafe + Unpin>() {}
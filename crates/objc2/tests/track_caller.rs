@@ -105,12 +105,12 @@ pub fn test_nil(checker: &PanicChecker) {
 pub fn test_verify(checker: &PanicChecker) {
     let obj = NSObject::new();
 
-    let msg = "invalid message send to -[NSObject description]: expected return to have type code '@', but found 'v'";
+    let msg = "invalid message send to -[NSObject description]: expected return to have type `*mut Object`, but found `()`";
     checker.assert_panics(msg, line!() + 1, || {
         let _: () = unsafe { msg_send![&obj, description] };
     });
 
-    let msg = format!("invalid message send to -[NSObject hash]: expected return to have type code '{}', but found '@'", usize::ENCODING);
+    let msg = format!("invalid message send to -[NSObject hash]: expected return to have type `{}`, but found `*mut Object`", usize::ENCODING.rust_type());
     checker.assert_panics(&msg, line!() + 1, || {
         let _: Option<Id<Object>> = unsafe { msg_send_id![&obj, hash] };
     });
@@ -0,0 +1,70 @@
+//! Test invalid uses of `#[derive(Encode)]`
+use core::marker::PhantomData;
+
+use objc2::encode::Encode;
+
+#[derive(Encode)]
+struct NoRepr {
+    a: u8,
+}
+
+#[repr(C, packed)]
+#[derive(Encode)]
+struct Packed {
+    a: u8,
+}
+
+#[repr(C, align(8))]
+#[derive(Encode)]
+struct Aligned {
+    a: u8,
+}
+
+#[repr(C)]
+#[derive(Encode)]
+#[encoding(unsafe_enum)]
+enum NoIntRepr {
+    A,
+    B,
+}
+
+#[repr(u8)]
+#[derive(Encode)]
+enum WithFields {
+    A,
+    B(u8),
+}
+
+#[repr(u8)]
+#[derive(Encode)]
+enum NotUnsafe {
+    A,
+    B,
+}
+
+#[repr(u8)]
+#[derive(Encode)]
+#[encoding(name = "named", unsafe_enum)]
+enum Named {
+    A,
+}
+
+#[repr(C)]
+#[derive(Encode)]
+#[encoding(unsafe_enum)]
+struct UnsafeStruct {
+    a: u8,
+}
+
+#[repr(transparent)]
+#[derive(Encode)]
+struct NoInnerField(PhantomData<u8>);
+
+#[repr(C)]
+#[derive(Encode)]
+#[encoding(nme = "invalid")]
+struct InvalidAttribute {
+    a: u8,
+}
+
+fn main() {}
@@ -13,29 +13,24 @@ extern_class!(
 extern_methods!(
     unsafe impl MyObject {
         #[method(a:)]
-        fn variadic_class(arg: i32, arg2: ...);
+        fn variadic_unnamed(&self, arg: i32, ...);
     }
 );
 
 extern_methods!(
     unsafe impl MyObject {
-        #[method(a:)]
-        fn variadic_instance(&self, arg: i32, ...);
-    }
-);
-
-extern_methods!(
-    unsafe impl MyObject {
-        #[method_id(a:)]
-        fn variadic_id(arg: i32, arg2: ...) -> Id<NSObject>;
+        #[method(a:_)]
+        fn variadic_error(arg2: ...) -> Result<(), Id<NSObject>>;
     }
 );
 
 extern_methods!(
     unsafe impl MyObject {
-        #[method(a:_)]
-        fn variadic_error(arg2: ...) -> Result<(), Id<NSObject>>;
+        #[method(a:)]
+        fn variadic_class(arg: i32, args: ...);
     }
 );
 
-fn main() {}
+fn main() {
+    MyObject::variadic_class(1, (1i128,));
+}
@@ -1,3 +1,4 @@
+#include <stdarg.h>
 #include <Foundation/NSObject.h>
 #include <Foundation/NSValue.h>
 
@@ -30,6 +31,7 @@
 
 + (instancetype) getAutoreleasedInstance;
 + (int) add: (int) a and: (int) b;
++ (double) sumOfInts: (int) ints doubles: (int) doubles, ...;
 
 - (int) var1;
 - (void) addToVar1: (int) number;
@@ -60,6 +62,20 @@
     return a + b;
 }
 
++ (double) sumOfInts: (int) ints doubles: (int) doubles, ... {
+    va_list args;
+    va_start(args, doubles);
+    double sum = 0;
+    for (int i = 0; i < ints; i++) {
+        sum += va_arg(args, int);
+    }
+    for (int i = 0; i < doubles; i++) {
+        sum += va_arg(args, double);
+    }
+    va_end(args);
+    return sum;
+}
+
 - (int) var1 {
     return var1;
 }
@@ -104,10 +104,15 @@ fn raise_catch() {
     let obj = res.unwrap_err().unwrap();
 
     assert_eq!(format!("{obj}"), "def");
+    // The rest of the output is the backtrace
     assert_eq!(
-        format!("{obj:?}"),
+        format!("{obj:?}").lines().next().unwrap(),
         format!("exception <NSException: {:p}> 'abc' reason:def", &*obj)
     );
+    if cfg!(feature = "apple") {
+        let backtrace = obj.backtrace().unwrap();
+        assert!(!backtrace.addresses().is_empty());
+    }
 }
 
 #[test]
@@ -133,7 +138,7 @@ fn catch_actual() {
 
     assert_eq!(format!("{}", exc), reason);
     assert_eq!(
-        format!("{:?}", exc),
+        format!("{:?}", exc).lines().next().unwrap(),
         format!(
             "exception <NSException: {:p}> '{}' reason:{}",
             &*exc, name, reason
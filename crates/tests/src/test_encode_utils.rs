@@ -302,3 +302,70 @@ assert_types! {
     // SIGNED_INT_128 => i128,
     // UNSIGNED_INT_128 => u128,
 }
+
+/// Check that `#[derive(Encode)]` matches the encodings emitted by the
+/// compiler.
+#[allow(dead_code)]
+mod derive {
+    use super::*;
+    use objc2::encode::{Encode, RefEncode};
+
+    #[repr(C)]
+    #[derive(Encode)]
+    #[encoding(name = "empty")]
+    struct Empty {}
+
+    #[repr(C)]
+    #[derive(Encode, RefEncode)]
+    #[encoding(name = "one_item")]
+    struct OneItem {
+        a: *const c_void,
+    }
+
+    #[repr(C)]
+    #[derive(Encode)]
+    #[encoding(name = "nested")]
+    struct Nested {
+        a: OneItem,
+        b: *const OneItem,
+    }
+
+    #[repr(C)]
+    #[derive(Encode)]
+    #[encoding(name = "two_items")]
+    struct TwoItems(f32, c_int);
+
+    #[repr(C)]
+    #[derive(Encode)]
+    #[encoding(name = "with_arrays")]
+    struct WithArrays {
+        a: [c_int; 1],
+        b: [*const c_int; 2],
+        c: *const [c_int; 3],
+    }
+
+    #[repr(C)]
+    #[derive(Encode)]
+    #[encoding(name = "union_")]
+    union Union {
+        a: f32,
+        b: c_int,
+    }
+
+    #[repr(i32)]
+    #[derive(Encode)]
+    #[encoding(unsafe_enum)]
+    enum Enum {
+        A,
+        B,
+    }
+
+    assert_inner!(enc ENCODING_INT => Enum::ENCODING);
+    assert_inner!(enc ENCODING_STRUCT_EMPTY => Empty::ENCODING);
+    assert_inner!(enc ENCODING_STRUCT_ONE_ITEM => OneItem::ENCODING);
+    assert_inner!(enc ENCODING_STRUCT_ONE_ITEM_POINTER => OneItem::ENCODING_REF);
+    assert_inner!(enc ENCODING_STRUCT_NESTED => Nested::ENCODING);
+    assert_inner!(enc ENCODING_STRUCT_TWO_ITEMS => TwoItems::ENCODING);
+    assert_inner!(enc ENCODING_STRUCT_WITH_ARRAYS => WithArrays::ENCODING);
+    assert_inner!(enc ENCODING_UNION => Union::ENCODING);
+}
@@ -8,7 +8,9 @@ use objc2::rc::{autoreleasepool, AutoreleasePool, Id, Owned};
 use objc2::runtime::{Bool, Class, NSObject, NSObjectProtocol, Object, Protocol, ProtocolObject};
 #[cfg(feature = "malloc")]
 use objc2::sel;
-use objc2::{class, extern_protocol, msg_send, msg_send_id, ClassType, Message, ProtocolType};
+use objc2::{
+    class, extern_methods, extern_protocol, msg_send, msg_send_id, ClassType, Message, ProtocolType,
+};
 
 extern_protocol!(
     unsafe trait MyTestProtocol: NSObjectProtocol {
@@ -101,6 +103,13 @@ unsafe impl ClassType for MyTestObject {
     }
 }
 
+extern_methods!(
+    unsafe impl MyTestObject {
+        #[method(sumOfInts:doubles:)]
+        fn sum(ints: c_int, doubles: c_int, args: ...) -> f64;
+    }
+);
+
 unsafe impl NSObjectProtocol for MyTestObject {}
 unsafe impl MyTestProtocol for MyTestObject {}
 
@@ -258,6 +267,18 @@ fn test_class() {
     }
 }
 
+#[test]
+fn test_variadic() {
+    let cls = MyTestObject::class();
+    let sum: f64 =
+        unsafe { msg_send![cls, sumOfInts: 3, doubles: 2, ...(1i8, -2i16, 3u8, 0.5f32, 0.25f64)] };
+    assert_eq!(sum, 2.75);
+
+    assert_eq!(MyTestObject::sum(2, 0, (4, 5)), 9.0);
+    assert_eq!(MyTestObject::sum(0, 1, (1.5,)), 1.5);
+    assert_eq!(MyTestObject::sum(0, 0, ()), 0.0);
+}
+
 #[test]
 fn test_object() {
     autoreleasepool(|pool| {